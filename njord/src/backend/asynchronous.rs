@@ -0,0 +1,376 @@
+use crate::dialect::Dialect;
+use crate::error::Result;
+use crate::logger;
+use crate::sensitive::Sensitive;
+use crate::statement::insert_statement;
+use crate::table::Table;
+use crate::trace;
+use crate::value::Value;
+
+use std::future::Future;
+
+use log::{info, warn};
+
+use super::ddl::{create_table_statements, drop_table_statements};
+use super::insert::{insert_values, rows_per_statement, set_keys};
+use super::{check_writable, Row};
+
+/// The asynchronous connection the builders of the libsql and mssql modules run on, as
+/// `Executor` is for the synchronous ones.
+///
+/// The builders generate their statements before their futures are polled, so a future
+/// only holds the connection and the generated statements, and can be sent across threads.
+pub(crate) trait AsyncExecutor: Send {
+    /// The SQL syntax of the database.
+    const DIALECT: &'static dyn Dialect;
+
+    /// The statements beginning, committing and rolling back the transaction of `insert_many`.
+    const TRANSACTION: [&'static str; 3] = ["BEGIN", "COMMIT", "ROLLBACK"];
+
+    /// Executes a statement with the values bound to its placeholders, and returns the
+    /// number of changed rows.
+    fn execute(
+        &mut self,
+        statement: &str,
+        params: Vec<Value>,
+    ) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Executes a query with the values bound to its placeholders, and returns its rows.
+    fn query(
+        &mut self,
+        statement: &str,
+        params: Vec<Value>,
+    ) -> impl Future<Output = Result<Vec<Row>>> + Send;
+
+    /// Executes a statement without parameters, e.g. one of `TRANSACTION`.
+    fn execute_batch(&mut self, statement: &str) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Executes a generated query with `?` placeholders and returns its rows.
+pub(crate) async fn fetch_rows<X: AsyncExecutor>(
+    exec: &mut X,
+    query: &str,
+    params: Vec<Value>,
+    sensitive: &Sensitive,
+) -> Result<Vec<Row>> {
+    let query = X::DIALECT.bind_placeholders(query);
+
+    // the parameters are moved into the executor, so the logged ones are a copy
+    let logged_params = sensitive.mask(&params).into_owned();
+
+    logger::run_async(&query, &logged_params, |sql| async move {
+        exec.query(&sql, params).await
+    })
+    .await
+}
+
+/// Executes a generated statement with `?` placeholders and returns the number of changed rows.
+pub(crate) async fn execute_statement<X: AsyncExecutor>(
+    exec: &mut X,
+    statement: &str,
+    params: Vec<Value>,
+    sensitive: &Sensitive,
+) -> Result<u64> {
+    let statement = X::DIALECT.bind_placeholders(statement);
+
+    let logged_params = sensitive.mask(&params).into_owned();
+
+    logger::run_async(&statement, &logged_params, |sql| async move {
+        exec.execute(&sql, params).await
+    })
+    .await
+}
+
+/// Executes the INSERT of the rows, writing the auto-increment keys back into the rows.
+pub(crate) async fn insert_rows<X: AsyncExecutor, T: Table + Send>(
+    exec: &mut X,
+    statement: String,
+    table_rows: &mut [T],
+) -> Result<u64> {
+    let (values, key) = insert_values(X::DIALECT, &statement, table_rows)?;
+    let sensitive = Sensitive::rows(table_rows.iter());
+
+    match key {
+        Some((column, statement)) => {
+            let rows = fetch_rows(exec, &statement, values, &sensitive).await?;
+
+            set_keys(table_rows, &column, rows)
+        }
+        None => execute_statement(exec, &statement, values, &sensitive).await,
+    }
+}
+
+/// Inserts many rows of the same table within a single transaction, writing the
+/// auto-increment primary keys assigned by the database back into the rows.
+pub(crate) async fn insert_many<X: AsyncExecutor, T: Table + Send>(
+    exec: &mut X,
+    table_rows: &mut [T],
+) -> Result<u64> {
+    let rows_per_statement = match table_rows.first() {
+        Some(first_row) => {
+            check_writable(first_row)?;
+
+            rows_per_statement(X::DIALECT, first_row)
+        }
+        None => return Ok(0),
+    };
+
+    let [begin, commit, rollback] = X::TRANSACTION;
+
+    let count = trace::transaction_async(async {
+        // create a transaction
+        exec.execute_batch(begin).await?;
+
+        let mut result = insert_chunks(exec, table_rows, rows_per_statement).await;
+
+        if result.is_ok() {
+            // commit the transaction
+            if let Err(error) = exec.execute_batch(commit).await {
+                result = Err(error);
+            }
+        }
+
+        // the error of the transaction is returned instead of an error of its rollback
+        if result.is_err() {
+            if let Err(error) = exec.execute_batch(rollback).await {
+                warn!("Failed to roll back the transaction: {}", error);
+            }
+        }
+
+        result
+    })
+    .await?;
+
+    info!("Inserted {} rows into table, done.", count);
+
+    Ok(count)
+}
+
+async fn insert_chunks<X: AsyncExecutor, T: Table + Send>(
+    exec: &mut X,
+    table_rows: &mut [T],
+    rows_per_statement: usize,
+) -> Result<u64> {
+    let mut count = 0;
+    for chunk in table_rows.chunks_mut(rows_per_statement) {
+        let statement = insert_statement(X::DIALECT, &chunk[0], chunk.len());
+
+        count += insert_rows(exec, statement, chunk).await?;
+    }
+
+    Ok(count)
+}
+
+/// Creates the table of `T` as `ddl::create_table` does, unless it already exists with
+/// `if_not_exists`.
+///
+/// The statements are generated before the first await, so the future does not hold `T`.
+pub(crate) async fn create_table<X: AsyncExecutor, T: Table + Default>(
+    exec: &mut X,
+    if_not_exists: bool,
+) -> Result<()> {
+    let (table_name, statements) = {
+        let table = T::default();
+        let statements = create_table_statements(X::DIALECT, &table, if_not_exists);
+
+        (table.get_name().to_string(), statements)
+    };
+
+    for statement in statements {
+        execute_ddl(exec, &statement).await?;
+    }
+
+    info!("Created table {}, done.", table_name);
+
+    Ok(())
+}
+
+/// Creates the indexes of `T` as `ddl::create_indexes` does.
+pub(crate) async fn create_indexes<X: AsyncExecutor, T: Table + Default>(
+    exec: &mut X,
+) -> Result<()> {
+    let (table_name, indexes) = {
+        let table = T::default();
+
+        (table.get_name().to_string(), table.get_indexes())
+    };
+
+    for index in indexes {
+        if let Some((query, params)) = X::DIALECT.index_exists(&table_name, &index.name) {
+            if !fetch_rows(exec, &query, params, &Sensitive::default())
+                .await?
+                .is_empty()
+            {
+                continue;
+            }
+        }
+
+        execute_ddl(exec, &X::DIALECT.create_index(&table_name, &index)).await?;
+    }
+
+    Ok(())
+}
+
+/// Drops the table of `T` as `ddl::drop_table` does, unless it does not exist with
+/// `if_exists`.
+pub(crate) async fn drop_table<X: AsyncExecutor, T: Table + Default>(
+    exec: &mut X,
+    if_exists: bool,
+) -> Result<()> {
+    let (table_name, statements) = {
+        let table = T::default();
+        let statements = drop_table_statements(X::DIALECT, &table, if_exists);
+
+        (table.get_name().to_string(), statements)
+    };
+
+    for statement in statements {
+        execute_ddl(exec, &statement).await?;
+    }
+
+    info!("Dropped table {}, done.", table_name);
+
+    Ok(())
+}
+
+/// Executes a generated DDL statement, which has no parameters.
+async fn execute_ddl<X: AsyncExecutor>(exec: &mut X, statement: &str) -> Result<()> {
+    logger::run_async(statement, &[], |sql| async move {
+        exec.execute_batch(&sql).await
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Implements the asynchronous `build` of the builders of the `backend` module for a
+/// connection implementing `AsyncExecutor`.
+macro_rules! impl_async_builders {
+    ($conn:ty) => {
+        impl<'a> $crate::backend::QueryBuilder<'a, $conn> {
+            /// Executes the query and maps its rows into instances of the struct.
+            ///
+            /// The SQL is generated before the returned future is polled, so the future only
+            /// holds the connection and can be sent across threads.
+            pub fn build<T: $crate::table::Table + Default + Send>(
+                self,
+            ) -> impl std::future::Future<Output = $crate::Result<Vec<T>>> + Send + 'a {
+                let (query, params) = self.to_sql();
+                let sensitive = self.sensitive();
+                let exec = self.into_exec();
+
+                async move {
+                    let rows = $crate::backend::asynchronous::fetch_rows(
+                        exec?, &query, params, &sensitive,
+                    )
+                    .await?;
+
+                    $crate::backend::into_instances(rows)
+                }
+            }
+
+            /// Executes the query and returns the first row, if any.
+            pub fn first<T: $crate::table::Table + Default + Send>(
+                self,
+            ) -> impl std::future::Future<Output = $crate::Result<Option<T>>> + Send + 'a {
+                let rows = self.limit_first().build::<T>();
+
+                async move { Ok(rows.await?.into_iter().next()) }
+            }
+
+            /// Executes the query and returns the number of rows it has.
+            pub fn count(
+                mut self,
+            ) -> impl std::future::Future<Output = $crate::Result<u64>> + Send + 'a {
+                let (query, params) = self.count_sql();
+                let sensitive = self.sensitive();
+                let exec = self.into_exec();
+
+                async move {
+                    let rows = $crate::backend::asynchronous::fetch_rows(
+                        exec?, &query, params, &sensitive,
+                    )
+                    .await?;
+
+                    Ok($crate::backend::query::row_count(&rows))
+                }
+            }
+        }
+
+        impl<'a, T: $crate::table::Table + Send> $crate::backend::InsertBuilder<'a, $conn, T> {
+            /// Inserts the row and returns the number of inserted rows.
+            pub fn build(
+                self,
+            ) -> impl std::future::Future<Output = $crate::Result<u64>> + Send + 'a {
+                let (exec, table_row, statement) = self
+                    .into_parts(<$conn as $crate::backend::asynchronous::AsyncExecutor>::DIALECT);
+                let checked = $crate::backend::check_writable(&*table_row);
+
+                async move {
+                    checked?;
+
+                    let count = $crate::backend::asynchronous::insert_rows(
+                        exec,
+                        statement,
+                        std::slice::from_mut(table_row),
+                    )
+                    .await?;
+
+                    log::info!("Inserted into table, done.");
+
+                    Ok(count)
+                }
+            }
+        }
+
+        impl<'a> $crate::backend::UpdateBuilder<'a, $conn> {
+            /// Updates the rows and returns the number of updated rows.
+            pub fn build(
+                self,
+            ) -> impl std::future::Future<Output = $crate::Result<u64>> + Send + 'a {
+                let sql =
+                    self.to_sql(<$conn as $crate::backend::asynchronous::AsyncExecutor>::DIALECT);
+                let sensitive = self.sensitive();
+                let exec = self.into_exec();
+
+                async move {
+                    let (query, params) = sql?;
+                    let count = $crate::backend::asynchronous::execute_statement(
+                        exec, &query, params, &sensitive,
+                    )
+                    .await?;
+
+                    log::info!("Updated {} rows in table, done.", count);
+
+                    Ok(count)
+                }
+            }
+        }
+
+        impl<'a> $crate::backend::DeleteBuilder<'a, $conn> {
+            /// Deletes the rows and returns the number of deleted rows.
+            pub fn build(
+                self,
+            ) -> impl std::future::Future<Output = $crate::Result<u64>> + Send + 'a {
+                let sql =
+                    self.to_sql(<$conn as $crate::backend::asynchronous::AsyncExecutor>::DIALECT);
+                let sensitive = self.sensitive();
+                let exec = self.into_exec();
+
+                async move {
+                    let (query, params) = sql?;
+                    let count = $crate::backend::asynchronous::execute_statement(
+                        exec, &query, params, &sensitive,
+                    )
+                    .await?;
+
+                    log::info!("Deleted {} rows from table, done.", count);
+
+                    Ok(count)
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use impl_async_builders;
@@ -0,0 +1,243 @@
+use crate::dialect::Dialect;
+use crate::error::Result;
+use crate::logger;
+use crate::sensitive::Sensitive;
+use crate::table::{ColumnSchema, Table};
+
+use log::info;
+
+use super::{fetch_rows, Executor};
+
+/// Creates the table of `T` from the schema of its columns, failing if it already exists.
+///
+/// The columns are declared in the same order as `Table::get_column_fields`, with their
+/// primary key, NOT NULL, UNIQUE, DEFAULT, CHECK and REFERENCES constraints. Their sqlite
+/// types are mapped to those of the dialect with `Dialect::column_type`, e.g. INTEGER to
+/// BIGINT.
+pub fn create_table<X: Executor, T: Table + Default>(exec: &mut X) -> Result<()> {
+    execute_create_table(exec, &T::default(), false)
+}
+
+/// Creates the table of `T` from the schema of its columns, unless it already exists.
+///
+/// An existing table is left as it is, even if its columns differ from `T`.
+pub fn create_table_if_not_exists<X: Executor, T: Table + Default>(exec: &mut X) -> Result<()> {
+    execute_create_table(exec, &T::default(), true)
+}
+
+fn execute_create_table<X: Executor>(
+    exec: &mut X,
+    table: &dyn Table,
+    if_not_exists: bool,
+) -> Result<()> {
+    for statement in create_table_statements(exec.dialect(), table, if_not_exists) {
+        execute_statement(exec, &statement)?;
+    }
+
+    info!("Created table {}, done.", table.get_name());
+
+    Ok(())
+}
+
+/// Generates the statements creating a table, i.e. its CREATE TABLE statement, preceded
+/// by the CREATE SEQUENCE of its auto-increment key for a dialect with `sequence_name`.
+pub(crate) fn create_table_statements(
+    dialect: &dyn Dialect,
+    table: &dyn Table,
+    if_not_exists: bool,
+) -> Vec<String> {
+    // the sequence outlives a table dropped by other means, so it is only created if it
+    // does not exist yet
+    let sequence = table
+        .get_auto_increment_column()
+        .and_then(|column| dialect.sequence_name(table.get_name(), &column))
+        .map(|sequence| format!("CREATE SEQUENCE IF NOT EXISTS {}", sequence));
+
+    sequence
+        .into_iter()
+        .chain([create_table_statement(
+            dialect,
+            table,
+            table.get_name(),
+            if_not_exists,
+        )])
+        .collect()
+}
+
+/// Generates the CREATE TABLE statement of a table from the schema of its columns,
+/// creating it with the given name.
+pub(crate) fn create_table_statement(
+    dialect: &dyn Dialect,
+    table: &dyn Table,
+    table_name: &str,
+    if_not_exists: bool,
+) -> String {
+    let schema = table.get_schema();
+
+    // a composite primary key is declared as a table constraint instead of on its columns
+    let primary_key_columns: Vec<String> = schema
+        .iter()
+        .filter(|column| column.primary_key)
+        .map(|column| dialect.quote_identifier(&column.name))
+        .collect();
+    let composite_primary_key = primary_key_columns.len() > 1;
+
+    let mut definitions: Vec<String> = schema
+        .iter()
+        .map(|column| column_definition(dialect, table, column, !composite_primary_key))
+        .collect();
+
+    if composite_primary_key {
+        definitions.push(format!("PRIMARY KEY ({})", primary_key_columns.join(", ")));
+    }
+
+    definitions.extend(
+        table
+            .get_checks()
+            .into_iter()
+            .map(|check| format!("CHECK ({})", check)),
+    );
+
+    dialect.create_table(table_name, &definitions.join(", "), if_not_exists)
+}
+
+/// Generates the definition of a column within a CREATE TABLE statement.
+///
+/// `inline_primary_key` declares the primary key on the column itself, which is only
+/// possible for a primary key of a single column.
+pub(crate) fn column_definition(
+    dialect: &dyn Dialect,
+    table: &dyn Table,
+    column: &ColumnSchema,
+    inline_primary_key: bool,
+) -> String {
+    // the timestamp columns stored as text are filled by the database unless they have
+    // another default, while njord sets them on insert either way
+    let is_timestamp = (Some(&column.name) == table.get_created_at_column().as_ref()
+        || Some(&column.name) == table.get_updated_at_column().as_ref())
+        && column.column_type != "INTEGER";
+
+    let keyed = column.primary_key
+        || column.unique
+        || table
+            .get_indexes()
+            .iter()
+            .any(|index| index.columns.contains(&column.name));
+    let has_default = column.default_value.is_some() || is_timestamp;
+
+    let mut definition = format!(
+        "{} {}",
+        dialect.quote_identifier(&column.name),
+        dialect.column_type(&column.column_type, keyed, has_default)
+    );
+
+    if column.primary_key && inline_primary_key {
+        definition.push_str(&format!(" {}", dialect.primary_key(column.auto_increment)));
+    } else if !column.nullable {
+        definition.push_str(" NOT NULL");
+    }
+
+    if column.unique {
+        definition.push_str(" UNIQUE");
+    }
+
+    let sequence = match column.auto_increment {
+        true => dialect.sequence_name(table.get_name(), &column.name),
+        false => None,
+    };
+
+    let default_expression = match (&column.default_value, sequence) {
+        (Some(default_value), _) => Some(default_value.clone()),
+        (None, Some(sequence)) => {
+            Some(dialect.default_expression(&format!("nextval('{}')", sequence)))
+        }
+        (None, None) if is_timestamp => {
+            Some(dialect.default_expression(dialect.current_timestamp()))
+        }
+        (None, None) => None,
+    };
+
+    if let Some(default_expression) = default_expression {
+        definition.push_str(&format!(" DEFAULT {}", default_expression));
+    }
+
+    if let Some(check) = &column.check {
+        definition.push_str(&format!(" CHECK ({})", check));
+    }
+
+    if let Some(references) = &column.references {
+        definition.push_str(&format!(" REFERENCES {}", references));
+    }
+
+    definition
+}
+
+/// Creates the indexes of `T` declared with `#[njord(index)]` and `#[njord(unique_index)]`,
+/// unless they already exist.
+pub fn create_indexes<X: Executor, T: Table + Default>(exec: &mut X) -> Result<()> {
+    let table = T::default();
+    let dialect = exec.dialect();
+
+    for index in table.get_indexes() {
+        if let Some((query, params)) = dialect.index_exists(table.get_name(), &index.name) {
+            if !fetch_rows(exec, &query, &params, &Sensitive::default())?.is_empty() {
+                continue;
+            }
+        }
+
+        execute_statement(exec, &dialect.create_index(table.get_name(), &index))?;
+    }
+
+    Ok(())
+}
+
+/// Drops the table of `T`, failing if it does not exist, along with the sequence of its
+/// auto-increment key for a dialect with `sequence_name`.
+pub fn drop_table<X: Executor, T: Table + Default>(exec: &mut X) -> Result<()> {
+    execute_drop_table(exec, &T::default(), false)
+}
+
+/// Drops the table of `T`, unless it does not exist.
+pub fn drop_table_if_exists<X: Executor, T: Table + Default>(exec: &mut X) -> Result<()> {
+    execute_drop_table(exec, &T::default(), true)
+}
+
+fn execute_drop_table<X: Executor>(exec: &mut X, table: &dyn Table, if_exists: bool) -> Result<()> {
+    for statement in drop_table_statements(exec.dialect(), table, if_exists) {
+        execute_statement(exec, &statement)?;
+    }
+
+    info!("Dropped table {}, done.", table.get_name());
+
+    Ok(())
+}
+
+/// Generates the DROP TABLE statement of a table, followed by the DROP SEQUENCE of its
+/// auto-increment key, which can only be dropped once no column depends on it.
+pub(crate) fn drop_table_statements(
+    dialect: &dyn Dialect,
+    table: &dyn Table,
+    if_exists: bool,
+) -> Vec<String> {
+    let if_exists_str = match if_exists {
+        true => "IF EXISTS ",
+        false => "",
+    };
+    let statement = format!(
+        "DROP TABLE {}{}",
+        if_exists_str,
+        dialect.quote_identifier(table.get_name())
+    );
+
+    let sequence = table
+        .get_auto_increment_column()
+        .and_then(|column| dialect.sequence_name(table.get_name(), &column))
+        .map(|sequence| format!("DROP SEQUENCE IF EXISTS {}", sequence));
+
+    [statement].into_iter().chain(sequence).collect()
+}
+
+/// Executes a generated DDL statement, which has no parameters.
+fn execute_statement<X: Executor>(exec: &mut X, statement: &str) -> Result<()> {
+    logger::run(statement, &[], |sql| exec.execute_batch(sql))
+}
@@ -0,0 +1,93 @@
+use crate::condition::Condition;
+use crate::dialect::Dialect;
+use crate::error::Result;
+use crate::sensitive::Sensitive;
+use crate::statement::delete_statement;
+use crate::table::Table;
+use crate::value::Value;
+
+use log::info;
+
+use super::{check_writable, execute_statement, Executor};
+
+pub fn delete<X: Executor>(exec: &mut X) -> DeleteBuilder<'_, X> {
+    DeleteBuilder::new(exec)
+}
+
+pub struct DeleteBuilder<'a, X> {
+    exec: &'a mut X,
+    table: Option<&'a dyn Table>,
+    where_condition: Option<Condition>,
+    force: bool,
+}
+
+impl<'a, X: Executor> DeleteBuilder<'a, X> {
+    /// Deletes the rows and returns the number of deleted rows.
+    pub fn build(self) -> Result<u64> {
+        let (query, params) = self.to_sql(self.exec.dialect())?;
+
+        let sensitive = self.sensitive();
+        let count = execute_statement(self.exec, &query, &params, &sensitive)?;
+
+        info!("Deleted {} rows from table, done.", count);
+
+        Ok(count)
+    }
+}
+
+impl<'a, X> DeleteBuilder<'a, X> {
+    pub fn new(exec: &'a mut X) -> Self {
+        DeleteBuilder {
+            exec,
+            table: None,
+            where_condition: None,
+            force: false,
+        }
+    }
+
+    pub fn from(mut self, table: &'a dyn Table) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    pub fn where_clause(mut self, condition: Condition) -> Self {
+        self.where_condition = Some(condition);
+        self
+    }
+
+    /// Removes the rows even if the table soft deletes them.
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+
+    /// Generates the DELETE of the rows, refusing to write to a read-only table.
+    pub(crate) fn to_sql(&self, dialect: &dyn Dialect) -> Result<(String, Vec<Value>)> {
+        if let Some(table) = self.table {
+            check_writable(table)?;
+        }
+
+        Ok(delete_statement(
+            dialect,
+            self.table,
+            self.where_condition.as_ref(),
+            self.force,
+        ))
+    }
+
+    /// Get the values the condition compares the sensitive columns with.
+    pub(crate) fn sensitive(&self) -> Sensitive {
+        let mut sensitive = Sensitive::new(self.table);
+
+        if let Some(condition) = &self.where_condition {
+            sensitive.condition(condition);
+        }
+
+        sensitive
+    }
+
+    #[cfg(any(feature = "libsql", feature = "mssql"))]
+    pub(crate) fn into_exec(self) -> &'a mut X {
+        self.exec
+    }
+}
@@ -0,0 +1,234 @@
+use crate::dialect::Dialect;
+use crate::error::{Error, Result};
+use crate::insert::{get_insert_columns, get_insert_values};
+use crate::sensitive::Sensitive;
+use crate::statement::insert_statement;
+use crate::table::Table;
+use crate::value::Value;
+
+use log::info;
+
+use super::{check_writable, execute_insert, fetch_rows, Executor, Row};
+
+/// Inserts a row. An auto-increment primary key is assigned by the database and
+/// written back into the row.
+pub fn insert<'a, X: Executor, T: Table>(
+    exec: &'a mut X,
+    table_row: &'a mut T,
+) -> InsertBuilder<'a, X, T> {
+    InsertBuilder::new(exec, table_row)
+}
+
+pub struct InsertBuilder<'a, X, T> {
+    exec: &'a mut X,
+    table_row: &'a mut T,
+    conflict_columns: Option<Vec<String>>,
+    update_columns: Vec<String>,
+}
+
+impl<'a, X: Executor, T: Table> InsertBuilder<'a, X, T> {
+    /// Inserts the row and returns the number of inserted rows, which MySQL reports as 2
+    /// for a conflicting row that was updated.
+    pub fn build(self) -> Result<u64> {
+        check_writable(self.table_row)?;
+
+        let statement = self.statement(self.exec.dialect());
+        let count = insert_rows(self.exec, &statement, std::slice::from_mut(self.table_row))?;
+
+        info!("Inserted into table, done.");
+
+        Ok(count)
+    }
+}
+
+impl<'a, X, T: Table> InsertBuilder<'a, X, T> {
+    pub fn new(exec: &'a mut X, table_row: &'a mut T) -> Self {
+        InsertBuilder {
+            exec,
+            table_row,
+            conflict_columns: None,
+            update_columns: Vec::new(),
+        }
+    }
+
+    /// Sets the columns whose unique constraint is checked for conflicts, for an upsert
+    /// with `do_update` or `do_nothing`.
+    ///
+    /// SQL Server has no conflict clause, so its rows are matched on the columns, which
+    /// are required, while MySQL checks every primary key and unique index of the table.
+    pub fn on_conflict(mut self, columns: &[&str]) -> Self {
+        self.conflict_columns = Some(columns.iter().map(|column| column.to_string()).collect());
+        self
+    }
+
+    /// Updates the given columns of the conflicting row with the values of the inserted row.
+    pub fn do_update(mut self, columns: &[&str]) -> Self {
+        self.conflict_columns.get_or_insert_with(Vec::new);
+        self.update_columns = columns.iter().map(|column| column.to_string()).collect();
+        self
+    }
+
+    /// Skips the row if it conflicts with an existing row.
+    pub fn do_nothing(mut self) -> Self {
+        self.conflict_columns.get_or_insert_with(Vec::new);
+        self.update_columns.clear();
+        self
+    }
+
+    /// Generates the INSERT of the row, or its upsert.
+    fn statement(&self, dialect: &dyn Dialect) -> String {
+        let statement = insert_statement(dialect, &*self.table_row, 1);
+
+        match &self.conflict_columns {
+            Some(columns) => dialect.upsert(
+                &statement,
+                columns,
+                &self.update_columns,
+                self.table_row.get_auto_increment_column().as_deref(),
+            ),
+            None => statement,
+        }
+    }
+
+    #[cfg(any(feature = "libsql", feature = "mssql"))]
+    pub(crate) fn into_parts(self, dialect: &dyn Dialect) -> (&'a mut X, &'a mut T, String) {
+        let statement = self.statement(dialect);
+
+        (self.exec, self.table_row, statement)
+    }
+}
+
+/// Inserts many rows of the same table within a single transaction of the executor,
+/// writing the auto-increment primary keys assigned by the database back into the rows.
+pub fn insert_many<X: Executor, T: Table>(exec: &mut X, table_rows: &mut [T]) -> Result<u64> {
+    let rows_per_statement = match table_rows.first() {
+        Some(first_row) => {
+            check_writable(first_row)?;
+
+            rows_per_statement(exec.dialect(), first_row)
+        }
+        None => return Ok(0),
+    };
+
+    let count = exec.transaction(|exec| {
+        let mut count = 0;
+        for chunk in table_rows.chunks_mut(rows_per_statement) {
+            let statement = insert_statement(exec.dialect(), &chunk[0], chunk.len());
+
+            count += insert_rows(exec, &statement, chunk)?;
+        }
+
+        Ok::<u64, Error>(count)
+    })?;
+
+    info!("Inserted {} rows into table, done.", count);
+
+    Ok(count)
+}
+
+/// The number of rows of each statement of `insert_many`, as many as the parameters of
+/// the dialect allow.
+///
+/// The keys of a multi-row statement are only written back if the dialect returns them
+/// in the order of the rows, so the rows are otherwise inserted one at a time.
+pub(crate) fn rows_per_statement<T: Table>(dialect: &dyn Dialect, first_row: &T) -> usize {
+    let column_count = get_insert_columns(first_row).len();
+    let returns_keys = match first_row.get_auto_increment_column() {
+        Some(column) => {
+            let statement = insert_statement(dialect, first_row, 1);
+            dialect.returning(&statement, &column).is_some() && dialect.returns_rows_in_order()
+        }
+        None => true,
+    };
+
+    match (column_count, returns_keys) {
+        // rows without columns are inserted with DEFAULT VALUES, one at a time
+        (0, _) | (_, false) => 1,
+        _ => (dialect.max_parameters() / column_count)
+            .max(1)
+            .min(dialect.max_rows()),
+    }
+}
+
+/// Executes the INSERT of the rows, writing the auto-increment keys back into the rows.
+fn insert_rows<X: Executor, T: Table>(
+    exec: &mut X,
+    statement: &str,
+    table_rows: &mut [T],
+) -> Result<u64> {
+    let (values, key) = insert_values(exec.dialect(), statement, table_rows)?;
+    let sensitive = Sensitive::rows(table_rows.iter());
+
+    match key {
+        Some((column, statement)) => {
+            let rows = fetch_rows(exec, &statement, &values, &sensitive)?;
+
+            set_keys(table_rows, &column, rows)
+        }
+        None => {
+            let (count, id) = execute_insert(exec, statement, &values, &sensitive)?;
+
+            set_inserted_key(table_rows, id)?;
+
+            Ok(count)
+        }
+    }
+}
+
+/// The auto-increment key column of a table, along with the INSERT statement returning it.
+pub(crate) type ReturnedKey = (String, String);
+
+/// Get the values bound to the placeholders of the INSERT of the rows, along with the
+/// auto-increment key of the table and the statement returning it, if the dialect can.
+pub(crate) fn insert_values<T: Table>(
+    dialect: &dyn Dialect,
+    statement: &str,
+    table_rows: &mut [T],
+) -> Result<(Vec<Value>, Option<ReturnedKey>)> {
+    // bind the column values of every row to the statement parameters
+    let values: Vec<Value> = table_rows
+        .iter_mut()
+        .map(|row| get_insert_values(row))
+        .collect::<Result<Vec<_>>>()?
+        .concat();
+
+    let key = table_rows
+        .first()
+        .and_then(|row| row.get_auto_increment_column())
+        .and_then(|column| {
+            let statement = dialect.returning(statement, &column)?;
+            Some((column, statement))
+        });
+
+    Ok((values, key))
+}
+
+/// Writes the keys returned by the INSERT back into the rows, and returns the number of
+/// inserted rows.
+pub(crate) fn set_keys<T: Table>(
+    table_rows: &mut [T],
+    column: &str,
+    rows: Vec<Row>,
+) -> Result<u64> {
+    let count = rows.len() as u64;
+
+    for (row, table_row) in rows.into_iter().zip(table_rows.iter_mut()) {
+        if let Some((_, id)) = row.into_iter().next() {
+            table_row.set_column_value(column, id)?;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Writes the key reported by `Executor::execute_insert` back into the only row of the
+/// INSERT.
+pub(crate) fn set_inserted_key<T: Table>(table_rows: &mut [T], id: Option<Value>) -> Result<()> {
+    if let ([table_row], Some(id)) = (table_rows, id) {
+        if let Some(column) = table_row.get_auto_increment_column() {
+            table_row.set_column_value(&column, id)?;
+        }
+    }
+
+    Ok(())
+}
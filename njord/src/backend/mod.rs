@@ -0,0 +1,221 @@
+use crate::dialect::Dialect;
+use crate::error::{Error, Result};
+use crate::logger;
+use crate::sensitive::Sensitive;
+use crate::table::Table;
+use crate::trace;
+use crate::value::Value;
+
+use log::warn;
+
+#[cfg(any(feature = "libsql", feature = "mssql"))]
+pub(crate) mod asynchronous;
+pub mod ddl;
+pub use ddl::{
+    create_indexes, create_table, create_table_if_not_exists, drop_table, drop_table_if_exists,
+};
+pub mod delete;
+pub use delete::{delete, DeleteBuilder};
+pub mod insert;
+pub use insert::{insert, insert_many, InsertBuilder};
+pub mod query;
+pub use query::{select, subquery, QueryBuilder};
+pub mod update;
+pub use update::{update, UpdateBuilder};
+
+pub use crate::condition::Condition;
+pub use crate::join::JoinType;
+
+/// A row of a query, as the name and value of each of its columns.
+pub type Row = Vec<(String, Value)>;
+
+/// The statements of `Executor::transaction` on a connection that is already within a
+/// transaction, which nest with a savepoint instead. A savepoint outlives its rollback,
+/// so it is released afterwards.
+#[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
+pub(crate) const SAVEPOINT_STATEMENTS: [&str; 3] = [
+    "SAVEPOINT njord",
+    "RELEASE SAVEPOINT njord",
+    "ROLLBACK TO SAVEPOINT njord; RELEASE SAVEPOINT njord",
+];
+
+/// The connection the builders of this module run on, e.g. an in-house proxy in front of
+/// a database, without a module of its own.
+///
+/// The builders generate the SQL of the dialect of the target, so an implementation only
+/// passes statements and values through. The builders and DDL functions of the postgres,
+/// mysql and duckdb modules are those of this module on the connections of those modules,
+/// which implement it as the sqlite connection does, so code written against it runs on
+/// them as well.
+///
+/// An error of the storage target is returned as `Error::Execution`, or as
+/// `Error::Constraint` for a statement rejected by a constraint of the table.
+pub trait Executor {
+    /// A statement prepared by `prepare`, e.g. the SQL itself for a target that does not
+    /// prepare statements.
+    type Statement;
+
+    /// The SQL syntax of the storage target.
+    fn dialect(&self) -> &'static dyn Dialect;
+
+    /// Prepares a generated statement, whose placeholders are already those of the dialect.
+    fn prepare(&mut self, statement: &str) -> Result<Self::Statement>;
+
+    /// Executes a prepared statement with the values bound to its placeholders, and
+    /// returns the number of changed rows.
+    fn execute(&mut self, statement: &Self::Statement, params: &[Value]) -> Result<u64>;
+
+    /// Executes a prepared query with the values bound to its placeholders, and returns
+    /// its rows.
+    fn query(&mut self, statement: &Self::Statement, params: &[Value]) -> Result<Vec<Row>>;
+
+    /// Executes a prepared INSERT like `execute`, along with the auto-increment key it
+    /// generated, for dialects whose `returning` cannot return it, e.g. MySQL.
+    fn execute_insert(
+        &mut self,
+        statement: &Self::Statement,
+        params: &[Value],
+    ) -> Result<(u64, Option<Value>)> {
+        Ok((self.execute(statement, params)?, None))
+    }
+
+    /// Executes statements without parameters, e.g. those of `transaction_statements`,
+    /// separated by semicolons, which are split apart and executed one at a time unless
+    /// the target runs them at once.
+    fn execute_batch(&mut self, statements: &str) -> Result<()> {
+        for statement in statements.split(';').map(str::trim) {
+            if !statement.is_empty() {
+                let prepared = self.prepare(statement)?;
+                self.execute(&prepared, &[])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The statements beginning, committing and rolling back the transactions of
+    /// `transaction`.
+    fn transaction_statements(&self) -> [&'static str; 3] {
+        ["BEGIN", "COMMIT", "ROLLBACK"]
+    }
+
+    /// Runs `f` within a transaction, committing it if `f` succeeds and rolling it back
+    /// otherwise, or if it fails to commit.
+    fn transaction<R, E, F>(&mut self, f: F) -> std::result::Result<R, E>
+    where
+        Self: Sized,
+        E: From<Error>,
+        F: FnOnce(&mut Self) -> std::result::Result<R, E>,
+    {
+        let [begin, commit, rollback] = self.transaction_statements();
+
+        trace::transaction(|| {
+            // create a transaction
+            self.execute_batch(begin)?;
+
+            let result = f(self).and_then(|result| {
+                // commit the transaction
+                self.execute_batch(commit)?;
+
+                Ok(result)
+            });
+
+            // the error of the transaction is returned instead of an error of its rollback
+            if result.is_err() {
+                if let Err(error) = self.execute_batch(rollback) {
+                    warn!("Failed to roll back the transaction: {}", error);
+                }
+            }
+
+            result
+        })
+    }
+}
+
+/// Refuses to write to the table of a `#[njord(readonly)]` struct.
+pub(crate) fn check_writable(table_row: &dyn Table) -> Result<()> {
+    if table_row.is_readonly() {
+        return Err(Error::Generation(format!(
+            "table {} is read-only",
+            table_row.get_name()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Maps each row of a query into an instance of the struct, with the result columns
+/// mapped to the fields with the same name.
+pub(crate) fn into_instances<T: Table + Default>(rows: Vec<Row>) -> Result<Vec<T>> {
+    rows.into_iter()
+        .map(|row| {
+            let mut instance = T::default();
+            for (column, value) in row {
+                instance.set_column_value(&column, value)?;
+            }
+            Ok(instance)
+        })
+        .collect()
+}
+
+/// Executes a generated query with `?` placeholders and maps each of its rows into an
+/// instance of the struct.
+pub(crate) fn query_rows<T: Table + Default, X: Executor>(
+    exec: &mut X,
+    query: &str,
+    params: &[Value],
+    sensitive: &Sensitive,
+) -> Result<Vec<T>> {
+    let rows = fetch_rows(exec, query, params, sensitive)?;
+
+    into_instances(rows)
+}
+
+/// Executes a generated query with `?` placeholders and returns its rows.
+pub(crate) fn fetch_rows<X: Executor>(
+    exec: &mut X,
+    query: &str,
+    params: &[Value],
+    sensitive: &Sensitive,
+) -> Result<Vec<Row>> {
+    let query = exec.dialect().bind_placeholders(query);
+
+    logger::run(&query, &sensitive.mask(params), |sql| {
+        let statement = trace::prepare(sql, || exec.prepare(sql))?;
+
+        exec.query(&statement, params)
+    })
+}
+
+/// Executes a generated statement with `?` placeholders and returns the number of changed rows.
+pub(crate) fn execute_statement<X: Executor>(
+    exec: &mut X,
+    statement: &str,
+    params: &[Value],
+    sensitive: &Sensitive,
+) -> Result<u64> {
+    let statement = exec.dialect().bind_placeholders(statement);
+
+    logger::run(&statement, &sensitive.mask(params), |sql| {
+        let prepared = trace::prepare(sql, || exec.prepare(sql))?;
+
+        exec.execute(&prepared, params)
+    })
+}
+
+/// Executes a generated INSERT with `?` placeholders and returns the number of inserted
+/// rows along with the auto-increment key reported by `Executor::execute_insert`.
+pub(crate) fn execute_insert<X: Executor>(
+    exec: &mut X,
+    statement: &str,
+    params: &[Value],
+    sensitive: &Sensitive,
+) -> Result<(u64, Option<Value>)> {
+    let statement = exec.dialect().bind_placeholders(statement);
+
+    logger::run(&statement, &sensitive.mask(params), |sql| {
+        let prepared = trace::prepare(sql, || exec.prepare(sql))?;
+
+        exec.execute_insert(&prepared, params)
+    })
+}
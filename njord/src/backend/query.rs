@@ -0,0 +1,196 @@
+use crate::condition::Condition;
+use crate::condition::Subquery;
+use crate::dialect::Dialect;
+use crate::error::{Error, Result};
+use crate::join::JoinType;
+use crate::sensitive::Sensitive;
+use crate::statement::Select;
+use crate::table::Table;
+use crate::value::Value;
+
+use std::collections::HashMap;
+
+use super::{fetch_rows, query_rows, Executor, Row};
+
+pub fn select<X: Executor>(exec: &mut X, columns: Vec<String>) -> QueryBuilder<'_, X> {
+    QueryBuilder::new(exec, columns)
+}
+
+/// Creates a query without an executor, to be embedded in a condition of another query,
+/// e.g. `Condition::InSubquery("id".to_string(), subquery(&Postgres, columns).from(&table).into())`.
+pub fn subquery<'a, X>(dialect: &'static dyn Dialect, columns: Vec<String>) -> QueryBuilder<'a, X> {
+    QueryBuilder::with_dialect(None, dialect, columns)
+}
+
+/// A query run on an `Executor`.
+///
+/// A query embedded in a condition of another query is converted into a `Subquery` with
+/// `into()`, which releases the executor for the other query.
+pub struct QueryBuilder<'a, X> {
+    exec: Option<&'a mut X>,
+    dialect: &'static dyn Dialect,
+    select: Select<'a>,
+}
+
+impl<'a, X: Executor> QueryBuilder<'a, X> {
+    pub fn new(exec: &'a mut X, columns: Vec<String>) -> Self {
+        let dialect = exec.dialect();
+
+        Self::with_dialect(Some(exec), dialect, columns)
+    }
+
+    /// Executes the query and maps its rows into instances of the struct.
+    pub fn build<T: Table + Default>(self) -> Result<Vec<T>> {
+        let (query, params) = self.to_sql();
+
+        let sensitive = self.sensitive();
+
+        query_rows::<T, X>(self.into_exec()?, &query, &params, &sensitive)
+    }
+
+    /// Executes the query and returns the first row, if any.
+    pub fn first<T: Table + Default>(self) -> Result<Option<T>> {
+        let rows = self.limit_first().build::<T>()?;
+
+        Ok(rows.into_iter().next())
+    }
+
+    /// Executes the query and returns the number of rows it has.
+    pub fn count(mut self) -> Result<u64> {
+        let (query, params) = self.count_sql();
+        let sensitive = self.sensitive();
+        let rows = fetch_rows(self.into_exec()?, &query, &params, &sensitive)?;
+
+        Ok(row_count(&rows))
+    }
+}
+
+impl<'a, X> QueryBuilder<'a, X> {
+    pub(crate) fn with_dialect(
+        exec: Option<&'a mut X>,
+        dialect: &'static dyn Dialect,
+        columns: Vec<String>,
+    ) -> Self {
+        QueryBuilder {
+            exec,
+            dialect,
+            select: Select::new(columns),
+        }
+    }
+
+    pub fn select(mut self, columns: Vec<String>) -> Self {
+        self.select.columns = columns;
+        self
+    }
+
+    pub fn distinct(mut self) -> Self {
+        self.select.distinct = true;
+        self
+    }
+
+    /// Includes the soft deleted rows of the table, which are left out by default.
+    pub fn with_deleted(mut self) -> Self {
+        self.select.with_deleted = true;
+        self
+    }
+
+    pub fn from(mut self, table: &'a dyn Table) -> Self {
+        self.select.table = Some(table);
+        self
+    }
+
+    pub fn join(mut self, join_type: JoinType, table: &'a dyn Table, on: Condition) -> Self {
+        self.select.joins.push((join_type, table, Some(on)));
+        self
+    }
+
+    pub fn cross_join(mut self, table: &'a dyn Table) -> Self {
+        self.select.joins.push((JoinType::Cross, table, None));
+        self
+    }
+
+    pub fn where_clause(mut self, condition: Condition) -> Self {
+        self.select.where_condition = Some(condition);
+        self
+    }
+
+    pub fn group_by(mut self, columns: Vec<String>) -> Self {
+        self.select.group_by = Some(columns);
+        self
+    }
+
+    pub fn order_by(mut self, col_and_order: HashMap<Vec<String>, String>) -> Self {
+        self.select.order_by = Some(col_and_order);
+        self
+    }
+
+    pub fn limit(mut self, count: usize) -> Self {
+        self.select.limit = Some(count);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.select.offset = Some(offset);
+        self
+    }
+
+    pub fn having(mut self, condition: Condition) -> Self {
+        self.select.having_condition = Some(condition);
+        self
+    }
+
+    /// Get the generated SQL and the values bound to its placeholders, without executing the query.
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        self.select.to_sql(self.dialect)
+    }
+
+    /// Get the values of the sensitive columns the conditions of the query compare with.
+    pub(crate) fn sensitive(&self) -> Sensitive {
+        self.select.sensitive()
+    }
+
+    /// Limits the query to its first row. The query itself is limited, as not every
+    /// database orders a derived table.
+    pub(crate) fn limit_first(mut self) -> Self {
+        self.select.limit = Some(self.select.limit.map_or(1, |count| count.min(1)));
+        self
+    }
+
+    /// Generates the query counting the rows of this query.
+    pub(crate) fn count_sql(&mut self) -> (String, Vec<Value>) {
+        // the order does not change the count, and not every database orders a derived table
+        if self.select.limit.is_none() && self.select.offset.is_none() {
+            self.select.order_by = None;
+        }
+
+        let (query, params) = self.to_sql();
+        let query = format!(
+            "SELECT {} FROM ({}) AS counted_rows",
+            self.dialect.count_all(),
+            query
+        );
+
+        (query, params)
+    }
+
+    pub(crate) fn into_exec(self) -> Result<&'a mut X> {
+        self.exec.ok_or_else(|| {
+            Error::Generation("a subquery can only be embedded in another query".to_string())
+        })
+    }
+}
+
+/// Reads the count of a query generated by `count_sql`.
+pub(crate) fn row_count(rows: &[Row]) -> u64 {
+    match rows.first().and_then(|row| row.first()) {
+        Some((_, Value::Integer(count))) => *count as u64,
+        _ => 0,
+    }
+}
+
+impl<'a, X> From<QueryBuilder<'a, X>> for Subquery {
+    fn from(query: QueryBuilder<'a, X>) -> Self {
+        let (sql, params) = query.to_sql();
+        Subquery::new(sql, params)
+    }
+}
@@ -0,0 +1,132 @@
+use crate::condition::Condition;
+use crate::dialect::Dialect;
+use crate::error::Result;
+use crate::sensitive::Sensitive;
+use crate::statement::{update_statement, Assignment};
+use crate::table::Table;
+use crate::value::Value;
+
+use log::info;
+
+use super::{check_writable, execute_statement, Executor};
+
+pub fn update<X: Executor>(exec: &mut X) -> UpdateBuilder<'_, X> {
+    UpdateBuilder::new(exec)
+}
+
+pub struct UpdateBuilder<'a, X> {
+    exec: &'a mut X,
+    table: Option<&'a dyn Table>,
+    columns: Vec<(String, Assignment)>,
+    where_condition: Option<Condition>,
+}
+
+impl<'a, X: Executor> UpdateBuilder<'a, X> {
+    /// Updates the rows and returns the number of updated rows.
+    pub fn build(self) -> Result<u64> {
+        let (query, params) = self.to_sql(self.exec.dialect())?;
+
+        let sensitive = self.sensitive();
+        let count = execute_statement(self.exec, &query, &params, &sensitive)?;
+
+        info!("Updated {} rows in table, done.", count);
+
+        Ok(count)
+    }
+}
+
+impl<'a, X> UpdateBuilder<'a, X> {
+    pub fn new(exec: &'a mut X) -> Self {
+        UpdateBuilder {
+            exec,
+            table: None,
+            columns: Vec::new(),
+            where_condition: None,
+        }
+    }
+
+    pub fn table(mut self, table: &'a dyn Table) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    /// Sets the column to a value, bound as it is, e.g. `"00501"` as TEXT.
+    pub fn set(mut self, column: String, value: impl Into<Value>) -> Self {
+        self.columns.push((column, Assignment::Value(value.into())));
+        self
+    }
+
+    /// Sets the column to a typed value, e.g. a `Vec<u8>` bound as a BLOB.
+    pub fn set_value<V: Into<Value>>(mut self, column: &str, value: V) -> Self {
+        self.columns
+            .push((column.to_string(), Assignment::Value(value.into())));
+        self
+    }
+
+    /// Sets the column to an SQL expression evaluated by the database, e.g. `counter + 1`.
+    ///
+    /// The expression is inserted into the query as it is, so it must not contain user input.
+    pub fn set_expr(mut self, column: &str, expression: &str) -> Self {
+        self.columns.push((
+            column.to_string(),
+            Assignment::Expression(expression.to_string()),
+        ));
+        self
+    }
+
+    /// Adds the amount to the current value of the column, atomically within the update.
+    pub fn increment(mut self, column: &str, amount: i64) -> Self {
+        self.columns
+            .push((column.to_string(), Assignment::Add(amount)));
+        self
+    }
+
+    /// Subtracts the amount from the current value of the column, atomically within the update.
+    pub fn decrement(mut self, column: &str, amount: i64) -> Self {
+        self.columns
+            .push((column.to_string(), Assignment::Subtract(amount)));
+        self
+    }
+
+    pub fn where_clause(mut self, condition: Condition) -> Self {
+        self.where_condition = Some(condition);
+        self
+    }
+
+    /// Generates the UPDATE of the rows, refusing to write to a read-only table.
+    pub(crate) fn to_sql(&self, dialect: &dyn Dialect) -> Result<(String, Vec<Value>)> {
+        if let Some(table) = self.table {
+            check_writable(table)?;
+        }
+
+        Ok(update_statement(
+            dialect,
+            self.table,
+            &self.columns,
+            self.where_condition.as_ref(),
+        ))
+    }
+
+    /// Get the values assigned to the sensitive columns, and compared with them by the
+    /// condition.
+    pub(crate) fn sensitive(&self) -> Sensitive {
+        let mut sensitive = Sensitive::new(self.table);
+
+        for (column, assignment) in &self.columns {
+            if let Assignment::Value(value) = assignment {
+                sensitive.column(column, value);
+            }
+        }
+
+        if let Some(condition) = &self.where_condition {
+            sensitive.condition(condition);
+        }
+
+        sensitive
+    }
+
+    #[cfg(any(feature = "libsql", feature = "mssql"))]
+    pub(crate) fn into_exec(self) -> &'a mut X {
+        self.exec
+    }
+}
@@ -0,0 +1,353 @@
+#[cfg(any(
+    feature = "chrono",
+    feature = "uuid",
+    feature = "json",
+    feature = "decimal"
+))]
+use crate::value::FromSqlError;
+use crate::value::{FromSqlResult, Value, ValueRef};
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+#[cfg(feature = "json")]
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
+#[cfg(feature = "derive")]
+#[allow(unused_imports)]
+use njord_derive::ColumnEnum;
+
+/// The ToColumn trait.
+///
+/// It is used for custom types, e.g. newtypes like `EmailAddress`, stored in a column.
+/// Fields of such types are marked with `#[njord(custom)]` in a struct deriving `Table`.
+pub trait ToColumn {
+    /// Get the value stored in the column.
+    fn to_column(&self) -> Value;
+
+    /// Get the type of the column, `TEXT` by default.
+    fn column_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "TEXT"
+    }
+}
+
+/// The FromColumn trait.
+///
+/// It is used for custom types parsed back from the value of a selected column.
+pub trait FromColumn: Sized {
+    /// Parse the value of the column.
+    fn from_column(value: ValueRef<'_>) -> FromSqlResult<Self>;
+}
+
+impl<T: ToColumn> ToColumn for Option<T> {
+    fn to_column(&self) -> Value {
+        self.as_ref().map_or(Value::Null, |value| value.to_column())
+    }
+
+    fn column_type() -> &'static str {
+        T::column_type()
+    }
+}
+
+impl<T: FromColumn> FromColumn for Option<T> {
+    fn from_column(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(None),
+            _ => T::from_column(value).map(Some),
+        }
+    }
+}
+
+/// The ColumnEnum trait.
+///
+/// It is used for fieldless enums stored in a column, either as the name of the
+/// variant in a TEXT column or as its discriminant in an INTEGER column.
+pub trait ColumnEnum: Sized {
+    /// Get the name of the variant.
+    fn variant_name(&self) -> &'static str;
+
+    /// Get the variant with the given name.
+    ///
+    /// Returns `None` if the enum has no such variant.
+    fn from_variant_name(name: &str) -> Option<Self>;
+
+    /// Get the discriminant of the variant.
+    fn discriminant(&self) -> i64;
+
+    /// Get the variant with the given discriminant.
+    ///
+    /// Returns `None` if the enum has no such variant.
+    fn from_discriminant(discriminant: i64) -> Option<Self>;
+}
+
+/// Converts an enum field, or an optional one, into the value of its column.
+///
+/// It is used by `derive(Table)` for fields marked with `#[njord(enum = "...")]`.
+pub trait EnumValue {
+    /// Get the name of the variant as TEXT, or NULL for `None`.
+    fn to_text_value(&self) -> Value;
+
+    /// Get the discriminant of the variant as INTEGER, or NULL for `None`.
+    fn to_integer_value(&self) -> Value;
+}
+
+impl<T: ColumnEnum> EnumValue for T {
+    fn to_text_value(&self) -> Value {
+        Value::Text(self.variant_name().to_string())
+    }
+
+    fn to_integer_value(&self) -> Value {
+        Value::Integer(self.discriminant())
+    }
+}
+
+impl<T: ColumnEnum> EnumValue for Option<T> {
+    fn to_text_value(&self) -> Value {
+        self.as_ref()
+            .map_or(Value::Null, |value| value.to_text_value())
+    }
+
+    fn to_integer_value(&self) -> Value {
+        self.as_ref()
+            .map_or(Value::Null, |value| value.to_integer_value())
+    }
+}
+
+/// Converts a date and time field, or an optional one, from and into seconds since the unix epoch.
+///
+/// It is used by `derive(Table)` for fields marked with `#[njord(datetime = "unix")]`,
+/// which are stored in an INTEGER column instead of ISO-8601 TEXT.
+#[cfg(feature = "chrono")]
+pub trait UnixTimestamp: Sized {
+    /// Get the timestamp as INTEGER, or NULL for `None`.
+    fn to_unix_value(&self) -> Value;
+
+    /// Parse the timestamp of a selected column.
+    fn from_unix_value(value: ValueRef<'_>) -> FromSqlResult<Self>;
+}
+
+#[cfg(feature = "chrono")]
+impl UnixTimestamp for DateTime<Utc> {
+    fn to_unix_value(&self) -> Value {
+        Value::Integer(self.timestamp())
+    }
+
+    fn from_unix_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let timestamp = value.as_i64()?;
+        DateTime::from_timestamp(timestamp, 0).ok_or(FromSqlError::OutOfRange(timestamp))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl UnixTimestamp for NaiveDateTime {
+    fn to_unix_value(&self) -> Value {
+        self.and_utc().to_unix_value()
+    }
+
+    fn from_unix_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        DateTime::<Utc>::from_unix_value(value).map(|datetime| datetime.naive_utc())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl UnixTimestamp for NaiveDate {
+    /// Dates are stored as the timestamp of their midnight in UTC.
+    fn to_unix_value(&self) -> Value {
+        self.and_time(NaiveTime::MIN).to_unix_value()
+    }
+
+    fn from_unix_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        DateTime::<Utc>::from_unix_value(value).map(|datetime| datetime.date_naive())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<T: UnixTimestamp> UnixTimestamp for Option<T> {
+    fn to_unix_value(&self) -> Value {
+        self.as_ref()
+            .map_or(Value::Null, |value| value.to_unix_value())
+    }
+
+    fn from_unix_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(None),
+            _ => T::from_unix_value(value).map(Some),
+        }
+    }
+}
+
+/// Converts a UUID field, or an optional one, from and into hyphenated TEXT, and generates it on insert.
+///
+/// It is used by `derive(Table)` for fields marked with `#[njord(uuid = "text")]` or
+/// `#[njord(default = "uuid_v4")]`. Other UUID fields are stored as a 16-byte BLOB.
+#[cfg(feature = "uuid")]
+pub trait UuidColumn: Sized {
+    /// Get the UUID as TEXT, or NULL for `None`.
+    fn to_text_value(&self) -> Value;
+
+    /// Parse the UUID of a selected column, stored either as TEXT or as a BLOB.
+    fn from_text_value(value: ValueRef<'_>) -> FromSqlResult<Self>;
+
+    /// Replace a nil UUID, or `None`, with a random one.
+    fn generate_v4(&mut self);
+}
+
+#[cfg(feature = "uuid")]
+impl UuidColumn for Uuid {
+    fn to_text_value(&self) -> Value {
+        Value::Text(self.hyphenated().to_string())
+    }
+
+    fn from_text_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Text(_) => Uuid::parse_str(value.as_str()?)
+                .map_err(|error| FromSqlError::Other(Box::new(error))),
+            _ => crate::value::from_value(value),
+        }
+    }
+
+    fn generate_v4(&mut self) {
+        if self.is_nil() {
+            *self = Uuid::new_v4();
+        }
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl UuidColumn for Option<Uuid> {
+    fn to_text_value(&self) -> Value {
+        self.as_ref()
+            .map_or(Value::Null, |value| value.to_text_value())
+    }
+
+    fn from_text_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(None),
+            _ => Uuid::from_text_value(value).map(Some),
+        }
+    }
+
+    fn generate_v4(&mut self) {
+        self.get_or_insert_with(Uuid::nil).generate_v4();
+    }
+}
+
+/// Serializes a field into the JSON TEXT of its column.
+///
+/// It is used by `derive(Table)` for fields marked with `#[njord(json)]`.
+/// A field serialized as JSON `null`, e.g. `None`, is stored as NULL.
+#[cfg(feature = "json")]
+pub fn to_json_value<T: Serialize>(value: &T) -> serde_json::Result<Value> {
+    let json = serde_json::to_string(value)?;
+
+    match json.as_str() {
+        "null" => Ok(Value::Null),
+        _ => Ok(Value::Text(json)),
+    }
+}
+
+/// Deserializes a field from the JSON TEXT of a selected column, with NULL read as JSON `null`.
+#[cfg(feature = "json")]
+pub fn from_json_value<T: DeserializeOwned>(value: ValueRef<'_>) -> FromSqlResult<T> {
+    let json = match value {
+        ValueRef::Null => "null",
+        _ => value.as_str()?,
+    };
+
+    serde_json::from_str(json).map_err(|error| FromSqlError::Other(Box::new(error)))
+}
+
+/// Converts a decimal field, or an optional one, from and into TEXT or a scaled INTEGER.
+///
+/// It is used by `derive(Table)` for fields marked with `#[njord(decimal)]`, or with
+/// `#[njord(decimal = 2)]` to store e.g. money amounts as a whole number of cents.
+#[cfg(feature = "decimal")]
+pub trait DecimalColumn: Sized {
+    /// Get the decimal as TEXT, or NULL for `None`.
+    fn to_text_value(&self) -> Value;
+
+    /// Parse the decimal of a selected TEXT or INTEGER column.
+    fn from_text_value(value: ValueRef<'_>) -> FromSqlResult<Self>;
+
+    /// Get the decimal as an INTEGER scaled by 10 to the power of `scale`, or NULL for `None`.
+    ///
+    /// Digits beyond the scale are rounded. Returns `None` if the scaled decimal
+    /// does not fit into an INTEGER.
+    fn to_scaled_value(&self, scale: u32) -> Option<Value>;
+
+    /// Parse the decimal of a selected INTEGER column scaled by 10 to the power of `scale`.
+    fn from_scaled_value(value: ValueRef<'_>, scale: u32) -> FromSqlResult<Self>;
+}
+
+#[cfg(feature = "decimal")]
+impl DecimalColumn for Decimal {
+    fn to_text_value(&self) -> Value {
+        Value::Text(self.to_string())
+    }
+
+    fn from_text_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(integer) => Ok(Decimal::from(integer)),
+            _ => value
+                .as_str()?
+                .parse()
+                .map_err(|error| FromSqlError::Other(Box::new(error))),
+        }
+    }
+
+    fn to_scaled_value(&self, scale: u32) -> Option<Value> {
+        let mut scaled = self.round_dp(scale);
+        scaled.rescale(scale);
+
+        // the rescaled decimal keeps the requested scale only if its digits fit
+        if scaled.scale() != scale {
+            return None;
+        }
+
+        i64::try_from(scaled.mantissa()).ok().map(Value::Integer)
+    }
+
+    fn from_scaled_value(value: ValueRef<'_>, scale: u32) -> FromSqlResult<Self> {
+        let integer = value.as_i64()?;
+        Decimal::try_from_i128_with_scale(integer.into(), scale)
+            .map_err(|error| FromSqlError::Other(Box::new(error)))
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl DecimalColumn for Option<Decimal> {
+    fn to_text_value(&self) -> Value {
+        self.as_ref()
+            .map_or(Value::Null, |value| value.to_text_value())
+    }
+
+    fn from_text_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(None),
+            _ => Decimal::from_text_value(value).map(Some),
+        }
+    }
+
+    fn to_scaled_value(&self, scale: u32) -> Option<Value> {
+        match self {
+            Some(value) => value.to_scaled_value(scale),
+            None => Some(Value::Null),
+        }
+    }
+
+    fn from_scaled_value(value: ValueRef<'_>, scale: u32) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(None),
+            _ => Decimal::from_scaled_value(value, scale).map(Some),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::value::Value;
+
+/// A select query embedded in a condition.
+///
+/// Holds the generated SQL of the query together with the values bound to its placeholders.
+pub struct Subquery {
+    sql: String,
+    params: Vec<Value>,
+}
+
+impl Subquery {
+    pub(crate) fn new(sql: String, params: Vec<Value>) -> Self {
+        Subquery { sql, params }
+    }
+
+    /// Get the SQL of the query.
+    pub fn get_sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Get the values to bind to the placeholders of the query.
+    pub fn get_params(&self) -> Vec<Value> {
+        self.params.clone()
+    }
+}
+
+/// A condition of a WHERE, HAVING or ON clause.
+///
+/// The values compared with are bound to placeholders as they are, e.g. `"00501"` as TEXT,
+/// and are easiest built with `Condition::eq` and its siblings from anything converting
+/// into a `Value`.
+pub enum Condition {
+    Eq(String, Value),
+    Ne(String, Value),
+    Lt(String, Value),
+    Gt(String, Value),
+    Le(String, Value),
+    Ge(String, Value),
+    /// `column LIKE pattern`
+    Like(String, Value),
+    /// `column BETWEEN low AND high`
+    Between(String, Value, Value),
+    /// `column IN (value, ...)`
+    InList(String, Vec<Value>),
+    IsNull(String),
+    IsNotNull(String),
+    /// Compares two columns, e.g. `TableA.id = TableB.a_id` in a join.
+    EqColumn(String, String),
+    /// `column IN (subquery)`
+    InSubquery(String, Subquery),
+    /// `EXISTS (subquery)`
+    Exists(Subquery),
+    /// Compares a column with the scalar result of a subquery, e.g. `price > (SELECT AVG(price) ...)`.
+    /// The second value is the comparison operator.
+    ScalarSubquery(String, String, Subquery),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    /// Combines this condition with another one, both wrapped in parentheses.
+    pub fn and(self, other: Condition) -> Condition {
+        Condition::And(Box::new(self), Box::new(other))
+    }
+
+    /// Combines this condition with another one, both wrapped in parentheses.
+    pub fn or(self, other: Condition) -> Condition {
+        Condition::Or(Box::new(self), Box::new(other))
+    }
+
+    /// `column = value`
+    pub fn eq(column: &str, value: impl Into<Value>) -> Condition {
+        Condition::Eq(column.to_string(), value.into())
+    }
+
+    /// `column <> value`
+    pub fn ne(column: &str, value: impl Into<Value>) -> Condition {
+        Condition::Ne(column.to_string(), value.into())
+    }
+
+    /// `column < value`
+    pub fn lt(column: &str, value: impl Into<Value>) -> Condition {
+        Condition::Lt(column.to_string(), value.into())
+    }
+
+    /// `column > value`
+    pub fn gt(column: &str, value: impl Into<Value>) -> Condition {
+        Condition::Gt(column.to_string(), value.into())
+    }
+
+    /// `column <= value`
+    pub fn le(column: &str, value: impl Into<Value>) -> Condition {
+        Condition::Le(column.to_string(), value.into())
+    }
+
+    /// `column >= value`
+    pub fn ge(column: &str, value: impl Into<Value>) -> Condition {
+        Condition::Ge(column.to_string(), value.into())
+    }
+
+    /// `column LIKE pattern`
+    pub fn like(column: &str, pattern: impl Into<Value>) -> Condition {
+        Condition::Like(column.to_string(), pattern.into())
+    }
+
+    /// `column BETWEEN low AND high`
+    pub fn between(column: &str, low: impl Into<Value>, high: impl Into<Value>) -> Condition {
+        Condition::Between(column.to_string(), low.into(), high.into())
+    }
+
+    /// `column IN (value, ...)`
+    pub fn in_list<V: Into<Value>>(column: &str, values: impl IntoIterator<Item = V>) -> Condition {
+        Condition::InList(
+            column.to_string(),
+            values.into_iter().map(Into::into).collect(),
+        )
+    }
+
+    pub fn in_subquery(column: &str, subquery: impl Into<Subquery>) -> Condition {
+        Condition::InSubquery(column.to_string(), subquery.into())
+    }
+
+    pub fn exists(subquery: impl Into<Subquery>) -> Condition {
+        Condition::Exists(subquery.into())
+    }
+
+    pub fn scalar_subquery(
+        column: &str,
+        operator: &str,
+        subquery: impl Into<Subquery>,
+    ) -> Condition {
+        Condition::ScalarSubquery(column.to_string(), operator.to_string(), subquery.into())
+    }
+
+    /// Builds the SQL for the condition, using `?` placeholders for the values.
+    pub fn build(&self) -> String {
+        match self {
+            Condition::Eq(column, _) => format!("{} = ?", column),
+            Condition::Ne(column, _) => format!("{} <> ?", column),
+            Condition::Lt(column, _) => format!("{} < ?", column),
+            Condition::Gt(column, _) => format!("{} > ?", column),
+            Condition::Le(column, _) => format!("{} <= ?", column),
+            Condition::Ge(column, _) => format!("{} >= ?", column),
+            Condition::Like(column, _) => format!("{} LIKE ?", column),
+            Condition::Between(column, _, _) => format!("{} BETWEEN ? AND ?", column),
+            Condition::InList(column, values) => {
+                let placeholders: Vec<&str> = values.iter().map(|_| "?").collect();
+                format!("{} IN ({})", column, placeholders.join(", "))
+            }
+            Condition::IsNull(column) => format!("{} IS NULL", column),
+            Condition::IsNotNull(column) => format!("{} IS NOT NULL", column),
+            Condition::EqColumn(left, right) => format!("{} = {}", left, right),
+            Condition::InSubquery(column, subquery) => format!("{} IN ({})", column, subquery.sql),
+            Condition::Exists(subquery) => format!("EXISTS ({})", subquery.sql),
+            Condition::ScalarSubquery(column, operator, subquery) => {
+                format!("{} {} ({})", column, operator, subquery.sql)
+            }
+            Condition::And(left, right) => format!("({}) AND ({})", left.build(), right.build()),
+            Condition::Or(left, right) => format!("({}) OR ({})", left.build(), right.build()),
+            Condition::Not(condition) => format!("NOT ({})", condition.build()),
+        }
+    }
+
+    /// Get the values to bind to the placeholders, in the same order
+    /// as they appear in the SQL returned by `build`.
+    pub fn get_params(&self) -> Vec<Value> {
+        match self {
+            Condition::Eq(_, value)
+            | Condition::Ne(_, value)
+            | Condition::Lt(_, value)
+            | Condition::Gt(_, value)
+            | Condition::Le(_, value)
+            | Condition::Ge(_, value)
+            | Condition::Like(_, value) => vec![value.clone()],
+            Condition::Between(_, low, high) => vec![low.clone(), high.clone()],
+            Condition::InList(_, values) => values.clone(),
+            Condition::IsNull(_) | Condition::IsNotNull(_) | Condition::EqColumn(_, _) => {
+                Vec::new()
+            }
+            Condition::InSubquery(_, subquery)
+            | Condition::Exists(subquery)
+            | Condition::ScalarSubquery(_, _, subquery) => subquery.get_params(),
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                let mut params = left.get_params();
+                params.extend(right.get_params());
+                params
+            }
+            Condition::Not(condition) => condition.get_params(),
+        }
+    }
+}
+
+impl std::ops::Not for Condition {
+    type Output = Condition;
+
+    fn not(self) -> Condition {
+        Condition::Not(Box::new(self))
+    }
+}
@@ -0,0 +1,798 @@
+use crate::table::IndexSchema;
+use crate::value::Value;
+
+/// The SQL syntax that differs between databases, used by the builders of every backend
+/// to generate their statements.
+///
+/// The builders generate statements with `?` placeholders, which are rewritten with
+/// `bind_placeholders` right before a statement is executed.
+pub trait Dialect {
+    /// Quotes a table or column name, e.g. `order.id`.
+    ///
+    /// Anything other than a plain name, e.g. `*` or `COUNT(*)`, should be left as it is.
+    fn quote_identifier(&self, name: &str) -> String;
+
+    /// The placeholder of the parameter at `index`, starting at 1.
+    fn placeholder(&self, index: usize) -> String;
+
+    /// The clause limiting the rows of a query, or an empty string for neither.
+    fn limit_offset(&self, limit: Option<usize>, offset: Option<usize>) -> String;
+
+    /// The clause limiting the rows of a query right after `SELECT`, e.g. `TOP (10)`,
+    /// for databases that limit them there instead of with `limit_offset`.
+    fn top(&self, _limit: Option<usize>, _offset: Option<usize>) -> String {
+        String::new()
+    }
+
+    /// Whether the rows of a query can only be skipped if it has an ORDER BY.
+    fn offset_requires_order_by(&self) -> bool {
+        false
+    }
+
+    /// Turns an INSERT statement into an upsert handling rows that conflict with existing
+    /// rows on `conflict_columns`, updating the `update_columns` of the existing row or
+    /// skipping the row if there are none.
+    ///
+    /// `key_column` is the auto-increment primary key of the table, if any.
+    fn upsert(
+        &self,
+        statement: &str,
+        conflict_columns: &[String],
+        update_columns: &[String],
+        key_column: Option<&str>,
+    ) -> String;
+
+    /// The expression of the current timestamp as `YYYY-MM-DD HH:MM:SS` text in UTC, the
+    /// `DEFAULT` of the `created_at` and `updated_at` columns stored as text.
+    fn current_timestamp(&self) -> &'static str;
+
+    /// The values of an INSERT of rows whose columns are all assigned by the database.
+    fn default_values(&self, row_count: usize) -> String;
+
+    /// Makes an INSERT statement return the `column` of the inserted rows, e.g. the
+    /// generated key, or `None` if the database cannot return it.
+    fn returning(&self, statement: &str, column: &str) -> Option<String> {
+        Some(format!(
+            "{} RETURNING {}",
+            statement,
+            self.quote_identifier(column)
+        ))
+    }
+
+    /// Whether the rows returned by `returning` come in the order of the inserted rows, so
+    /// the keys of a multi-row INSERT can be written back into its rows.
+    fn returns_rows_in_order(&self) -> bool {
+        true
+    }
+
+    /// The maximum number of parameters of a statement, which limits the rows of a
+    /// multi-row INSERT. The default is the lowest limit of the supported databases,
+    /// which SQLite had before version 3.32.
+    fn max_parameters(&self) -> usize {
+        999
+    }
+
+    /// The maximum number of rows of a VALUES clause, if the database has one.
+    fn max_rows(&self) -> usize {
+        usize::MAX
+    }
+
+    /// The aggregate counting the rows of a query.
+    fn count_all(&self) -> &'static str {
+        "COUNT(*)"
+    }
+
+    /// Maps the sqlite type of a column, e.g. `INTEGER`, to the type of the database
+    /// holding the same values.
+    ///
+    /// `keyed` is set for a column of a primary key, a unique constraint or an index, and
+    /// `has_default` for a column with a default, which some databases only allow for
+    /// text and binary types of a limited length.
+    fn column_type<'a>(&self, column_type: &'a str, _keyed: bool, _has_default: bool) -> &'a str {
+        column_type
+    }
+
+    /// The constraint declaring a column the primary key of its table, along with the
+    /// clause generating its keys for an auto-increment column.
+    fn primary_key(&self, _auto_increment: bool) -> &'static str {
+        "PRIMARY KEY"
+    }
+
+    /// The sequence generating the keys of the auto-increment column of a table, for
+    /// databases without auto-increment columns, which is created and dropped with it.
+    fn sequence_name(&self, _table_name: &str, _column: &str) -> Option<String> {
+        None
+    }
+
+    /// The DEFAULT of a column computed by an expression, e.g. `current_timestamp`,
+    /// rather than a literal.
+    fn default_expression(&self, expression: &str) -> String {
+        expression.to_string()
+    }
+
+    /// The CREATE TABLE statement of a table with the definitions of its columns and
+    /// constraints, which leaves an existing table as it is with `if_not_exists`.
+    fn create_table(&self, table_name: &str, definitions: &str, if_not_exists: bool) -> String {
+        let if_not_exists_str = match if_not_exists {
+            true => "IF NOT EXISTS ",
+            false => "",
+        };
+
+        format!(
+            "CREATE TABLE {}{} ({})",
+            if_not_exists_str,
+            self.quote_identifier(table_name),
+            definitions
+        )
+    }
+
+    /// The CREATE INDEX statement of an index of a table, which leaves an existing index
+    /// as it is.
+    fn create_index(&self, table_name: &str, index: &IndexSchema) -> String {
+        format!(
+            "CREATE {}INDEX IF NOT EXISTS {} ON {} ({})",
+            unique_str(index),
+            self.quote_identifier(&index.name),
+            self.quote_identifier(table_name),
+            quote_columns(self, &index.columns)
+        )
+    }
+
+    /// A query returning a row if an index of a table exists, for databases whose
+    /// `create_index` cannot leave an existing index as it is.
+    fn index_exists(&self, _table_name: &str, _index_name: &str) -> Option<(String, Vec<Value>)> {
+        None
+    }
+
+    /// Rewrites the `?` placeholders of a generated statement with `placeholder`, leaving
+    /// quoted strings and identifiers as they are.
+    fn bind_placeholders(&self, statement: &str) -> String {
+        let mut bound = String::with_capacity(statement.len());
+        let mut quote: Option<char> = None;
+        let mut count = 0;
+
+        for c in statement.chars() {
+            match (quote, c) {
+                (None, '\'') | (None, '"') | (None, '`') => {
+                    quote = Some(c);
+                    bound.push(c);
+                }
+                (Some(open), _) if open == c => {
+                    quote = None;
+                    bound.push(c);
+                }
+                (None, '?') => {
+                    count += 1;
+                    bound.push_str(&self.placeholder(count));
+                }
+                _ => bound.push(c),
+            }
+        }
+
+        bound
+    }
+}
+
+/// The dialect of SQLite.
+///
+/// Plain names are quoted with double quotes, e.g. `"order"."id"`, so they may be keywords.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sqlite;
+
+impl Dialect for Sqlite {
+    fn quote_identifier(&self, name: &str) -> String {
+        quote_plain_name(name, '"', '"')
+    }
+
+    fn placeholder(&self, _index: usize) -> String {
+        "?".to_string()
+    }
+
+    fn limit_offset(&self, limit: Option<usize>, offset: Option<usize>) -> String {
+        match (limit, offset) {
+            (Some(count), Some(offset)) => format!("LIMIT {} OFFSET {}", count, offset),
+            (Some(count), None) => format!("LIMIT {}", count),
+            // an offset needs a limit, and a negative limit means all rows
+            (None, Some(offset)) => format!("LIMIT -1 OFFSET {}", offset),
+            (None, None) => String::new(),
+        }
+    }
+
+    fn upsert(
+        &self,
+        statement: &str,
+        conflict_columns: &[String],
+        update_columns: &[String],
+        _key_column: Option<&str>,
+    ) -> String {
+        format!(
+            "{} {}",
+            statement,
+            on_conflict_clause(self, conflict_columns, update_columns)
+        )
+    }
+
+    fn current_timestamp(&self) -> &'static str {
+        "CURRENT_TIMESTAMP"
+    }
+
+    fn default_values(&self, _row_count: usize) -> String {
+        "DEFAULT VALUES".to_string()
+    }
+
+    /// The limit of the bundled SQLite, raised in version 3.32.
+    fn max_parameters(&self) -> usize {
+        32766
+    }
+
+    fn primary_key(&self, auto_increment: bool) -> &'static str {
+        match auto_increment {
+            true => "PRIMARY KEY AUTOINCREMENT",
+            false => "PRIMARY KEY",
+        }
+    }
+
+    /// The index of a table of an attached database, e.g. `archive.users`, is qualified
+    /// with its alias instead of the table, as SQLite requires.
+    fn create_index(&self, table_name: &str, index: &IndexSchema) -> String {
+        let (index_name, table_name) = match table_name.split_once('.') {
+            Some((schema, table_name)) => (format!("{}.{}", schema, index.name), table_name),
+            None => (index.name.clone(), table_name),
+        };
+
+        format!(
+            "CREATE {}INDEX IF NOT EXISTS {} ON {} ({})",
+            unique_str(index),
+            self.quote_identifier(&index_name),
+            self.quote_identifier(table_name),
+            quote_columns(self, &index.columns)
+        )
+    }
+}
+
+/// The dialect of PostgreSQL, with numbered `$1, $2, ...` placeholders.
+///
+/// Names are left unquoted, so they fold to lowercase the same way as the unquoted
+/// names in conditions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Postgres;
+
+impl Dialect for Postgres {
+    fn quote_identifier(&self, name: &str) -> String {
+        name.to_string()
+    }
+
+    fn placeholder(&self, index: usize) -> String {
+        format!("${}", index)
+    }
+
+    fn limit_offset(&self, limit: Option<usize>, offset: Option<usize>) -> String {
+        let limit_str = limit.map(|count| format!("LIMIT {}", count));
+        let offset_str = offset.map(|offset| format!("OFFSET {}", offset));
+
+        limit_str
+            .into_iter()
+            .chain(offset_str)
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    fn upsert(
+        &self,
+        statement: &str,
+        conflict_columns: &[String],
+        update_columns: &[String],
+        _key_column: Option<&str>,
+    ) -> String {
+        format!(
+            "{} {}",
+            statement,
+            on_conflict_clause(self, conflict_columns, update_columns)
+        )
+    }
+
+    fn current_timestamp(&self) -> &'static str {
+        "to_char(CURRENT_TIMESTAMP AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')"
+    }
+
+    fn default_values(&self, _row_count: usize) -> String {
+        "DEFAULT VALUES".to_string()
+    }
+
+    fn max_parameters(&self) -> usize {
+        65535
+    }
+
+    fn column_type<'a>(&self, column_type: &'a str, _keyed: bool, _has_default: bool) -> &'a str {
+        match column_type.to_ascii_uppercase().as_str() {
+            "INTEGER" => "BIGINT",
+            "REAL" => "DOUBLE PRECISION",
+            "BLOB" => "BYTEA",
+            // an empty type holds any value in sqlite, which is stored as text
+            "" => "TEXT",
+            _ => column_type,
+        }
+    }
+
+    fn primary_key(&self, auto_increment: bool) -> &'static str {
+        match auto_increment {
+            true => "GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY",
+            false => "PRIMARY KEY",
+        }
+    }
+}
+
+/// The dialect of MySQL and MariaDB, with names quoted with backticks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MySql;
+
+impl Dialect for MySql {
+    fn quote_identifier(&self, name: &str) -> String {
+        quote_plain_name(name, '`', '`')
+    }
+
+    fn placeholder(&self, _index: usize) -> String {
+        "?".to_string()
+    }
+
+    fn limit_offset(&self, limit: Option<usize>, offset: Option<usize>) -> String {
+        match (limit, offset) {
+            (Some(count), Some(offset)) => format!("LIMIT {} OFFSET {}", count, offset),
+            (Some(count), None) => format!("LIMIT {}", count),
+            // an offset needs a limit, so a query without one is limited to all rows
+            (None, Some(offset)) => format!("LIMIT {} OFFSET {}", u64::MAX, offset),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// MySQL checks every primary key and unique index of the table for conflicts, so
+    /// `conflict_columns` are not part of the statement.
+    ///
+    /// The auto-increment key is set to `LAST_INSERT_ID(key)`, so the key of an updated
+    /// row is reported as if it had been inserted.
+    fn upsert(
+        &self,
+        statement: &str,
+        _conflict_columns: &[String],
+        update_columns: &[String],
+        key_column: Option<&str>,
+    ) -> String {
+        if update_columns.is_empty() {
+            return statement.replacen("INSERT INTO", "INSERT IGNORE INTO", 1);
+        }
+
+        // the values of the row that failed to insert are available as `VALUES(column)`
+        let mut set_str: Vec<String> = update_columns
+            .iter()
+            .map(|column| {
+                let column = self.quote_identifier(column);
+                format!("{} = VALUES({})", column, column)
+            })
+            .collect();
+
+        if let Some(column) = key_column {
+            let column = self.quote_identifier(column);
+            set_str.push(format!("{} = LAST_INSERT_ID({})", column, column));
+        }
+
+        format!(
+            "{} ON DUPLICATE KEY UPDATE {}",
+            statement,
+            set_str.join(", ")
+        )
+    }
+
+    fn current_timestamp(&self) -> &'static str {
+        "DATE_FORMAT(UTC_TIMESTAMP(), '%Y-%m-%d %H:%i:%s')"
+    }
+
+    fn default_values(&self, row_count: usize) -> String {
+        format!("VALUES {}", vec!["()"; row_count].join(", "))
+    }
+
+    fn max_parameters(&self) -> usize {
+        65535
+    }
+
+    /// MySQL has no RETURNING clause, and reports the generated key with `LAST_INSERT_ID()`.
+    fn returning(&self, _statement: &str, _column: &str) -> Option<String> {
+        None
+    }
+
+    /// MySQL only indexes and defaults text and binary columns of a limited length.
+    fn column_type<'a>(&self, column_type: &'a str, keyed: bool, has_default: bool) -> &'a str {
+        match (
+            column_type.to_ascii_uppercase().as_str(),
+            keyed || has_default,
+        ) {
+            ("INTEGER", _) => "BIGINT",
+            ("REAL", _) => "DOUBLE",
+            ("BLOB", true) => "VARBINARY(255)",
+            ("BLOB", false) => "LONGBLOB",
+            // an empty type holds any value in sqlite, which is stored as text
+            ("TEXT" | "", true) => "VARCHAR(255)",
+            ("TEXT" | "", false) => "TEXT",
+            _ => column_type,
+        }
+    }
+
+    fn primary_key(&self, auto_increment: bool) -> &'static str {
+        match auto_increment {
+            true => "AUTO_INCREMENT PRIMARY KEY",
+            false => "PRIMARY KEY",
+        }
+    }
+
+    /// An expression default is written within parentheses.
+    fn default_expression(&self, expression: &str) -> String {
+        format!("({})", expression)
+    }
+
+    /// MySQL has no IF NOT EXISTS clause for indexes, so they are looked up with
+    /// `index_exists` first.
+    fn create_index(&self, table_name: &str, index: &IndexSchema) -> String {
+        format!(
+            "CREATE {}INDEX {} ON {} ({})",
+            unique_str(index),
+            self.quote_identifier(&index.name),
+            self.quote_identifier(table_name),
+            quote_columns(self, &index.columns)
+        )
+    }
+
+    /// A table qualified with its database, e.g. `archive.users`, is looked up there.
+    fn index_exists(&self, table_name: &str, index_name: &str) -> Option<(String, Vec<Value>)> {
+        let (schema_str, mut params) = match table_name.split_once('.') {
+            Some((schema, table_name)) => (
+                "?",
+                vec![
+                    Value::Text(schema.to_string()),
+                    Value::Text(table_name.to_string()),
+                ],
+            ),
+            None => ("DATABASE()", vec![Value::Text(table_name.to_string())]),
+        };
+        params.push(Value::Text(index_name.to_string()));
+
+        let query = format!(
+            "SELECT 1 FROM information_schema.statistics WHERE table_schema = {} AND table_name = ? AND index_name = ? LIMIT 1",
+            schema_str
+        );
+
+        Some((query, params))
+    }
+}
+
+/// The dialect of DuckDB, which follows PostgreSQL apart from its timestamp functions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DuckDb;
+
+impl Dialect for DuckDb {
+    fn quote_identifier(&self, name: &str) -> String {
+        Postgres.quote_identifier(name)
+    }
+
+    fn placeholder(&self, _index: usize) -> String {
+        "?".to_string()
+    }
+
+    fn limit_offset(&self, limit: Option<usize>, offset: Option<usize>) -> String {
+        Postgres.limit_offset(limit, offset)
+    }
+
+    fn upsert(
+        &self,
+        statement: &str,
+        conflict_columns: &[String],
+        update_columns: &[String],
+        _key_column: Option<&str>,
+    ) -> String {
+        format!(
+            "{} {}",
+            statement,
+            on_conflict_clause(self, conflict_columns, update_columns)
+        )
+    }
+
+    fn current_timestamp(&self) -> &'static str {
+        "strftime(CAST(CURRENT_TIMESTAMP AS TIMESTAMP), '%Y-%m-%d %H:%M:%S')"
+    }
+
+    fn default_values(&self, _row_count: usize) -> String {
+        "DEFAULT VALUES".to_string()
+    }
+
+    fn max_parameters(&self) -> usize {
+        32766
+    }
+
+    /// The INTEGER and REAL types of DuckDB only hold 32 bits.
+    fn column_type<'a>(&self, column_type: &'a str, _keyed: bool, _has_default: bool) -> &'a str {
+        match column_type.to_ascii_uppercase().as_str() {
+            "INTEGER" => "BIGINT",
+            "REAL" => "DOUBLE",
+            // an empty type holds any value in sqlite, which is stored as text
+            "" => "TEXT",
+            _ => column_type,
+        }
+    }
+
+    /// DuckDB has no auto-increment columns, so their keys come from a sequence named
+    /// after the table and the column, e.g. `users_id_seq`.
+    fn sequence_name(&self, table_name: &str, column: &str) -> Option<String> {
+        Some(format!("{}_{}_seq", table_name, column))
+    }
+}
+
+/// The dialect of SQL Server, with names quoted with brackets and numbered `@P1, @P2, ...`
+/// placeholders.
+///
+/// A query is limited with `TOP` unless it skips rows, which needs `OFFSET ... FETCH`
+/// and an ORDER BY.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MsSql;
+
+impl Dialect for MsSql {
+    fn quote_identifier(&self, name: &str) -> String {
+        quote_plain_name(name, '[', ']')
+    }
+
+    fn placeholder(&self, index: usize) -> String {
+        format!("@P{}", index)
+    }
+
+    fn limit_offset(&self, limit: Option<usize>, offset: Option<usize>) -> String {
+        match (limit, offset) {
+            (Some(count), Some(offset)) => {
+                format!("OFFSET {} ROWS FETCH NEXT {} ROWS ONLY", offset, count)
+            }
+            (None, Some(offset)) => format!("OFFSET {} ROWS", offset),
+            // a limit without an offset is a TOP clause
+            (_, None) => String::new(),
+        }
+    }
+
+    fn top(&self, limit: Option<usize>, offset: Option<usize>) -> String {
+        match (limit, offset) {
+            (Some(count), None) => format!("TOP ({})", count),
+            _ => String::new(),
+        }
+    }
+
+    fn offset_requires_order_by(&self) -> bool {
+        true
+    }
+
+    /// SQL Server has no upsert clause, so the rows of the INSERT statement are merged
+    /// into the table instead, matched with its rows on `conflict_columns`, which are
+    /// required. The MERGE statement ends with a semicolon, as SQL Server requires.
+    fn upsert(
+        &self,
+        statement: &str,
+        conflict_columns: &[String],
+        update_columns: &[String],
+        _key_column: Option<&str>,
+    ) -> String {
+        // the statement is generated as `INSERT INTO table (columns) VALUES rows`
+        let parts = statement
+            .strip_prefix("INSERT INTO ")
+            .and_then(|statement| statement.split_once(" ("))
+            .and_then(|(table, statement)| {
+                let (columns, rows) = statement.split_once(") VALUES ")?;
+                Some((table, columns, rows))
+            });
+
+        let (table, columns, rows) = match parts {
+            Some(parts) if !conflict_columns.is_empty() => parts,
+            _ => return statement.to_string(),
+        };
+
+        let on_str: Vec<String> = conflict_columns
+            .iter()
+            .map(|column| {
+                let column = self.quote_identifier(column);
+                format!("target.{} = source.{}", column, column)
+            })
+            .collect();
+
+        let mut merge = format!(
+            "MERGE INTO {} AS target USING (VALUES {}) AS source ({}) ON {}",
+            table,
+            rows,
+            columns,
+            on_str.join(" AND ")
+        );
+
+        if !update_columns.is_empty() {
+            let set_str: Vec<String> = update_columns
+                .iter()
+                .map(|column| {
+                    let column = self.quote_identifier(column);
+                    format!("{} = source.{}", column, column)
+                })
+                .collect();
+
+            merge.push_str(&format!(
+                " WHEN MATCHED THEN UPDATE SET {}",
+                set_str.join(", ")
+            ));
+        }
+
+        let values_str: Vec<String> = columns
+            .split(", ")
+            .map(|column| format!("source.{}", column))
+            .collect();
+
+        format!(
+            "{} WHEN NOT MATCHED THEN INSERT ({}) VALUES ({});",
+            merge,
+            columns,
+            values_str.join(", ")
+        )
+    }
+
+    fn current_timestamp(&self) -> &'static str {
+        "CONVERT(VARCHAR(19), SYSUTCDATETIME(), 120)"
+    }
+
+    fn default_values(&self, _row_count: usize) -> String {
+        "DEFAULT VALUES".to_string()
+    }
+
+    /// SQL Server returns the columns of the inserted rows with an OUTPUT clause, which
+    /// comes before the inserted values.
+    fn returning(&self, statement: &str, column: &str) -> Option<String> {
+        let output_str = format!("OUTPUT INSERTED.{}", self.quote_identifier(column));
+
+        let statement = if let Some(merge) = statement.strip_suffix(';') {
+            format!("{} {};", merge, output_str)
+        } else if let Some(insert) = statement.strip_suffix(" DEFAULT VALUES") {
+            format!("{} {} DEFAULT VALUES", insert, output_str)
+        } else {
+            statement.replacen(") VALUES ", &format!(") {} VALUES ", output_str), 1)
+        };
+
+        Some(statement)
+    }
+
+    /// The OUTPUT clause returns the rows in no particular order.
+    fn returns_rows_in_order(&self) -> bool {
+        false
+    }
+
+    /// Below the 2100 parameters allowed by SQL Server, to leave room for the parameters
+    /// of the driver.
+    fn max_parameters(&self) -> usize {
+        2000
+    }
+
+    fn max_rows(&self) -> usize {
+        1000
+    }
+
+    /// COUNT(*) is an INT in SQL Server, which overflows above 2^31 rows.
+    fn count_all(&self) -> &'static str {
+        "COUNT_BIG(*)"
+    }
+
+    /// The key of an index is limited to 900 bytes, so the text and binary columns of keys
+    /// and indexes are limited to 450 characters and 900 bytes.
+    fn column_type<'a>(&self, column_type: &'a str, keyed: bool, _has_default: bool) -> &'a str {
+        match (column_type.to_ascii_uppercase().as_str(), keyed) {
+            ("INTEGER", _) => "BIGINT",
+            ("REAL", _) => "FLOAT",
+            ("BLOB", false) => "VARBINARY(MAX)",
+            ("BLOB", true) => "VARBINARY(900)",
+            // an empty type holds any value in sqlite, which is stored as text
+            ("TEXT" | "", false) => "NVARCHAR(MAX)",
+            ("TEXT" | "", true) => "NVARCHAR(450)",
+            _ => column_type,
+        }
+    }
+
+    fn primary_key(&self, auto_increment: bool) -> &'static str {
+        match auto_increment {
+            true => "IDENTITY(1,1) PRIMARY KEY",
+            false => "PRIMARY KEY",
+        }
+    }
+
+    /// An expression default is written within parentheses.
+    fn default_expression(&self, expression: &str) -> String {
+        format!("({})", expression)
+    }
+
+    /// SQL Server has no IF NOT EXISTS clause for tables, so the table is looked up first.
+    fn create_table(&self, table_name: &str, definitions: &str, if_not_exists: bool) -> String {
+        let statement = format!(
+            "CREATE TABLE {} ({})",
+            self.quote_identifier(table_name),
+            definitions
+        );
+
+        match if_not_exists {
+            true => format!(
+                "IF OBJECT_ID(N'{}', N'U') IS NULL {}",
+                table_name.replace('\'', "''"),
+                statement
+            ),
+            false => statement,
+        }
+    }
+
+    /// SQL Server has no IF NOT EXISTS clause for indexes, so the index is looked up first.
+    fn create_index(&self, table_name: &str, index: &IndexSchema) -> String {
+        format!(
+            "IF NOT EXISTS (SELECT 1 FROM sys.indexes WHERE name = N'{}' AND object_id = OBJECT_ID(N'{}')) CREATE {}INDEX {} ON {} ({})",
+            index.name.replace('\'', "''"),
+            table_name.replace('\'', "''"),
+            unique_str(index),
+            self.quote_identifier(&index.name),
+            self.quote_identifier(table_name),
+            quote_columns(self, &index.columns)
+        )
+    }
+}
+
+/// The UNIQUE keyword of the CREATE INDEX statement of a unique index.
+fn unique_str(index: &IndexSchema) -> &'static str {
+    match index.unique {
+        true => "UNIQUE ",
+        false => "",
+    }
+}
+
+/// Quotes the columns of an index, separated by commas.
+fn quote_columns<D: Dialect + ?Sized>(dialect: &D, columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|column| dialect.quote_identifier(column))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Quotes each part of a plain name, e.g. `order.id`, leaving anything else as it is.
+fn quote_plain_name(name: &str, open: char, close: char) -> String {
+    let is_plain = name.split('.').all(|part| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    });
+
+    if !is_plain {
+        return name.to_string();
+    }
+
+    name.split('.')
+        .map(|part| format!("{}{}{}", open, part, close))
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+/// Generates the `ON CONFLICT` clause of an upsert, shared by SQLite, PostgreSQL and DuckDB.
+fn on_conflict_clause<D: Dialect + ?Sized>(
+    dialect: &D,
+    conflict_columns: &[String],
+    update_columns: &[String],
+) -> String {
+    let target_str = if conflict_columns.is_empty() {
+        String::new()
+    } else {
+        format!("({})", quote_columns(dialect, conflict_columns))
+    };
+
+    if update_columns.is_empty() {
+        return format!("ON CONFLICT{} DO NOTHING", target_str);
+    }
+
+    // the values of the row that failed to insert are available as `excluded`
+    let set_str: Vec<String> = update_columns
+        .iter()
+        .map(|column| dialect.quote_identifier(column))
+        .map(|column| format!("{} = excluded.{}", column, column))
+        .collect();
+
+    format!(
+        "ON CONFLICT{} DO UPDATE SET {}",
+        target_str,
+        set_str.join(", ")
+    )
+}
@@ -0,0 +1,41 @@
+use crate::backend;
+use crate::dialect::DuckDb;
+
+use std::path::Path;
+
+use ::duckdb::Connection;
+
+mod value;
+
+pub use crate::backend::{
+    create_indexes, create_table, create_table_if_not_exists, drop_table, drop_table_if_exists,
+};
+pub use crate::backend::{delete, insert, insert_many, select, update};
+pub use crate::backend::{DeleteBuilder, InsertBuilder, UpdateBuilder};
+pub use crate::condition::Condition;
+pub use crate::join::JoinType;
+
+/// A query run against DuckDB.
+pub type QueryBuilder<'a> = backend::QueryBuilder<'a, Connection>;
+
+/// Creates a subquery of the DuckDB dialect, as `backend::subquery` does.
+pub fn subquery<'a>(columns: Vec<String>) -> QueryBuilder<'a> {
+    backend::subquery(&DuckDb, columns)
+}
+
+/// The error of a query run against DuckDB, with the `duckdb::Error` of the driver as its source.
+pub use crate::error::{Error, Result};
+
+/// Open a DuckDB database file, creating it if it does not exist.
+pub fn open<P: AsRef<Path>>(path: P) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+
+    Ok(conn)
+}
+
+/// Open a DuckDB database in memory, which is gone once the connection is closed.
+pub fn open_in_memory() -> Result<Connection> {
+    let conn = Connection::open_in_memory()?;
+
+    Ok(conn)
+}
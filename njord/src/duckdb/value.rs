@@ -0,0 +1,168 @@
+use crate::backend::{Executor, Row};
+use crate::dialect::{Dialect, DuckDb};
+use crate::error::{Error, Result};
+use crate::timestamp::format_date;
+use crate::value::Value;
+
+use ::duckdb::types::{TimeUnit, Value as DuckDbValue};
+use ::duckdb::{params_from_iter, Connection};
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+/// Executes a query and returns its rows, as the name and value of each column.
+fn read_rows(conn: &Connection, query: &str, params: &[Value]) -> ::duckdb::Result<Vec<Row>> {
+    let mut statement = conn.prepare(query)?;
+    let mut rows = statement.query(params_from_iter(bind_values(params)))?;
+
+    // the columns of a statement are only known once it has been executed
+    let column_names = match rows.as_ref() {
+        Some(statement) => statement.column_names(),
+        None => Vec::new(),
+    };
+
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut values = Vec::with_capacity(column_names.len());
+
+        for (index, column) in column_names.iter().enumerate() {
+            let value: DuckDbValue = row.get(index)?;
+            values.push((column.clone(), value.into()));
+        }
+
+        result.push(values);
+    }
+
+    Ok(result)
+}
+
+/// Converts a value of the driver into the value the struct fields are set from.
+///
+/// Integers too large for an `i64` and decimals are converted into text, as are dates
+/// and times, in the format sqlite stores them as. Nested values such as lists and
+/// structs have no counterpart in sqlite and are kept as their debug representation.
+impl From<DuckDbValue> for Value {
+    fn from(value: DuckDbValue) -> Self {
+        match value {
+            DuckDbValue::Null => Value::Null,
+            DuckDbValue::Boolean(value) => Value::Integer(value as i64),
+            DuckDbValue::TinyInt(value) => Value::Integer(i64::from(value)),
+            DuckDbValue::SmallInt(value) => Value::Integer(i64::from(value)),
+            DuckDbValue::Int(value) => Value::Integer(i64::from(value)),
+            DuckDbValue::BigInt(value) => Value::Integer(value),
+            DuckDbValue::UTinyInt(value) => Value::Integer(i64::from(value)),
+            DuckDbValue::USmallInt(value) => Value::Integer(i64::from(value)),
+            DuckDbValue::UInt(value) => Value::Integer(i64::from(value)),
+            DuckDbValue::UBigInt(value) => integer_or_text(i64::try_from(value), value),
+            DuckDbValue::HugeInt(value) => integer_or_text(i64::try_from(value), value),
+            DuckDbValue::Float(value) => Value::Real(f64::from(value)),
+            DuckDbValue::Double(value) => Value::Real(value),
+            DuckDbValue::Decimal(value) => Value::Text(value.to_string()),
+            DuckDbValue::Text(value) | DuckDbValue::Enum(value) => Value::Text(value),
+            DuckDbValue::Blob(value) => Value::Blob(value),
+            DuckDbValue::Date32(days) => Value::Text(format_date(i64::from(days))),
+            DuckDbValue::Timestamp(unit, value) => Value::Text(format_timestamp(unit, value)),
+            DuckDbValue::Time64(unit, value) => Value::Text(format_time(unit.to_micros(value))),
+            DuckDbValue::Union(value) => (*value).into(),
+            value => Value::Text(format!("{:?}", value)),
+        }
+    }
+}
+
+fn integer_or_text<E, V: ToString>(integer: std::result::Result<i64, E>, value: V) -> Value {
+    match integer {
+        Ok(integer) => Value::Integer(integer),
+        Err(_) => Value::Text(value.to_string()),
+    }
+}
+
+/// Formats the microseconds since midnight as `HH:MM:SS`, with the fraction of a second
+/// if there is one.
+fn format_time(micros: i64) -> String {
+    let seconds = micros / MICROS_PER_SECOND;
+    let fraction = micros % MICROS_PER_SECOND;
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+
+    match fraction {
+        0 => time,
+        _ => format!("{}.{:06}", time, fraction),
+    }
+}
+
+/// Formats a timestamp as `YYYY-MM-DD HH:MM:SS`, with the fraction of a second if there
+/// is one.
+fn format_timestamp(unit: TimeUnit, value: i64) -> String {
+    let micros = unit.to_micros(value);
+
+    format!(
+        "{} {}",
+        format_date(micros.div_euclid(MICROS_PER_DAY)),
+        format_time(micros.rem_euclid(MICROS_PER_DAY))
+    )
+}
+
+/// Converts a value into the parameter of a statement. DuckDB casts the parameters to
+/// the types of the columns they are compared with, so the value is bound as it is.
+impl From<Value> for DuckDbValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => DuckDbValue::Null,
+            Value::Integer(value) => DuckDbValue::BigInt(value),
+            Value::Real(value) => DuckDbValue::Double(value),
+            Value::Text(value) => DuckDbValue::Text(value),
+            Value::Blob(value) => DuckDbValue::Blob(value),
+        }
+    }
+}
+
+fn bind_values(values: &[Value]) -> Vec<DuckDbValue> {
+    values.iter().cloned().map(Into::into).collect()
+}
+
+/// The violations of a constraint, whose message DuckDB prefixes with "Constraint Error",
+/// are returned as `Error::Constraint`.
+impl From<::duckdb::Error> for Error {
+    fn from(error: ::duckdb::Error) -> Self {
+        let is_constraint = match &error {
+            ::duckdb::Error::DuckDBFailure(_, Some(message)) => {
+                message.starts_with("Constraint Error")
+            }
+            _ => false,
+        };
+
+        Error::from_driver(error, is_constraint)
+    }
+}
+
+/// Runs the builders of the `backend` module on a DuckDB connection. Statements are
+/// prepared when they are run.
+impl Executor for Connection {
+    type Statement = String;
+
+    fn dialect(&self) -> &'static dyn Dialect {
+        &DuckDb
+    }
+
+    fn prepare(&mut self, statement: &str) -> Result<String> {
+        Ok(statement.to_string())
+    }
+
+    fn execute(&mut self, statement: &String, params: &[Value]) -> Result<u64> {
+        let count = Connection::execute(self, statement, params_from_iter(bind_values(params)))?;
+
+        Ok(count as u64)
+    }
+
+    fn query(&mut self, statement: &String, params: &[Value]) -> Result<Vec<Row>> {
+        Ok(read_rows(self, statement, params)?)
+    }
+
+    fn execute_batch(&mut self, statement: &str) -> Result<()> {
+        Ok(Connection::execute_batch(self, statement)?)
+    }
+}
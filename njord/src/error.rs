@@ -0,0 +1,82 @@
+use crate::value::FromSqlError;
+
+/// An error of a driver, boxed so that every backend shares `Error`.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// The error returned by the functions and builders of every backend.
+///
+/// The error of the driver is kept as the source of `Execution` and `Constraint`, and can
+/// be told apart with `Error::driver_error`, e.g. `rusqlite::Error` or `postgres::Error`.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A builder used in a way that cannot produce a valid statement, e.g. a write to a
+    /// read-only table or a lookup by primary key on a table without one.
+    #[error("{0}")]
+    Generation(String),
+    /// An error reported by the database or its driver while running a statement.
+    #[error(transparent)]
+    Execution(BoxError),
+    /// A selected value that cannot be converted into the field of its column, or a field
+    /// that cannot be converted into the value of its column.
+    #[error("failed to convert the value of column {column}: {source}")]
+    Mapping {
+        column: String,
+        #[source]
+        source: FromSqlError,
+    },
+    /// A statement rejected by a constraint of the table, e.g. UNIQUE, NOT NULL, CHECK or
+    /// a foreign key.
+    #[error(transparent)]
+    Constraint(BoxError),
+    /// An update of a row with a `#[njord(version)]` column that was updated by someone
+    /// else since it was read, so its version no longer matches.
+    #[error("row of table {table} was updated since it was read")]
+    VersionConflict { table: String },
+    /// A query expected to return a row that returned none, e.g. `one`.
+    #[error("query returned no rows")]
+    NotFound,
+    /// A query expected to return a single row that returned more, with the number of rows
+    /// it returned.
+    #[error("query returned {0} rows instead of one")]
+    TooManyRows(usize),
+    /// A statement blocked by the `before` of a `Middleware`, with the reason it was blocked.
+    #[error("statement blocked: {0}")]
+    Blocked(String),
+    /// A statement cancelled while it was running, e.g. by `sqlite::InterruptHandle` from
+    /// another thread.
+    #[error("statement was cancelled")]
+    Cancelled,
+}
+
+impl Error {
+    /// Get the error of the driver, if it is one of type `E`.
+    pub fn driver_error<E: std::error::Error + 'static>(&self) -> Option<&E> {
+        match self {
+            Error::Execution(error) | Error::Constraint(error) => error.downcast_ref::<E>(),
+            _ => None,
+        }
+    }
+
+    /// Wraps the error of a driver, as a `Constraint` if `is_constraint` and as an
+    /// `Execution` otherwise.
+    #[cfg(any(
+        feature = "sqlite",
+        feature = "postgres",
+        feature = "mysql",
+        feature = "duckdb",
+        feature = "mssql"
+    ))]
+    pub(crate) fn from_driver<E>(error: E, is_constraint: bool) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        match is_constraint {
+            true => Error::Constraint(Box::new(error)),
+            false => Error::Execution(Box::new(error)),
+        }
+    }
+}
+
+/// The result of the functions and builders of every backend.
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,50 @@
+use crate::error::Result;
+use crate::table::Table;
+use crate::timestamp::current_timestamp;
+use crate::value::Value;
+
+/// Get the names of the inserted columns, leaving out the auto-increment primary key
+/// and the columns filled by their database default.
+pub(crate) fn get_insert_columns(table_row: &dyn Table) -> Vec<String> {
+    let mut skipped_columns = table_row.get_default_columns();
+    skipped_columns.extend(table_row.get_auto_increment_column());
+
+    table_row
+        .get_column_fields()
+        .into_iter()
+        .filter(|field| !skipped_columns.contains(field))
+        .collect()
+}
+
+/// Get the values bound to the placeholders of an inserted row,
+/// after generating the values njord fills on insert.
+///
+/// The timestamp columns are set to the current time, which is written into the row.
+pub(crate) fn get_insert_values(table_row: &mut dyn Table) -> Result<Vec<Value>> {
+    table_row.generate_values();
+
+    for column in get_timestamp_columns(table_row) {
+        let timestamp = current_timestamp(table_row, &column);
+        table_row.set_column_value(&column, timestamp)?;
+    }
+
+    let column_fields = get_insert_columns(table_row);
+
+    Ok(table_row
+        .get_column_fields()
+        .into_iter()
+        .zip(table_row.get_column_values()?)
+        .filter(|(field, _)| column_fields.contains(field))
+        .map(|(_, value)| value)
+        .collect())
+}
+
+/// Get the names of the `created_at` and `updated_at` columns, which are set to
+/// the current time on insert.
+pub(crate) fn get_timestamp_columns(table_row: &dyn Table) -> Vec<String> {
+    table_row
+        .get_created_at_column()
+        .into_iter()
+        .chain(table_row.get_updated_at_column())
+        .collect()
+}
@@ -0,0 +1,16 @@
+pub enum JoinType {
+    Inner,
+    Left,
+    Cross,
+}
+
+impl JoinType {
+    /// Builds the SQL keyword for the join.
+    pub fn build(&self) -> String {
+        match self {
+            JoinType::Inner => "INNER JOIN".to_string(),
+            JoinType::Left => "LEFT JOIN".to_string(),
+            JoinType::Cross => "CROSS JOIN".to_string(),
+        }
+    }
+}
@@ -1,3 +1,46 @@
+pub mod backend;
+pub mod column;
+pub mod condition;
+pub mod dialect;
+#[cfg(feature = "duckdb")]
+pub mod duckdb;
+pub mod error;
+mod insert;
+pub mod join;
+#[cfg(feature = "libsql")]
+pub mod libsql;
+pub mod logger;
+#[cfg(feature = "metrics")]
+mod meter;
+pub mod middleware;
+#[cfg(feature = "sqlite")]
+pub mod migrations;
+#[cfg(feature = "mssql")]
+pub mod mssql;
+#[cfg(feature = "mysql")]
+pub mod mysql;
+// a pool blocks the thread waiting for a connection, which a browser cannot do
+#[cfg(all(
+    feature = "sqlite",
+    not(all(target_family = "wasm", target_os = "unknown"))
+))]
+pub mod pool;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+mod sensitive;
+#[cfg(feature = "sqlite")]
 pub mod sqlite;
+mod statement;
 pub mod table;
+mod timestamp;
+mod trace;
 pub mod util;
+pub mod value;
+
+pub use error::{Error, Result};
+pub use logger::{
+    clear_slow_query_log, last_query_stats, set_query_logger, set_slow_query_log, QueryEvent,
+    QueryStats, SlowQuery,
+};
+pub use middleware::{add_middleware, clear_middleware, Middleware};
+pub use value::Value;
@@ -0,0 +1,70 @@
+use crate::backend::{self, asynchronous};
+use crate::dialect::Sqlite;
+use crate::table::Table;
+
+use ::libsql::{Builder, Connection};
+
+pub mod ddl;
+pub use ddl::{
+    create_indexes, create_table, create_table_if_not_exists, drop_table, drop_table_if_exists,
+};
+mod value;
+
+pub use crate::condition::Condition;
+pub use crate::join::JoinType;
+
+/// The error of a query run against a libsql server, with the `libsql::Error` of the driver as its source.
+pub use crate::error::{Error, Result};
+
+/// A query run against a libsql server, whose `build`, `first` and `count` return futures.
+pub type QueryBuilder<'a> = backend::QueryBuilder<'a, Connection>;
+pub type InsertBuilder<'a, T> = backend::InsertBuilder<'a, Connection, T>;
+pub type UpdateBuilder<'a> = backend::UpdateBuilder<'a, Connection>;
+pub type DeleteBuilder<'a> = backend::DeleteBuilder<'a, Connection>;
+
+pub fn select(conn: &mut Connection, columns: Vec<String>) -> QueryBuilder<'_> {
+    QueryBuilder::with_dialect(Some(conn), &Sqlite, columns)
+}
+
+/// Creates a subquery of the SQLite dialect, as `backend::subquery` does.
+pub fn subquery<'a>(columns: Vec<String>) -> QueryBuilder<'a> {
+    backend::subquery(&Sqlite, columns)
+}
+
+/// Inserts a row as `backend::insert` does.
+pub fn insert<'a, T: Table + Send>(
+    conn: &'a mut Connection,
+    table_row: &'a mut T,
+) -> InsertBuilder<'a, T> {
+    InsertBuilder::new(conn, table_row)
+}
+
+/// Inserts many rows within a single transaction as `backend::insert_many` does.
+pub async fn insert_many<T: Table + Send>(
+    conn: &mut Connection,
+    table_rows: &mut [T],
+) -> Result<u64> {
+    asynchronous::insert_many(conn, table_rows).await
+}
+
+pub fn update(conn: &mut Connection) -> UpdateBuilder<'_> {
+    UpdateBuilder::new(conn)
+}
+
+pub fn delete(conn: &mut Connection) -> DeleteBuilder<'_> {
+    DeleteBuilder::new(conn)
+}
+
+/// Open a connection to a remote database over the libsql protocol, e.g. a database
+/// hosted on Turso with `connect("libsql://app-org.turso.io", token)`.
+///
+/// Embedded replicas are not available, as the libsql library they need clashes
+/// with the sqlite library bundled by the sqlite module.
+pub async fn connect(url: &str, auth_token: &str) -> Result<Connection> {
+    let database = Builder::new_remote(url.to_string(), auth_token.to_string())
+        .build()
+        .await?;
+    let conn = database.connect()?;
+
+    Ok(conn)
+}
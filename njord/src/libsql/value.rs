@@ -0,0 +1,96 @@
+use crate::backend::asynchronous::{impl_async_builders, AsyncExecutor};
+use crate::backend::Row;
+use crate::dialect::{Dialect, Sqlite};
+use crate::error::{Error, Result};
+use crate::value::Value;
+
+use ::libsql::params::Params;
+use ::libsql::Connection;
+
+/// The primary result code of the violations of a constraint, as the ffi module of the
+/// driver is only built for local databases.
+const SQLITE_CONSTRAINT: i32 = 19;
+
+/// Runs the builders of the `backend` module on a connection to a libsql server.
+impl AsyncExecutor for Connection {
+    const DIALECT: &'static dyn Dialect = &Sqlite;
+
+    async fn execute(&mut self, statement: &str, params: Vec<Value>) -> Result<u64> {
+        Ok(Connection::execute(self, statement, bind_values(params)).await?)
+    }
+
+    async fn query(&mut self, query: &str, params: Vec<Value>) -> Result<Vec<Row>> {
+        let mut rows = Connection::query(self, query, bind_values(params)).await?;
+
+        let column_names: Vec<String> = (0..rows.column_count())
+            .map(|index| rows.column_name(index).unwrap_or_default().to_string())
+            .collect();
+
+        let mut fetched = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let mut values = Vec::with_capacity(column_names.len());
+            for (index, column) in column_names.iter().enumerate() {
+                values.push((column.clone(), row.get_value(index as i32)?.into()));
+            }
+            fetched.push(values);
+        }
+
+        Ok(fetched)
+    }
+
+    async fn execute_batch(&mut self, statement: &str) -> Result<()> {
+        Connection::execute(self, statement, Params::None).await?;
+
+        Ok(())
+    }
+}
+
+impl_async_builders!(Connection);
+
+/// The violations of a constraint, whose code is SQLITE_CONSTRAINT or one of its extended
+/// codes, are returned as `Error::Constraint`. A remote server reports the name of the
+/// code along with its message.
+impl From<::libsql::Error> for Error {
+    fn from(error: ::libsql::Error) -> Self {
+        let is_constraint = match &error {
+            ::libsql::Error::SqliteFailure(code, _) => code & 0xff == SQLITE_CONSTRAINT,
+            ::libsql::Error::RemoteSqliteFailure(code, _, _) => *code == SQLITE_CONSTRAINT,
+            ::libsql::Error::Hrana(error) => error.to_string().contains("SQLITE_CONSTRAINT"),
+            _ => false,
+        };
+
+        Error::from_driver(error, is_constraint)
+    }
+}
+
+impl From<::libsql::Value> for Value {
+    fn from(value: ::libsql::Value) -> Self {
+        match value {
+            ::libsql::Value::Null => Value::Null,
+            ::libsql::Value::Integer(value) => Value::Integer(value),
+            ::libsql::Value::Real(value) => Value::Real(value),
+            ::libsql::Value::Text(value) => Value::Text(value),
+            ::libsql::Value::Blob(value) => Value::Blob(value),
+        }
+    }
+}
+
+impl From<Value> for ::libsql::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => ::libsql::Value::Null,
+            Value::Integer(value) => ::libsql::Value::Integer(value),
+            Value::Real(value) => ::libsql::Value::Real(value),
+            Value::Text(value) => ::libsql::Value::Text(value),
+            Value::Blob(value) => ::libsql::Value::Blob(value),
+        }
+    }
+}
+
+fn bind_values(values: Vec<Value>) -> Params {
+    if values.is_empty() {
+        return Params::None;
+    }
+
+    Params::Positional(values.into_iter().map(Into::into).collect())
+}
@@ -0,0 +1,312 @@
+use crate::error::{Error, Result};
+#[cfg(feature = "metrics")]
+use crate::meter;
+use crate::middleware;
+use crate::statement::is_write;
+use crate::trace;
+use crate::value::Value;
+
+use std::cell::Cell;
+use std::sync::{PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+
+/// A statement run by one of the backends, passed to the query logger once it has run.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct QueryEvent<'a> {
+    /// The SQL of the statement, with the placeholders of its dialect.
+    pub sql: &'a str,
+    /// The values bound to its placeholders, which are empty for the raw queries of
+    /// `sqlite::query_as`, whose parameters are not njord values.
+    ///
+    /// The values of the columns marked with `#[njord(sensitive)]` are masked as the text
+    /// `***`.
+    pub params: &'a [Value],
+    /// The number of rows it returned or changed, which is `None` if it failed or is not
+    /// counted, e.g. for a DDL statement.
+    pub rows: Option<u64>,
+    /// The time it took to run, including fetching its rows, whether it succeeded or not.
+    pub duration: Duration,
+    /// The error of the statement, if it failed.
+    pub error: Option<&'a Error>,
+    /// Whether the statement was prepared from the statement cache of the connection
+    /// instead of being compiled, which is `None` if it was not prepared from the cache,
+    /// e.g. for a DDL statement or on a backend without one.
+    pub cache_hit: Option<bool>,
+}
+
+impl QueryEvent<'_> {
+    /// Get the statistics of the statement, whose rows are counted as written if its main
+    /// keyword is that of an INSERT, UPDATE, DELETE or REPLACE, even after the comments or
+    /// the `WITH` a middleware may have rewritten it with.
+    pub fn stats(&self) -> QueryStats {
+        let rows = self.rows.unwrap_or(0);
+        let (rows_read, rows_written) = match is_write(self.sql) {
+            true => (0, rows),
+            false => (rows, 0),
+        };
+
+        QueryStats {
+            duration: self.duration,
+            rows_read,
+            rows_written,
+            cache_hit: self.cache_hit,
+        }
+    }
+}
+
+/// The statistics of a statement that has run, from `QueryEvent::stats` or
+/// `last_query_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct QueryStats {
+    /// The time it took to run, including fetching its rows.
+    pub duration: Duration,
+    /// The number of rows a query returned.
+    pub rows_read: u64,
+    /// The number of rows an INSERT, UPDATE or DELETE changed.
+    pub rows_written: u64,
+    /// Whether the statement was prepared from the statement cache, as in `QueryEvent`.
+    pub cache_hit: Option<bool>,
+}
+
+thread_local! {
+    static CACHE_HIT: Cell<Option<bool>> = const { Cell::new(None) };
+    static LAST_QUERY_STATS: Cell<Option<QueryStats>> = const { Cell::new(None) };
+}
+
+/// Get the statistics of the last statement run on this thread, e.g. right after a `build`
+/// of the `sqlite` builders, or `None` if no statement has run on it.
+///
+/// A builder running several statements, e.g. `insert_many` in chunks, reports the
+/// statistics of its last one. The futures of the async backends report theirs on the
+/// thread that polled them, so their statistics are better read in the query logger with
+/// `QueryEvent::stats`.
+pub fn last_query_stats() -> Option<QueryStats> {
+    LAST_QUERY_STATS.get()
+}
+
+/// Records whether the statement being run was prepared from the statement cache, as
+/// reported in its `QueryEvent`.
+#[cfg(feature = "sqlite")]
+pub(crate) fn record_cache_hit(hit: bool) {
+    CACHE_HIT.set(Some(hit));
+}
+
+/// The result of a statement, counted as the rows it returned or changed.
+pub(crate) trait RowCount {
+    fn row_count(&self) -> Option<u64>;
+}
+
+impl RowCount for () {
+    fn row_count(&self) -> Option<u64> {
+        None
+    }
+}
+
+macro_rules! impl_row_count_for_changed_rows {
+    ($($t:ty),*) => {
+        $(impl RowCount for $t {
+            fn row_count(&self) -> Option<u64> {
+                Some(*self as u64)
+            }
+        })*
+    };
+}
+
+impl_row_count_for_changed_rows!(usize, u64);
+
+impl<T> RowCount for Vec<T> {
+    fn row_count(&self) -> Option<u64> {
+        Some(self.len() as u64)
+    }
+}
+
+/// A row that may not exist, e.g. the rowid of an insert skipped with `do_nothing`.
+impl<T> RowCount for Option<T> {
+    fn row_count(&self) -> Option<u64> {
+        Some(self.is_some() as u64)
+    }
+}
+
+/// The values of the only row of a statement, e.g. a `COUNT(*)` or an inserted rowid.
+impl<T, const N: usize> RowCount for [T; N] {
+    fn row_count(&self) -> Option<u64> {
+        Some(1)
+    }
+}
+
+/// The changed rows of an INSERT, along with the key it generated.
+impl<T> RowCount for (u64, T) {
+    fn row_count(&self) -> Option<u64> {
+        Some(self.0)
+    }
+}
+
+static QUERY_LOGGER: RwLock<fn(&QueryEvent)> = RwLock::new(log_query);
+
+/// Replaces the function every statement is reported to once it has run, which is
+/// `log_query` by default.
+///
+/// ```
+/// njord::set_query_logger(|event| {
+///     eprintln!("{} took {:?}", event.sql, event.duration);
+/// });
+/// ```
+pub fn set_query_logger(logger: fn(&QueryEvent)) {
+    *QUERY_LOGGER.write().unwrap_or_else(PoisonError::into_inner) = logger;
+}
+
+/// The default query logger, which logs the statement along with its parameters and
+/// duration with the `log` crate at debug level.
+pub fn log_query(event: &QueryEvent) {
+    debug!("{} {:?} ({:?})", event.sql, event.params, event.duration);
+}
+
+/// A statement that ran for longer than the threshold of the slow query log, passed to its
+/// callback.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct SlowQuery<'a> {
+    /// The SQL of the statement, with the placeholders of its dialect.
+    pub sql: &'a str,
+    /// The type of each value bound to its placeholders, e.g. `TEXT`, whose values are
+    /// redacted so that the values of the rows do not end up in the log.
+    pub params: Vec<&'static str>,
+    /// The time it took to run, including fetching its rows, whether it succeeded or not.
+    pub duration: Duration,
+}
+
+#[derive(Clone, Copy)]
+struct SlowQueryLog {
+    threshold: Duration,
+    callback: fn(&SlowQuery),
+}
+
+static SLOW_QUERY_LOG: RwLock<Option<SlowQueryLog>> = RwLock::new(None);
+
+/// Reports every statement that runs for longer than `threshold`, e.g. 200ms, to
+/// `callback`, along with its duration and redacted parameters, e.g. `log_slow_query`.
+///
+/// The statements are reported to the query logger as well.
+pub fn set_slow_query_log(threshold: Duration, callback: fn(&SlowQuery)) {
+    *SLOW_QUERY_LOG
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(SlowQueryLog {
+        threshold,
+        callback,
+    });
+}
+
+/// Stops reporting the statements of the slow query log set with `set_slow_query_log`.
+pub fn clear_slow_query_log() {
+    *SLOW_QUERY_LOG
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// A slow query log callback, which logs the statement along with its duration and
+/// redacted parameters with the `log` crate at warn level.
+pub fn log_slow_query(query: &SlowQuery) {
+    warn!(
+        "Slow query ({:?}): {} {:?}",
+        query.duration, query.sql, query.params
+    );
+}
+
+/// Get the type of a parameter in place of its value.
+fn redact(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "NULL",
+        Value::Integer(_) => "INTEGER",
+        Value::Real(_) => "REAL",
+        Value::Text(_) => "TEXT",
+        Value::Blob(_) => "BLOB",
+    }
+}
+
+/// Runs a statement with `f`, given its SQL as rewritten by the middleware, and reports
+/// it to the query logger.
+pub(crate) fn run<R: RowCount, E: Into<Error>>(
+    sql: &str,
+    params: &[Value],
+    f: impl FnOnce(&str) -> std::result::Result<R, E>,
+) -> Result<R> {
+    let sql = middleware::before(sql, params)?;
+
+    CACHE_HIT.set(None);
+
+    let start = Instant::now();
+    let result = trace::execute(&sql, || f(&sql).map_err(Into::into));
+    let duration = start.elapsed();
+
+    report(&sql, params, &result, duration, CACHE_HIT.take());
+
+    result
+}
+
+/// Runs a statement with the future of `f`, given its SQL as rewritten by the middleware,
+/// and reports it to the query logger.
+#[cfg(any(feature = "libsql", feature = "mssql"))]
+pub(crate) async fn run_async<R: RowCount, F>(
+    sql: &str,
+    params: &[Value],
+    f: impl FnOnce(String) -> F,
+) -> Result<R>
+where
+    F: std::future::Future<Output = Result<R>>,
+{
+    let sql = middleware::before(sql, params)?.into_owned();
+
+    let start = Instant::now();
+    let result = trace::execute_async(&sql, f(sql.clone())).await;
+
+    // the async backends do not prepare their statements from a cache of njord
+    report(&sql, params, &result, start.elapsed(), None);
+
+    result
+}
+
+fn report<R: RowCount>(
+    sql: &str,
+    params: &[Value],
+    result: &Result<R>,
+    duration: Duration,
+    cache_hit: Option<bool>,
+) {
+    #[cfg(feature = "metrics")]
+    meter::record(sql, result, duration);
+
+    let logger = *QUERY_LOGGER.read().unwrap_or_else(PoisonError::into_inner);
+
+    let event = QueryEvent {
+        sql,
+        params,
+        rows: result.as_ref().ok().and_then(RowCount::row_count),
+        duration,
+        error: result.as_ref().err(),
+        cache_hit,
+    };
+
+    LAST_QUERY_STATS.set(Some(event.stats()));
+
+    logger(&event);
+    middleware::after(&event);
+
+    // the log is copied out of the lock, so that its callback may replace it
+    let slow_query_log = *SLOW_QUERY_LOG
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    if let Some(slow_query_log) = slow_query_log {
+        if duration > slow_query_log.threshold {
+            (slow_query_log.callback)(&SlowQuery {
+                sql,
+                params: params.iter().map(redact).collect(),
+                duration,
+            });
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::statement::describe;
+
+use std::sync::Once;
+use std::time::Duration;
+
+use metrics::{counter, describe_counter, describe_histogram, histogram, Unit};
+
+/// The counter of the statements run, by `table` and `kind`.
+const QUERIES: &str = "njord_queries_total";
+/// The counter of the statements that failed, by `table` and `kind`.
+const QUERY_ERRORS: &str = "njord_query_errors_total";
+/// The histogram of the durations of the statements in seconds, by `table` and `kind`.
+const QUERY_DURATION: &str = "njord_query_duration_seconds";
+
+static DESCRIBE: Once = Once::new();
+
+/// Records a statement that has run to the metrics recorder installed by the application,
+/// e.g. a Prometheus exporter, labeled with its `table` and `kind` as in the tracing spans.
+pub(crate) fn record<R, E>(sql: &str, result: &std::result::Result<R, E>, duration: Duration) {
+    // the metrics are described to the recorder installed when the first statement runs
+    DESCRIBE.call_once(|| {
+        describe_counter!(QUERIES, "The number of statements run by njord.");
+        describe_counter!(
+            QUERY_ERRORS,
+            "The number of statements run by njord that failed."
+        );
+        describe_histogram!(
+            QUERY_DURATION,
+            Unit::Seconds,
+            "The duration of the statements run by njord."
+        );
+    });
+
+    let (kind, table) = describe(sql);
+    let labels = [
+        ("table", table.unwrap_or_default().to_string()),
+        ("kind", kind),
+    ];
+
+    counter!(QUERIES, &labels).increment(1);
+    histogram!(QUERY_DURATION, &labels).record(duration.as_secs_f64());
+
+    if result.is_err() {
+        counter!(QUERY_ERRORS, &labels).increment(1);
+    }
+}
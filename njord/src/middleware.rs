@@ -0,0 +1,77 @@
+use crate::error::Result;
+use crate::logger::QueryEvent;
+use crate::value::Value;
+
+use std::borrow::Cow;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// A middleware of the statements run by every backend, added with `add_middleware`, e.g.
+/// to tag the statements with the id of a request, to filter the rows of a tenant or to
+/// block dangerous statements.
+pub trait Middleware: Send + Sync {
+    /// Inspects a statement before it runs, and may rewrite its SQL, e.g. by appending a
+    /// `/* request-id */` comment. The values of its placeholders are bound as they are, so
+    /// a rewritten statement keeps them, and are given with the values of the columns
+    /// marked with `#[njord(sensitive)]` masked, as in `QueryEvent`.
+    ///
+    /// An error blocks the statement, which fails with it without running, e.g. with
+    /// `Error::Blocked`.
+    fn before(&self, _sql: &mut String, _params: &[Value]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Observes a statement once it has run, with the SQL it was run with, and its error if
+    /// it failed.
+    fn after(&self, _event: &QueryEvent) {}
+}
+
+static MIDDLEWARE: RwLock<Vec<Arc<dyn Middleware>>> = RwLock::new(Vec::new());
+
+/// Adds a middleware to the end of the chain of middleware every statement runs through,
+/// whose `before` is called in the order they were added.
+pub fn add_middleware<M: Middleware + 'static>(middleware: M) {
+    MIDDLEWARE
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Arc::new(middleware));
+}
+
+/// Removes every middleware added with `add_middleware`.
+pub fn clear_middleware() {
+    MIDDLEWARE
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+/// Runs the `before` of every middleware on a statement, returning the SQL to run it with.
+pub(crate) fn before<'a>(sql: &'a str, params: &[Value]) -> Result<Cow<'a, str>> {
+    let chain = chain();
+
+    // the SQL is only copied to be rewritten if there is a middleware
+    if chain.is_empty() {
+        return Ok(Cow::Borrowed(sql));
+    }
+
+    let mut sql = sql.to_string();
+    for middleware in &chain {
+        middleware.before(&mut sql, params)?;
+    }
+
+    Ok(Cow::Owned(sql))
+}
+
+/// Runs the `after` of every middleware on a statement that has run.
+pub(crate) fn after(event: &QueryEvent) {
+    for middleware in chain() {
+        middleware.after(event);
+    }
+}
+
+/// Get the chain of middleware, copied out of the lock so that a middleware may add more.
+fn chain() -> Vec<Arc<dyn Middleware>> {
+    MIDDLEWARE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
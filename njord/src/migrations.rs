@@ -0,0 +1,298 @@
+use std::collections::HashSet;
+
+use rusqlite::Connection;
+
+use log::info;
+
+use crate::error::Result;
+use crate::logger;
+use crate::sqlite::key::misuse;
+
+/// The table tracking the versions of the applied migrations.
+pub const MIGRATIONS_TABLE: &str = "_njord_migrations";
+
+/// A function applying or reverting a migration.
+type StepFunction = Box<dyn Fn(&Connection) -> Result<()>>;
+
+/// A step of a migration, either SQL or a Rust function.
+enum Step {
+    Sql(String),
+    Function(StepFunction),
+}
+
+impl Step {
+    fn run(&self, conn: &Connection) -> Result<()> {
+        match self {
+            Step::Sql(sql) => logger::run(sql, &[], |sql| conn.execute_batch(sql)),
+            Step::Function(function) => function(conn),
+        }
+    }
+}
+
+/// A versioned change of the database schema, applied by its `up` step and reverted
+/// by its optional `down` step.
+pub struct Migration {
+    version: i64,
+    name: String,
+    up: Step,
+    down: Option<Step>,
+}
+
+impl Migration {
+    /// Creates a migration applied by the given SQL, which may hold several statements.
+    pub fn new(version: i64, name: &str, up: &str) -> Self {
+        Migration {
+            version,
+            name: name.to_string(),
+            up: Step::Sql(up.to_string()),
+            down: None,
+        }
+    }
+
+    /// Creates a migration applied by the given function, e.g. to transform rows in Rust.
+    pub fn from_fn<F>(version: i64, name: &str, up: F) -> Self
+    where
+        F: Fn(&Connection) -> Result<()> + 'static,
+    {
+        Migration {
+            version,
+            name: name.to_string(),
+            up: Step::Function(Box::new(up)),
+            down: None,
+        }
+    }
+
+    /// Sets the SQL reverting the migration.
+    pub fn down(mut self, down: &str) -> Self {
+        self.down = Some(Step::Sql(down.to_string()));
+        self
+    }
+
+    /// Sets the function reverting the migration.
+    pub fn down_fn<F>(mut self, down: F) -> Self
+    where
+        F: Fn(&Connection) -> Result<()> + 'static,
+    {
+        self.down = Some(Step::Function(Box::new(down)));
+        self
+    }
+
+    /// Get the version of the migration.
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// Get the name of the migration.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the migration can be reverted.
+    pub fn is_reversible(&self) -> bool {
+        self.down.is_some()
+    }
+}
+
+/// Runs migrations in the order of their versions, tracking the applied versions
+/// in the `_njord_migrations` table.
+///
+/// # Example
+///
+/// ```rust
+/// use njord::migrations::{Migration, Migrator};
+///
+/// let mut conn = njord::sqlite::open_in_memory().unwrap();
+/// let migrator = Migrator::new()
+///     .migration(Migration::new(1, "create_users", "CREATE TABLE users (id INTEGER PRIMARY KEY)"))
+///     .migration(Migration::new(2, "add_name", "ALTER TABLE users ADD COLUMN name TEXT"));
+///
+/// migrator.migrate(&mut conn).unwrap();
+/// ```
+pub struct Migrator {
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    pub fn new() -> Self {
+        Migrator {
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Adds a migration, which can be added in any order.
+    pub fn migration(mut self, migration: Migration) -> Self {
+        self.migrations.push(migration);
+        self.migrations.sort_by_key(|migration| migration.version);
+        self
+    }
+
+    /// Get the migrations, ordered by their versions.
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Get the versions of the applied migrations, in ascending order.
+    pub fn applied(&self, conn: &Connection) -> Result<Vec<i64>> {
+        create_migrations_table(conn)?;
+
+        let query = format!("SELECT version FROM {} ORDER BY version", MIGRATIONS_TABLE);
+        let mut stmt = conn.prepare(query.as_str())?;
+        let versions = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<i64>>>()?;
+
+        Ok(versions)
+    }
+
+    /// Get the migrations that are not applied yet, in the order they are applied.
+    pub fn pending(&self, conn: &Connection) -> Result<Vec<&Migration>> {
+        let applied: HashSet<i64> = self.applied(conn)?.into_iter().collect();
+
+        Ok(self
+            .migrations
+            .iter()
+            .filter(|migration| !applied.contains(&migration.version))
+            .collect())
+    }
+
+    /// Applies the pending migrations in the order of their versions, e.g. at startup.
+    ///
+    /// Each migration is applied and recorded within its own transaction, so a failing
+    /// migration is rolled back as a whole and leaves the migrations before it applied.
+    /// Returns the number of applied migrations.
+    pub fn migrate(&self, conn: &mut Connection) -> Result<usize> {
+        self.check_versions()?;
+
+        let pending: Vec<&Migration> = self.pending(conn)?;
+
+        for migration in &pending {
+            // create a transaction
+            let tx = conn.savepoint()?;
+
+            migration.up.run(&tx)?;
+
+            let statement = format!(
+                "INSERT INTO {} (version, name) VALUES (?, ?)",
+                MIGRATIONS_TABLE
+            );
+            tx.execute(
+                statement.as_str(),
+                rusqlite::params![migration.version, migration.name],
+            )?;
+
+            // commit the transaction
+            tx.commit()?;
+
+            info!(
+                "Applied migration {} {}, done.",
+                migration.version, migration.name
+            );
+        }
+
+        Ok(pending.len())
+    }
+
+    /// Reverts the last `count` applied migrations, in descending order of their versions.
+    ///
+    /// Each migration is reverted and unrecorded within its own transaction. Nothing is
+    /// reverted if one of the migrations is not reversible or not known to the migrator.
+    /// Returns the number of reverted migrations.
+    pub fn migrate_down(&self, conn: &mut Connection, count: usize) -> Result<usize> {
+        let applied = self.applied(conn)?;
+        let versions: Vec<i64> = applied.into_iter().rev().take(count).collect();
+
+        self.revert(conn, &versions)
+    }
+
+    /// Reverts the applied migrations newer than the given version, in descending order
+    /// of their versions, so the given version is the last applied one.
+    ///
+    /// Like `migrate_down`, nothing is reverted if one of the migrations is not reversible.
+    /// Returns the number of reverted migrations.
+    pub fn rollback_to(&self, conn: &mut Connection, version: i64) -> Result<usize> {
+        let applied = self.applied(conn)?;
+        let versions: Vec<i64> = applied
+            .into_iter()
+            .rev()
+            .filter(|applied| *applied > version)
+            .collect();
+
+        self.revert(conn, &versions)
+    }
+
+    fn revert(&self, conn: &mut Connection, versions: &[i64]) -> Result<usize> {
+        self.check_versions()?;
+
+        // every migration is checked first, so a rollback is not stopped halfway
+        let mut migrations = Vec::new();
+        for version in versions {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|migration| migration.version == *version)
+                .ok_or_else(|| misuse(format!("applied migration {} is unknown", version)))?;
+
+            let down = migration.down.as_ref().ok_or_else(|| {
+                misuse(format!(
+                    "migration {} {} is not reversible",
+                    migration.version, migration.name
+                ))
+            })?;
+
+            migrations.push((migration, down));
+        }
+
+        for (migration, down) in &migrations {
+            // create a transaction
+            let tx = conn.savepoint()?;
+
+            down.run(&tx)?;
+
+            let statement = format!("DELETE FROM {} WHERE version = ?", MIGRATIONS_TABLE);
+            tx.execute(statement.as_str(), [migration.version])?;
+
+            // commit the transaction
+            tx.commit()?;
+
+            info!(
+                "Reverted migration {} {}, done.",
+                migration.version, migration.name
+            );
+        }
+
+        Ok(migrations.len())
+    }
+
+    /// Fails if two migrations have the same version.
+    fn check_versions(&self) -> Result<()> {
+        let duplicate = self
+            .migrations
+            .windows(2)
+            .find(|pair| pair[0].version == pair[1].version);
+
+        match duplicate {
+            Some(pair) => Err(misuse(format!(
+                "migrations {} and {} have the same version {}",
+                pair[0].name, pair[1].name, pair[0].version
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for Migrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn create_migrations_table(conn: &Connection) -> Result<()> {
+    let statement = format!(
+        "CREATE TABLE IF NOT EXISTS {} (version INTEGER PRIMARY KEY, name TEXT NOT NULL, applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP)",
+        MIGRATIONS_TABLE
+    );
+
+    conn.execute(statement.as_str(), [])?;
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use crate::backend::asynchronous;
+use crate::table::Table;
+
+use super::{Connection, Result};
+
+/// Creates the table of `T` as `backend::create_table` does, failing if it already exists.
+pub async fn create_table<T: Table + Default>(conn: &mut Connection) -> Result<()> {
+    asynchronous::create_table::<_, T>(conn, false).await
+}
+
+/// Creates the table of `T` as `backend::create_table` does, unless it already exists.
+pub async fn create_table_if_not_exists<T: Table + Default>(conn: &mut Connection) -> Result<()> {
+    asynchronous::create_table::<_, T>(conn, true).await
+}
+
+/// Creates the indexes of `T` as `backend::create_indexes` does.
+pub async fn create_indexes<T: Table + Default>(conn: &mut Connection) -> Result<()> {
+    asynchronous::create_indexes::<_, T>(conn).await
+}
+
+/// Drops the table of `T`, failing if it does not exist.
+pub async fn drop_table<T: Table + Default>(conn: &mut Connection) -> Result<()> {
+    asynchronous::drop_table::<_, T>(conn, false).await
+}
+
+/// Drops the table of `T`, unless it does not exist.
+pub async fn drop_table_if_exists<T: Table + Default>(conn: &mut Connection) -> Result<()> {
+    asynchronous::drop_table::<_, T>(conn, true).await
+}
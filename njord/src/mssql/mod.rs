@@ -0,0 +1,77 @@
+use crate::backend::{self, asynchronous};
+use crate::dialect::MsSql;
+use crate::table::Table;
+
+use ::tiberius::{Client, Config};
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+
+pub mod ddl;
+pub use ddl::{
+    create_indexes, create_table, create_table_if_not_exists, drop_table, drop_table_if_exists,
+};
+mod value;
+
+pub use crate::condition::Condition;
+pub use crate::join::JoinType;
+
+/// A connection to SQL Server over TCP, opened with `connect`.
+pub type Connection = Client<Compat<TcpStream>>;
+
+/// The error of a query run against SQL Server, with the `tiberius::error::Error` of the driver as its source.
+pub use crate::error::{Error, Result};
+
+/// A query run against SQL Server, whose `build`, `first` and `count` return futures.
+pub type QueryBuilder<'a> = backend::QueryBuilder<'a, Connection>;
+pub type InsertBuilder<'a, T> = backend::InsertBuilder<'a, Connection, T>;
+pub type UpdateBuilder<'a> = backend::UpdateBuilder<'a, Connection>;
+pub type DeleteBuilder<'a> = backend::DeleteBuilder<'a, Connection>;
+
+pub fn select(conn: &mut Connection, columns: Vec<String>) -> QueryBuilder<'_> {
+    QueryBuilder::with_dialect(Some(conn), &MsSql, columns)
+}
+
+/// Creates a subquery of the SQL Server dialect, as `backend::subquery` does.
+pub fn subquery<'a>(columns: Vec<String>) -> QueryBuilder<'a> {
+    backend::subquery(&MsSql, columns)
+}
+
+/// Inserts a row as `backend::insert` does.
+pub fn insert<'a, T: Table + Send>(
+    conn: &'a mut Connection,
+    table_row: &'a mut T,
+) -> InsertBuilder<'a, T> {
+    InsertBuilder::new(conn, table_row)
+}
+
+/// Inserts many rows within a single transaction as `backend::insert_many` does.
+pub async fn insert_many<T: Table + Send>(
+    conn: &mut Connection,
+    table_rows: &mut [T],
+) -> Result<u64> {
+    asynchronous::insert_many(conn, table_rows).await
+}
+
+pub fn update(conn: &mut Connection) -> UpdateBuilder<'_> {
+    UpdateBuilder::new(conn)
+}
+
+pub fn delete(conn: &mut Connection) -> DeleteBuilder<'_> {
+    DeleteBuilder::new(conn)
+}
+
+/// Open a connection to SQL Server from an ADO.NET connection string, e.g.
+/// `connect("server=tcp:localhost,1433;user=sa;password=...;TrustServerCertificate=true")`.
+pub async fn connect(connection_string: &str) -> Result<Connection> {
+    let config = Config::from_ado_string(connection_string)?;
+
+    let tcp = TcpStream::connect(config.get_addr())
+        .await
+        .map_err(::tiberius::error::Error::from)?;
+    tcp.set_nodelay(true)
+        .map_err(::tiberius::error::Error::from)?;
+
+    let conn = Client::connect(config, tcp.compat_write()).await?;
+
+    Ok(conn)
+}
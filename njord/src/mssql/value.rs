@@ -0,0 +1,153 @@
+use crate::backend::asynchronous::{impl_async_builders, AsyncExecutor};
+use crate::backend::Row;
+use crate::dialect::{Dialect, MsSql};
+use crate::error::{Error, Result};
+use crate::value::Value;
+
+use std::borrow::Cow;
+
+use ::tiberius::time::chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use ::tiberius::{ColumnData, FromSql, IntoSql, Query};
+
+use super::Connection;
+
+/// Runs the builders of the `backend` module on a connection to SQL Server.
+impl AsyncExecutor for Connection {
+    const DIALECT: &'static dyn Dialect = &MsSql;
+
+    /// A failed statement does not always abort the transaction in SQL Server, so it is
+    /// only rolled back if it is still open.
+    const TRANSACTION: [&'static str; 3] = [
+        "BEGIN TRANSACTION",
+        "COMMIT TRANSACTION",
+        "IF @@TRANCOUNT > 0 ROLLBACK TRANSACTION",
+    ];
+
+    async fn execute(&mut self, statement: &str, params: Vec<Value>) -> Result<u64> {
+        let result = bind_values(statement.to_string(), params)
+            .execute(self)
+            .await?;
+
+        Ok(result.total())
+    }
+
+    /// Returns the rows of the first result of the query.
+    async fn query(&mut self, query: &str, params: Vec<Value>) -> Result<Vec<Row>> {
+        let rows = bind_values(query.to_string(), params)
+            .query(self)
+            .await?
+            .into_first_result()
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let column_names: Vec<String> = row
+                    .columns()
+                    .iter()
+                    .map(|column| column.name().to_string())
+                    .collect();
+
+                column_names
+                    .into_iter()
+                    .zip(row)
+                    .map(|(column, data)| (column, data.into()))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// The statements without parameters are sent as a batch, outside of `sp_executesql`.
+    async fn execute_batch(&mut self, statement: &str) -> Result<()> {
+        self.simple_query(statement).await?.into_results().await?;
+
+        Ok(())
+    }
+}
+
+impl_async_builders!(Connection);
+
+/// The errors of SQL Server raised by a duplicate key (2601 and 2627), a NULL in a NOT NULL
+/// column (515) or a foreign key or CHECK constraint (547) are returned as
+/// `Error::Constraint`.
+impl From<::tiberius::error::Error> for Error {
+    fn from(error: ::tiberius::error::Error) -> Self {
+        let is_constraint = match &error {
+            ::tiberius::error::Error::Server(error) => {
+                matches!(error.code(), 515 | 547 | 2601 | 2627)
+            }
+            _ => false,
+        };
+
+        Error::from_driver(error, is_constraint)
+    }
+}
+
+fn bind_values<'a>(statement: String, values: Vec<Value>) -> Query<'a> {
+    let mut query = Query::new(statement);
+
+    for value in values {
+        query.bind(value);
+    }
+
+    query
+}
+
+/// Converts a column of a row into the value the struct fields are set from.
+///
+/// Decimals and GUIDs are converted into text, as are dates and times, in the format
+/// sqlite stores them as.
+impl From<ColumnData<'static>> for Value {
+    fn from(data: ColumnData<'static>) -> Self {
+        let value = match &data {
+            ColumnData::U8(value) => value.map(|value| Value::Integer(i64::from(value))),
+            ColumnData::I16(value) => value.map(|value| Value::Integer(i64::from(value))),
+            ColumnData::I32(value) => value.map(|value| Value::Integer(i64::from(value))),
+            ColumnData::I64(value) => value.map(Value::Integer),
+            ColumnData::F32(value) => value.map(|value| Value::Real(f64::from(value))),
+            ColumnData::F64(value) => value.map(Value::Real),
+            ColumnData::Bit(value) => value.map(|value| Value::Integer(value as i64)),
+            ColumnData::String(value) => value.as_ref().map(|value| Value::Text(value.to_string())),
+            ColumnData::Guid(value) => value.map(|value| Value::Text(value.to_string())),
+            ColumnData::Binary(value) => value.as_ref().map(|value| Value::Blob(value.to_vec())),
+            ColumnData::Numeric(value) => value.map(|value| Value::Text(value.to_string())),
+            ColumnData::Xml(value) => value.as_ref().map(|value| Value::Text(value.to_string())),
+            ColumnData::DateTime(_) | ColumnData::SmallDateTime(_) | ColumnData::DateTime2(_) => {
+                NaiveDateTime::from_sql(&data)
+                    .ok()
+                    .flatten()
+                    .map(format_text)
+            }
+            ColumnData::Date(_) => NaiveDate::from_sql(&data).ok().flatten().map(format_text),
+            ColumnData::Time(_) => NaiveTime::from_sql(&data).ok().flatten().map(format_text),
+            // a date and time with an offset is converted into UTC
+            ColumnData::DateTimeOffset(_) => DateTime::<Utc>::from_sql(&data)
+                .ok()
+                .flatten()
+                .map(|value| format_text(value.naive_utc())),
+        };
+
+        value.unwrap_or(Value::Null)
+    }
+}
+
+/// Formats a date or time the way chrono displays it, which is the format of sqlite,
+/// e.g. `YYYY-MM-DD HH:MM:SS` with the fraction of a second if there is one.
+fn format_text(value: impl std::fmt::Display) -> Value {
+    Value::Text(value.to_string())
+}
+
+/// Converts a value into the parameter of a statement. SQL Server converts between text
+/// and numbers itself, so the value is bound as it is, and NULL as a BIGINT, which
+/// converts to the other types of the columns.
+impl<'a> IntoSql<'a> for Value {
+    fn into_sql(self) -> ColumnData<'a> {
+        match self {
+            Value::Null => ColumnData::I64(None),
+            Value::Integer(value) => ColumnData::I64(Some(value)),
+            Value::Real(value) => ColumnData::F64(Some(value)),
+            Value::Text(value) => ColumnData::String(Some(Cow::Owned(value))),
+            Value::Blob(value) => ColumnData::Binary(Some(Cow::Owned(value))),
+        }
+    }
+}
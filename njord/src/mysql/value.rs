@@ -0,0 +1,193 @@
+use crate::backend::{Executor, Row as BackendRow, SAVEPOINT_STATEMENTS};
+use crate::dialect::{Dialect, MySql};
+use crate::error::{Error, Result};
+use crate::value::Value;
+
+use ::mysql::consts::ColumnType;
+use ::mysql::prelude::Queryable;
+use ::mysql::{Column, Conn, Params, PooledConn, Row, Statement, Transaction};
+
+/// The character set MySQL reports for binary columns.
+const BINARY_CHARACTER_SET: u16 = 63;
+
+/// Converts a column of a row into the value the struct fields are set from.
+///
+/// Text and binary strings are both sent as bytes, told apart by the character set of
+/// the column, and DATE columns are sent as a date and time at midnight.
+pub(crate) fn get_value(column: &Column, value: ::mysql::Value) -> Value {
+    match value {
+        ::mysql::Value::Bytes(bytes) if is_binary(column) => Value::Blob(bytes),
+        ::mysql::Value::Date(year, month, day, ..)
+            if matches!(column.column_type(), ColumnType::MYSQL_TYPE_DATE) =>
+        {
+            Value::Text(format!("{:04}-{:02}-{:02}", year, month, day))
+        }
+        value => value.into(),
+    }
+}
+
+/// Converts a value of the driver, with bytes read as text unless they are not UTF-8.
+/// Dates and times are converted into the text sqlite stores them as.
+impl From<::mysql::Value> for Value {
+    fn from(value: ::mysql::Value) -> Self {
+        match value {
+            ::mysql::Value::NULL => Value::Null,
+            ::mysql::Value::Int(value) => Value::Integer(value),
+            ::mysql::Value::UInt(value) => Value::Integer(value as i64),
+            ::mysql::Value::Float(value) => Value::Real(f64::from(value)),
+            ::mysql::Value::Double(value) => Value::Real(value),
+            ::mysql::Value::Bytes(bytes) => match String::from_utf8(bytes) {
+                Ok(text) => Value::Text(text),
+                Err(error) => Value::Blob(error.into_bytes()),
+            },
+            ::mysql::Value::Date(year, month, day, hour, minute, second, micros) => {
+                let date = format!("{:04}-{:02}-{:02}", year, month, day);
+
+                Value::Text(match micros {
+                    0 => format!("{} {:02}:{:02}:{:02}", date, hour, minute, second),
+                    _ => format!(
+                        "{} {:02}:{:02}:{:02}.{:06}",
+                        date, hour, minute, second, micros
+                    ),
+                })
+            }
+            ::mysql::Value::Time(negative, days, hours, minutes, seconds, _) => {
+                let sign = if negative { "-" } else { "" };
+                let hours = days * 24 + u32::from(hours);
+
+                Value::Text(format!(
+                    "{}{:02}:{:02}:{:02}",
+                    sign, hours, minutes, seconds
+                ))
+            }
+        }
+    }
+}
+
+/// Converts a value into the parameter of a statement. MySQL converts between text and
+/// numbers itself, so the value is bound as it is.
+impl From<Value> for ::mysql::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => ::mysql::Value::NULL,
+            Value::Integer(value) => ::mysql::Value::Int(value),
+            Value::Real(value) => ::mysql::Value::Double(value),
+            Value::Text(value) => ::mysql::Value::Bytes(value.into_bytes()),
+            Value::Blob(value) => ::mysql::Value::Bytes(value),
+        }
+    }
+}
+
+fn is_binary(column: &Column) -> bool {
+    let is_string = matches!(
+        column.column_type(),
+        ColumnType::MYSQL_TYPE_TINY_BLOB
+            | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+            | ColumnType::MYSQL_TYPE_LONG_BLOB
+            | ColumnType::MYSQL_TYPE_BLOB
+            | ColumnType::MYSQL_TYPE_VAR_STRING
+            | ColumnType::MYSQL_TYPE_VARCHAR
+            | ColumnType::MYSQL_TYPE_STRING
+    );
+
+    // numeric columns such as DECIMAL are reported with the binary character set too
+    is_string && column.character_set() == BINARY_CHARACTER_SET
+}
+
+fn bind_values(values: &[Value]) -> Params {
+    if values.is_empty() {
+        return Params::Empty;
+    }
+
+    Params::Positional(values.iter().cloned().map(Into::into).collect())
+}
+
+/// The violations of an integrity constraint, whose SQLSTATE is of class 23, are
+/// returned as `Error::Constraint`.
+impl From<::mysql::Error> for Error {
+    fn from(error: ::mysql::Error) -> Self {
+        let is_constraint = match &error {
+            ::mysql::Error::MySqlError(error) => error.state.starts_with("23"),
+            _ => false,
+        };
+
+        Error::from_driver(error, is_constraint)
+    }
+}
+
+/// Implements `Executor` for the connections of the driver, so the builders of the
+/// `backend` module run on a connection, a pooled connection or within a transaction,
+/// whose transaction nests with a savepoint.
+macro_rules! impl_executor {
+    ($conn:ty, $transaction_statements:expr) => {
+        impl Executor for $conn {
+            type Statement = Statement;
+
+            fn dialect(&self) -> &'static dyn Dialect {
+                &MySql
+            }
+
+            fn prepare(&mut self, statement: &str) -> Result<Statement> {
+                Ok(self.prep(statement)?)
+            }
+
+            fn execute(&mut self, statement: &Statement, params: &[Value]) -> Result<u64> {
+                let result = self.exec_iter(statement, bind_values(params))?;
+
+                Ok(result.affected_rows())
+            }
+
+            fn query(
+                &mut self,
+                statement: &Statement,
+                params: &[Value],
+            ) -> Result<Vec<BackendRow>> {
+                let rows = self
+                    .exec_iter(statement, bind_values(params))?
+                    .collect::<::mysql::Result<Vec<Row>>>()?;
+
+                Ok(rows.into_iter().map(get_row_values).collect())
+            }
+
+            /// The key is read from the `LAST_INSERT_ID()` reported by the statement, as
+            /// MySQL has no RETURNING clause. An ignored row reports no key.
+            fn execute_insert(
+                &mut self,
+                statement: &Statement,
+                params: &[Value],
+            ) -> Result<(u64, Option<Value>)> {
+                let result = self.exec_iter(statement, bind_values(params))?;
+                let id = result
+                    .last_insert_id()
+                    .filter(|id| *id > 0)
+                    .map(|id| Value::Integer(id as i64));
+
+                Ok((result.affected_rows(), id))
+            }
+
+            /// The transaction statements cannot be prepared in MySQL, so they are sent as text.
+            fn execute_batch(&mut self, statement: &str) -> Result<()> {
+                Ok(self.query_drop(statement)?)
+            }
+
+            fn transaction_statements(&self) -> [&'static str; 3] {
+                $transaction_statements
+            }
+        }
+    };
+}
+
+impl_executor!(Conn, ["START TRANSACTION", "COMMIT", "ROLLBACK"]);
+impl_executor!(PooledConn, ["START TRANSACTION", "COMMIT", "ROLLBACK"]);
+impl_executor!(Transaction<'_>, SAVEPOINT_STATEMENTS);
+
+/// Get the names and values of the columns of a row.
+fn get_row_values(row: Row) -> BackendRow {
+    let columns = row.columns();
+
+    columns
+        .iter()
+        .zip(row.unwrap())
+        .map(|(column, value)| (column.name_str().to_string(), get_value(column, value)))
+        .collect()
+}
@@ -0,0 +1,242 @@
+use crate::error::Result;
+use crate::sqlite::ConnectionBuilder;
+
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use rusqlite::{ffi, Connection};
+
+use log::info;
+
+/// Opens the connections of a pool.
+type ConnectionFactory = Box<dyn Fn() -> Result<Connection> + Send + Sync>;
+
+/// Options of a connection `Pool`.
+pub struct PoolOptions {
+    max_size: usize,
+    min_idle: usize,
+    timeout: Duration,
+}
+
+impl PoolOptions {
+    pub fn new() -> Self {
+        PoolOptions {
+            max_size: 10,
+            min_idle: 0,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Sets the maximum number of open connections, 10 by default.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size.max(1);
+        self
+    }
+
+    /// Sets the number of connections opened when the pool is created, 0 by default.
+    pub fn min_idle(mut self, min_idle: usize) -> Self {
+        self.min_idle = min_idle;
+        self
+    }
+
+    /// Sets how long `get` waits for a connection when all are checked out, 30 seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct PoolState {
+    idle: Vec<Connection>,
+    open: usize,
+}
+
+struct PoolInner {
+    factory: ConnectionFactory,
+    state: Mutex<PoolState>,
+    available: Condvar,
+    max_size: usize,
+    timeout: Duration,
+}
+
+impl PoolInner {
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        // the state stays consistent even if a thread panicked while holding the lock
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A pool of connections to the same database, shared between threads,
+/// e.g. to check out a connection per request of a web server.
+///
+/// Connections are opened on demand up to the maximum size, and a checked out
+/// `PooledConnection` returns to the pool when dropped. Cloning the pool shares it.
+///
+/// Every connection of an in-memory database is a separate database, so pools are
+/// meant for database files.
+#[derive(Clone)]
+pub struct Pool {
+    inner: Arc<PoolInner>,
+}
+
+impl Pool {
+    /// Creates a pool of connections to the database file at the given path.
+    pub fn open<P: AsRef<Path>>(path: P, options: PoolOptions) -> Result<Pool> {
+        let path = path.as_ref().to_path_buf();
+
+        Pool::new(move || Ok(Connection::open(&path)?), options)
+    }
+
+    /// Creates a pool of connections opened by the given builder, so every connection
+    /// is configured with the same pragmas.
+    pub fn from_builder(builder: ConnectionBuilder, options: PoolOptions) -> Result<Pool> {
+        Pool::new(move || builder.open(), options)
+    }
+
+    /// Creates a pool of the connections opened by the given function, e.g. to configure
+    /// each of them.
+    pub fn new<F>(factory: F, options: PoolOptions) -> Result<Pool>
+    where
+        F: Fn() -> Result<Connection> + Send + Sync + 'static,
+    {
+        let mut idle = Vec::new();
+        for _ in 0..options.min_idle.min(options.max_size) {
+            idle.push(factory()?);
+        }
+
+        let pool = Pool {
+            inner: Arc::new(PoolInner {
+                factory: Box::new(factory),
+                state: Mutex::new(PoolState {
+                    open: idle.len(),
+                    idle,
+                }),
+                available: Condvar::new(),
+                max_size: options.max_size,
+                timeout: options.timeout,
+            }),
+        };
+
+        Ok(pool)
+    }
+
+    /// Checks out a connection, waiting for one to be returned if all are checked out.
+    ///
+    /// Returns `DatabaseBusy` if no connection was returned within the timeout of the pool.
+    pub fn get(&self) -> Result<PooledConnection> {
+        let deadline = Instant::now() + self.inner.timeout;
+        let mut state = self.inner.lock();
+
+        loop {
+            if let Some(conn) = state.idle.pop() {
+                return Ok(PooledConnection::new(self.inner.clone(), conn));
+            }
+
+            if state.open < self.inner.max_size {
+                state.open += 1;
+                drop(state);
+
+                // connections are opened without holding the lock
+                return match (self.inner.factory)() {
+                    Ok(conn) => Ok(PooledConnection::new(self.inner.clone(), conn)),
+                    Err(error) => {
+                        self.inner.lock().open -= 1;
+                        self.inner.available.notify_one();
+                        Err(error)
+                    }
+                };
+            }
+
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() {
+                return Err(rusqlite::Error::SqliteFailure(
+                    ffi::Error::new(ffi::SQLITE_BUSY),
+                    Some("timed out waiting for a pooled connection".to_string()),
+                )
+                .into());
+            }
+
+            state = self
+                .inner
+                .available
+                .wait_timeout(state, timeout)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+    }
+
+    /// Get the number of open connections, whether idle or checked out.
+    pub fn connections(&self) -> usize {
+        self.inner.lock().open
+    }
+
+    /// Get the number of idle connections.
+    pub fn idle_connections(&self) -> usize {
+        self.inner.lock().idle.len()
+    }
+}
+
+/// A connection checked out of a `Pool`, returned to it when dropped.
+///
+/// It dereferences to the `Connection`, so it is used like one, e.g. `sqlite::insert(&mut conn, ...)`.
+pub struct PooledConnection {
+    pool: Arc<PoolInner>,
+    conn: Option<Connection>,
+}
+
+impl PooledConnection {
+    fn new(pool: Arc<PoolInner>, conn: Connection) -> Self {
+        PooledConnection {
+            pool,
+            conn: Some(conn),
+        }
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection is returned on drop")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection is returned on drop")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        let conn = match self.conn.take() {
+            Some(conn) => conn,
+            None => return,
+        };
+
+        // a transaction left open is rolled back, so the next user starts clean,
+        // and a connection that cannot roll back is closed instead of reused
+        let reusable = conn.is_autocommit() || conn.execute_batch("ROLLBACK").is_ok();
+
+        let mut state = self.pool.lock();
+        if reusable {
+            state.idle.push(conn);
+        } else {
+            state.open -= 1;
+            info!("Closed pooled connection left in a transaction.");
+        }
+        drop(state);
+
+        self.pool.available.notify_one();
+    }
+}
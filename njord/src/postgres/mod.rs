@@ -0,0 +1,33 @@
+use crate::backend;
+use crate::dialect::Postgres;
+
+use ::postgres::{Client, NoTls};
+
+mod value;
+
+pub use crate::backend::{
+    create_indexes, create_table, create_table_if_not_exists, drop_table, drop_table_if_exists,
+};
+pub use crate::backend::{delete, insert, insert_many, select, update};
+pub use crate::backend::{DeleteBuilder, InsertBuilder, UpdateBuilder};
+pub use crate::condition::Condition;
+pub use crate::join::JoinType;
+
+/// A query run against PostgreSQL, on a client or within one of its transactions.
+pub type QueryBuilder<'a, C = Client> = backend::QueryBuilder<'a, C>;
+
+/// Creates a subquery of the PostgreSQL dialect, as `backend::subquery` does.
+pub fn subquery<'a>(columns: Vec<String>) -> QueryBuilder<'a> {
+    backend::subquery(&Postgres, columns)
+}
+
+/// The error of a query run against PostgreSQL, with the `postgres::Error` of the driver as its source.
+pub use crate::error::{Error, Result};
+
+/// Open a database connection, e.g. `connect("host=localhost user=postgres dbname=app")`
+/// or `connect("postgresql://postgres@localhost/app")`, without TLS.
+pub fn connect(params: &str) -> Result<Client> {
+    let client = Client::connect(params, NoTls)?;
+
+    Ok(client)
+}
@@ -0,0 +1,187 @@
+use crate::backend::{Executor, Row as BackendRow, SAVEPOINT_STATEMENTS};
+use crate::dialect::{Dialect, Postgres};
+use crate::error::{Error, Result};
+use crate::util::convert_value;
+use crate::value::Value;
+
+use std::error::Error as StdError;
+
+use ::postgres::types::{FromSql, ToSql, Type};
+use ::postgres::{Client, GenericClient, Row, Statement, Transaction};
+
+/// Converts a column of a row into the value the struct fields are set from.
+///
+/// Fails for the types without a text representation in the driver.
+impl<'a> FromSql<'a> for Value {
+    fn from_sql(
+        ty: &Type,
+        raw: &'a [u8],
+    ) -> std::result::Result<Self, Box<dyn StdError + Sync + Send>> {
+        let value = match *ty {
+            Type::INT2 => Value::Integer(i64::from(i16::from_sql(ty, raw)?)),
+            Type::INT4 => Value::Integer(i64::from(i32::from_sql(ty, raw)?)),
+            Type::INT8 => Value::Integer(i64::from_sql(ty, raw)?),
+            Type::FLOAT4 => Value::Real(f64::from(f32::from_sql(ty, raw)?)),
+            Type::FLOAT8 => Value::Real(f64::from_sql(ty, raw)?),
+            Type::BOOL => Value::Integer(bool::from_sql(ty, raw)? as i64),
+            Type::BYTEA => Value::Blob(Vec::<u8>::from_sql(ty, raw)?),
+            _ => Value::Text(String::from_sql(ty, raw)?),
+        };
+
+        Ok(value)
+    }
+
+    fn from_sql_null(_ty: &Type) -> std::result::Result<Self, Box<dyn StdError + Sync + Send>> {
+        Ok(Value::Null)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        matches!(
+            *ty,
+            Type::INT2
+                | Type::INT4
+                | Type::INT8
+                | Type::FLOAT4
+                | Type::FLOAT8
+                | Type::BOOL
+                | Type::BYTEA
+        ) || <String as FromSql>::accepts(ty)
+    }
+}
+
+/// Converts the values into the Rust types of the placeholders they are bound to, as
+/// PostgreSQL does not convert between e.g. BIGINT and INTEGER parameters.
+///
+/// Values that do not convert are bound as they are, so the driver reports the mismatch.
+fn bind_values(types: &[Type], values: &[Value]) -> Vec<Box<dyn ToSql + Sync>> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| bind_value(types.get(index).unwrap_or(&Type::TEXT), value))
+        .collect()
+}
+
+fn bind_value(param_type: &Type, value: &Value) -> Box<dyn ToSql + Sync> {
+    match (value, param_type) {
+        (Value::Null, &Type::INT2) => Box::new(None::<i16>),
+        (Value::Null, &Type::INT4) => Box::new(None::<i32>),
+        (Value::Null, &Type::INT8) => Box::new(None::<i64>),
+        (Value::Null, &Type::FLOAT4) => Box::new(None::<f32>),
+        (Value::Null, &Type::FLOAT8) => Box::new(None::<f64>),
+        (Value::Null, &Type::BOOL) => Box::new(None::<bool>),
+        (Value::Null, &Type::BYTEA) => Box::new(None::<Vec<u8>>),
+        (Value::Null, _) => Box::new(None::<String>),
+
+        (Value::Integer(value), &Type::INT2) => match i16::try_from(*value) {
+            Ok(value) => Box::new(value),
+            Err(_) => Box::new(*value),
+        },
+        (Value::Integer(value), &Type::INT4) => match i32::try_from(*value) {
+            Ok(value) => Box::new(value),
+            Err(_) => Box::new(*value),
+        },
+        (Value::Integer(value), &Type::FLOAT4) => Box::new(*value as f32),
+        (Value::Integer(value), &Type::FLOAT8) => Box::new(*value as f64),
+        (Value::Integer(value), &Type::BOOL) => Box::new(*value != 0),
+        (Value::Integer(value), param_type) if is_text(param_type) => Box::new(value.to_string()),
+        (Value::Integer(value), _) => Box::new(*value),
+
+        (Value::Real(value), &Type::FLOAT4) => Box::new(*value as f32),
+        (Value::Real(value), param_type) if is_text(param_type) => Box::new(value.to_string()),
+        (Value::Real(value), _) => Box::new(*value),
+
+        (Value::Text(value), param_type) if !is_text(param_type) => match convert_value(value) {
+            Value::Text(_) => Box::new(value.clone()),
+            converted => bind_value(param_type, &converted),
+        },
+        (Value::Text(value), _) => Box::new(value.clone()),
+
+        (Value::Blob(value), _) => Box::new(value.clone()),
+    }
+}
+
+fn is_text(param_type: &Type) -> bool {
+    matches!(
+        *param_type,
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN
+    )
+}
+
+fn param_refs(params: &[Box<dyn ToSql + Sync>]) -> Vec<&(dyn ToSql + Sync)> {
+    params.iter().map(|param| param.as_ref()).collect()
+}
+
+/// The violations of an integrity constraint, whose SQLSTATE is of class 23, are
+/// returned as `Error::Constraint`.
+impl From<::postgres::Error> for Error {
+    fn from(error: ::postgres::Error) -> Self {
+        let is_constraint = error
+            .code()
+            .is_some_and(|code| code.code().starts_with("23"));
+
+        Error::from_driver(error, is_constraint)
+    }
+}
+
+/// Implements `Executor` for the clients of the driver, so the builders of the `backend`
+/// module run on a client as well as within one of its transactions, whose transaction
+/// nests with a savepoint.
+macro_rules! impl_executor {
+    ($client:ty, $transaction_statements:expr) => {
+        impl Executor for $client {
+            type Statement = Statement;
+
+            fn dialect(&self) -> &'static dyn Dialect {
+                &Postgres
+            }
+
+            fn prepare(&mut self, statement: &str) -> Result<Statement> {
+                Ok(GenericClient::prepare(self, statement)?)
+            }
+
+            fn execute(&mut self, statement: &Statement, params: &[Value]) -> Result<u64> {
+                let params = bind_values(statement.params(), params);
+
+                Ok(GenericClient::execute(
+                    self,
+                    statement,
+                    &param_refs(&params),
+                )?)
+            }
+
+            fn query(
+                &mut self,
+                statement: &Statement,
+                params: &[Value],
+            ) -> Result<Vec<BackendRow>> {
+                let params = bind_values(statement.params(), params);
+
+                GenericClient::query(self, statement, &param_refs(&params))?
+                    .iter()
+                    .map(get_row_values)
+                    .collect()
+            }
+
+            /// The statements without parameters are sent without preparing them.
+            fn execute_batch(&mut self, statement: &str) -> Result<()> {
+                Ok(GenericClient::batch_execute(self, statement)?)
+            }
+
+            fn transaction_statements(&self) -> [&'static str; 3] {
+                $transaction_statements
+            }
+        }
+    };
+}
+
+impl_executor!(Client, ["BEGIN", "COMMIT", "ROLLBACK"]);
+impl_executor!(Transaction<'_>, SAVEPOINT_STATEMENTS);
+
+/// Get the names and values of the columns of a row.
+fn get_row_values(row: &Row) -> Result<BackendRow> {
+    row.columns()
+        .iter()
+        .enumerate()
+        .map(|(index, column)| Ok((column.name().to_string(), row.try_get(index)?)))
+        .collect()
+}
@@ -0,0 +1,137 @@
+use crate::condition::Condition;
+use crate::table::Table;
+use crate::value::Value;
+
+use std::borrow::Cow;
+
+/// The text the values of the sensitive columns are replaced with in the reported
+/// parameters of a statement.
+const MASK: &str = "***";
+
+/// The values bound for the columns of a statement marked with `#[njord(sensitive)]`, which
+/// are masked wherever its parameters are reported, e.g. to the query logger and the
+/// `after` of the middleware.
+///
+/// The values are masked wherever they are bound in the statement, so a value of another
+/// column that happens to be equal is masked as well.
+#[derive(Default)]
+pub(crate) struct Sensitive {
+    columns: Vec<String>,
+    values: Vec<Value>,
+}
+
+impl Sensitive {
+    /// Get the sensitive columns of the tables of a statement, e.g. of a query and its joins.
+    pub(crate) fn new<'a>(tables: impl IntoIterator<Item = &'a dyn Table>) -> Self {
+        Sensitive {
+            columns: tables
+                .into_iter()
+                .flat_map(|table| table.get_sensitive_columns())
+                .collect(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Get the values of the primary key of a row looked up by id, whose columns may be
+    /// sensitive as well, e.g. the hash of a token.
+    #[cfg(feature = "sqlite")]
+    pub(crate) fn primary_key(table: &dyn Table, values: &[Value]) -> Self {
+        let mut sensitive = Sensitive::new([table]);
+        for (column, value) in table.get_primary_key_columns().iter().zip(values) {
+            sensitive.column(column, value);
+        }
+
+        sensitive
+    }
+
+    /// Get the values of the sensitive columns of the rows written by a statement.
+    pub(crate) fn rows<'a, T: Table + ?Sized + 'a>(rows: impl IntoIterator<Item = &'a T>) -> Self {
+        let mut sensitive = Sensitive::default();
+        for row in rows {
+            sensitive.row(row);
+        }
+
+        sensitive
+    }
+
+    /// Adds the values of the sensitive columns of a row.
+    pub(crate) fn row<T: Table + ?Sized>(&mut self, row: &T) {
+        let columns = row.get_sensitive_columns();
+        if columns.is_empty() {
+            return;
+        }
+
+        // a row whose fields cannot be converted fails before its statement is reported
+        let values = row
+            .get_column_fields()
+            .into_iter()
+            .zip(row.get_column_values().unwrap_or_default())
+            .filter(|(column, _)| columns.contains(column))
+            .map(|(_, value)| value);
+
+        self.values.extend(values);
+    }
+
+    /// Adds the value assigned to a column, if it is one of the sensitive columns.
+    pub(crate) fn column(&mut self, column: &str, value: &Value) {
+        if self.is_sensitive(column) {
+            self.values.push(value.clone());
+        }
+    }
+
+    /// Adds the values a condition compares the sensitive columns with, e.g. of
+    /// `Condition::Eq("password", ...)`.
+    pub(crate) fn condition(&mut self, condition: &Condition) {
+        if self.columns.is_empty() {
+            return;
+        }
+
+        match condition {
+            Condition::Eq(column, _)
+            | Condition::Ne(column, _)
+            | Condition::Lt(column, _)
+            | Condition::Gt(column, _)
+            | Condition::Le(column, _)
+            | Condition::Ge(column, _)
+            | Condition::Like(column, _)
+            | Condition::Between(column, _, _)
+            | Condition::InList(column, _)
+                if self.is_sensitive(column) =>
+            {
+                self.values.extend(condition.get_params());
+            }
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                self.condition(left);
+                self.condition(right);
+            }
+            Condition::Not(condition) => self.condition(condition),
+            // the values of a subquery belong to the columns of its own tables
+            _ => {}
+        }
+    }
+
+    /// Get the parameters of a statement with the values of its sensitive columns masked.
+    pub(crate) fn mask<'a>(&self, params: &'a [Value]) -> Cow<'a, [Value]> {
+        // the parameters are only copied to be masked if a sensitive column was bound
+        if self.values.is_empty() {
+            return Cow::Borrowed(params);
+        }
+
+        let params = params
+            .iter()
+            .map(|value| match self.values.contains(value) {
+                true => Value::Text(MASK.to_string()),
+                false => value.clone(),
+            })
+            .collect();
+
+        Cow::Owned(params)
+    }
+
+    /// Whether a column, which may be qualified with its table, is a sensitive column.
+    fn is_sensitive(&self, column: &str) -> bool {
+        let column = column.rsplit('.').next().unwrap_or(column);
+
+        self.columns.iter().any(|sensitive| sensitive == column)
+    }
+}
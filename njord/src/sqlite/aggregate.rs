@@ -0,0 +1,67 @@
+use crate::error::Result;
+use crate::value::Value;
+
+use rusqlite::types::FromSql;
+
+pub enum Aggregate {
+    Count(String),
+    Sum(String),
+    Avg(String),
+    Min(String),
+    Max(String),
+}
+
+impl Aggregate {
+    /// Builds the SQL for the aggregate function.
+    pub fn build(&self) -> String {
+        match self {
+            Aggregate::Count(column) => format!("COUNT({})", column),
+            Aggregate::Sum(column) => format!("SUM({})", column),
+            Aggregate::Avg(column) => format!("AVG({})", column),
+            Aggregate::Min(column) => format!("MIN({})", column),
+            Aggregate::Max(column) => format!("MAX({})", column),
+        }
+    }
+}
+
+/// A row returned by an aggregate query.
+///
+/// Holds the selected values in the same order as they appear in the query.
+#[derive(Debug, Clone)]
+pub struct AggregateRow {
+    values: Vec<Value>,
+}
+
+impl AggregateRow {
+    pub(crate) fn new(values: Vec<Value>) -> Self {
+        AggregateRow { values }
+    }
+
+    /// Get the value at the given index, converted to the requested type.
+    ///
+    /// Use an `Option` for aggregates that can be NULL, e.g. `SUM` over no rows.
+    pub fn get<V: FromSql>(&self, index: usize) -> Result<V> {
+        let value = self
+            .values
+            .get(index)
+            .ok_or(rusqlite::Error::InvalidColumnIndex(index))?;
+
+        let value = rusqlite::types::ValueRef::from(value.as_value_ref());
+
+        let value = V::column_result(value).map_err(|err| {
+            rusqlite::Error::FromSqlConversionFailure(index, value.data_type(), Box::new(err))
+        })?;
+
+        Ok(value)
+    }
+
+    /// Get the number of values in the row.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if the row has no values.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
@@ -0,0 +1,200 @@
+use crate::error::Result;
+use crate::table::Table;
+use crate::value::Value;
+
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{ffi, params_from_iter, Connection, InterruptHandle};
+
+use super::query::QueryBuilder;
+use super::{
+    delete_by_id, find, insert, insert_many, query_as, save, subquery, PrimaryKey, Session,
+};
+
+/// A connection usable from async code, e.g. the handlers of an async web framework.
+///
+/// Every call runs the blocking sqlite functions on the blocking thread pool of tokio,
+/// so the executor threads are never blocked by the database. The calls are serialized
+/// on the underlying connection, and cloning the connection shares it.
+#[derive(Clone)]
+pub struct AsyncConnection {
+    conn: Arc<Mutex<Connection>>,
+    interrupt: Arc<InterruptHandle>,
+}
+
+impl AsyncConnection {
+    /// Opens a connection to the database at `path`.
+    pub async fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let conn = spawn_blocking(move || Ok(Connection::open(path)?)).await?;
+
+        Ok(Self::from_connection(conn))
+    }
+
+    /// Opens a connection to a new in-memory database.
+    pub async fn open_in_memory() -> Result<Self> {
+        let conn = spawn_blocking(|| Ok(Connection::open_in_memory()?)).await?;
+
+        Ok(Self::from_connection(conn))
+    }
+
+    /// Wraps an open connection, e.g. one configured before being shared.
+    pub fn from_connection(conn: Connection) -> Self {
+        AsyncConnection {
+            interrupt: Arc::new(conn.get_interrupt_handle()),
+            conn: Arc::new(Mutex::new(conn)),
+        }
+    }
+
+    /// Cancels the statement running on the connection, e.g. of a call taking too long,
+    /// which then fails with `Error::Cancelled`.
+    ///
+    /// Dropping the future of a call does not stop it, as it runs on the blocking thread
+    /// pool until it is done. The calls waiting for the connection run as usual.
+    pub fn interrupt(&self) {
+        self.interrupt.interrupt();
+    }
+
+    /// Runs `f` with the connection on the blocking thread pool, e.g. to build a query
+    /// with `select` or to run any other blocking function of the crate.
+    pub async fn call<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Connection) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+
+        spawn_blocking(move || {
+            // the connection stays usable even if a call panicked while holding the lock
+            let mut conn = conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&mut conn)
+        })
+        .await
+    }
+
+    /// Runs `f` within a `Session`, committing its writes if `f` succeeds and rolling
+    /// them back if it fails.
+    pub async fn transaction<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Session<'_>) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.call(|conn| {
+            let mut session = Session::new(conn)?;
+            let result = f(&mut session)?;
+            session.commit()?;
+
+            Ok(result)
+        })
+        .await
+    }
+
+    /// Creates a query without a connection, to be run on this connection with
+    /// `build_async`, e.g. `conn.select(columns).from(&table).build_async(&conn)`.
+    pub fn select<'a>(&self, columns: Vec<String>) -> QueryBuilder<'a> {
+        subquery(columns)
+    }
+
+    /// Async variant of `sqlite::find`.
+    pub async fn find_async<T, K>(&self, id: K) -> Result<Option<T>>
+    where
+        T: Table + Default + Send + 'static,
+        K: PrimaryKey + Send + 'static,
+    {
+        self.call(move |conn| find::<T, K>(conn, id)).await
+    }
+
+    /// Async variant of `sqlite::query_as`.
+    pub async fn query_as_async<T: Table + Default + Send + 'static>(
+        &self,
+        query: &str,
+        params: Vec<Value>,
+    ) -> Result<Vec<T>> {
+        let query = query.to_string();
+
+        self.call(move |conn| query_as::<T, _>(conn, &query, params_from_iter(params)))
+            .await
+    }
+
+    /// Async variant of `sqlite::insert`. The row is moved into the call and returned,
+    /// with an auto-increment primary key assigned by the database written back into it.
+    pub async fn insert_async<T: Table + Send + 'static>(&self, mut table_row: T) -> Result<T> {
+        self.call(move |conn| {
+            insert(conn, &mut table_row).build()?;
+
+            Ok(table_row)
+        })
+        .await
+    }
+
+    /// Async variant of `sqlite::insert_many`, returning the rows as in `insert_async`.
+    pub async fn insert_many_async<T: Table + Send + 'static>(
+        &self,
+        mut table_rows: Vec<T>,
+    ) -> Result<Vec<T>> {
+        self.call(move |conn| {
+            insert_many(conn, &mut table_rows)?;
+
+            Ok(table_rows)
+        })
+        .await
+    }
+
+    /// Async variant of `sqlite::save`, returning the row as in `insert_async`.
+    pub async fn save_async<T: Table + Default + Send + 'static>(
+        &self,
+        mut table_row: T,
+    ) -> Result<T> {
+        self.call(move |conn| {
+            save(conn, &mut table_row)?;
+
+            Ok(table_row)
+        })
+        .await
+    }
+
+    /// Async variant of `sqlite::delete_by_id`.
+    pub async fn delete_by_id_async<T, K>(&self, id: K) -> Result<usize>
+    where
+        T: Table + Default + Send + 'static,
+        K: PrimaryKey + Send + 'static,
+    {
+        self.call(move |conn| delete_by_id::<T, K>(conn, id)).await
+    }
+}
+
+impl<'a> QueryBuilder<'a> {
+    /// Runs the query on an `AsyncConnection`, without blocking the executor.
+    ///
+    /// The statement is generated from the builder before awaiting, so the builder
+    /// and the tables it borrows do not need to outlive the call. The builder is created
+    /// without a connection with `subquery`, or with `AsyncConnection::select`.
+    pub fn build_async<T: Table + Default + Send + 'static>(
+        self,
+        conn: &AsyncConnection,
+    ) -> impl Future<Output = Result<Vec<T>>> + Send + 'static {
+        let (query, params) = self.to_sql();
+        let conn = conn.clone();
+
+        async move { conn.query_as_async::<T>(&query, params).await }
+    }
+}
+
+/// Runs a blocking function on the blocking thread pool of tokio.
+async fn spawn_blocking<F, R>(f: F) -> Result<R>
+where
+    F: FnOnce() -> Result<R> + Send + 'static,
+    R: Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+        Err(_) => Err(rusqlite::Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_ABORT),
+            Some("the blocking task was cancelled".to_string()),
+        )
+        .into()),
+    }
+}
@@ -0,0 +1,139 @@
+use crate::error::Result;
+use crate::logger;
+use crate::table::{ColumnSchema, IndexSchema, Relation, Table};
+use crate::value::Value;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::{params_from_iter, Connection};
+
+/// Attaches the database file at `path` to the connection under `alias`, so its tables
+/// can be queried as `alias.table`, e.g. with `Qualified` or `#[njord(schema = "...")]`.
+pub fn attach<P: AsRef<Path>>(conn: &Connection, path: P, alias: &str) -> Result<()> {
+    let statement = format!("ATTACH DATABASE ? AS {}", alias);
+
+    let params = [Value::Text(path.as_ref().to_string_lossy().into_owned())];
+
+    logger::run(&statement, &params, |sql| {
+        conn.execute(sql, params_from_iter(&params))
+    })?;
+
+    Ok(())
+}
+
+/// Detaches the database attached under `alias`.
+pub fn detach(conn: &Connection, alias: &str) -> Result<()> {
+    let statement = format!("DETACH DATABASE {}", alias);
+
+    logger::run(&statement, &[], |sql| conn.execute(sql, []))?;
+
+    Ok(())
+}
+
+/// A table qualified with the alias of an attached database, e.g. to select the rows of
+/// `User` from `archive.users` and join them with the rows of `users` in the main database.
+///
+/// It maps the same columns as the wrapped table, and can be used wherever a `Table` is,
+/// e.g. `select(..).from(&Qualified::new("archive", User::default()))`.
+pub struct Qualified<T> {
+    name: String,
+    table: T,
+}
+
+impl<T: Table> Qualified<T> {
+    pub fn new(schema: &str, table: T) -> Self {
+        Qualified {
+            name: format!("{}.{}", schema, table.get_name()),
+            table,
+        }
+    }
+
+    /// Get the wrapped table row.
+    pub fn inner(&self) -> &T {
+        &self.table
+    }
+
+    /// Unwraps the table row, e.g. after inserting it with its primary key written back.
+    pub fn into_inner(self) -> T {
+        self.table
+    }
+}
+
+impl<T: Table> Table for Qualified<T> {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_columns(&self) -> HashMap<String, String> {
+        self.table.get_columns()
+    }
+
+    fn get_column_fields(&self) -> Vec<String> {
+        self.table.get_column_fields()
+    }
+
+    fn get_column_values(&self) -> Result<Vec<Value>> {
+        self.table.get_column_values()
+    }
+
+    fn set_column_value(&mut self, column: &str, value: Value) -> Result<()> {
+        self.table.set_column_value(column, value)
+    }
+
+    fn get_primary_key_columns(&self) -> Vec<String> {
+        self.table.get_primary_key_columns()
+    }
+
+    fn get_primary_key(&self) -> Result<Vec<(String, Value)>> {
+        self.table.get_primary_key()
+    }
+
+    fn get_auto_increment_column(&self) -> Option<String> {
+        self.table.get_auto_increment_column()
+    }
+
+    fn get_soft_delete_column(&self) -> Option<String> {
+        self.table.get_soft_delete_column()
+    }
+
+    fn get_version_column(&self) -> Option<String> {
+        self.table.get_version_column()
+    }
+
+    fn get_created_at_column(&self) -> Option<String> {
+        self.table.get_created_at_column()
+    }
+
+    fn get_updated_at_column(&self) -> Option<String> {
+        self.table.get_updated_at_column()
+    }
+
+    fn get_default_columns(&self) -> Vec<String> {
+        self.table.get_default_columns()
+    }
+
+    fn get_schema(&self) -> Vec<ColumnSchema> {
+        self.table.get_schema()
+    }
+
+    fn get_checks(&self) -> Vec<String> {
+        self.table.get_checks()
+    }
+
+    fn get_indexes(&self) -> Vec<IndexSchema> {
+        self.table.get_indexes()
+    }
+
+    fn get_relations(&self) -> Vec<Relation> {
+        self.table.get_relations()
+    }
+
+    fn is_readonly(&self) -> bool {
+        self.table.is_readonly()
+    }
+
+    fn generate_values(&mut self) {
+        self.table.generate_values()
+    }
+}
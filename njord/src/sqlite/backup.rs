@@ -0,0 +1,99 @@
+use crate::error::Result;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::Connection;
+
+use log::info;
+
+pub use rusqlite::backup::Progress;
+
+/// Options for copying a database with `backup`.
+pub struct BackupOptions {
+    pages_per_step: i32,
+    pause: Duration,
+}
+
+impl BackupOptions {
+    pub fn new() -> Self {
+        BackupOptions {
+            pages_per_step: 100,
+            pause: Duration::from_millis(10),
+        }
+    }
+
+    /// Sets the number of pages copied by each step, 100 by default.
+    ///
+    /// The source database is only locked while a step runs, so smaller steps let
+    /// other connections write to it during the backup. A negative number copies all
+    /// pages in a single step.
+    pub fn pages_per_step(mut self, pages_per_step: i32) -> Self {
+        self.pages_per_step = pages_per_step;
+        self
+    }
+
+    /// Sets how long to wait between steps, and before retrying a step when the source
+    /// database is locked, 10 milliseconds by default. The pause is skipped in a browser.
+    pub fn pause(mut self, pause: Duration) -> Self {
+        self.pause = pause;
+        self
+    }
+}
+
+impl Default for BackupOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Copies the main database of `src` into the main database of `dst` while `src` stays
+/// in use, replacing the contents of `dst`.
+///
+/// `progress` is called after each step with the number of pages copied so far, as
+/// `progress.pagecount - progress.remaining`. Writes to `src` made by other connections
+/// during the backup restart it, while writes made through `src` itself are copied too.
+pub fn backup<F: FnMut(Progress)>(
+    src: &Connection,
+    dst: &mut Connection,
+    options: BackupOptions,
+    mut progress: F,
+) -> Result<()> {
+    let backup = Backup::new(src, dst)?;
+
+    loop {
+        match backup.step(options.pages_per_step)? {
+            StepResult::Done => {
+                progress(backup.progress());
+                break;
+            }
+            StepResult::More => progress(backup.progress()),
+            // the source database is locked by another connection, so the step is retried
+            _ => {}
+        }
+
+        // a browser cannot block the thread, so the steps run back to back there
+        let can_sleep = cfg!(not(all(target_family = "wasm", target_os = "unknown")));
+        if can_sleep && !options.pause.is_zero() {
+            thread::sleep(options.pause);
+        }
+    }
+
+    info!("Backed up database, done.");
+
+    Ok(())
+}
+
+/// Copies the main database of `src` into the database file at `path` as in `backup`,
+/// creating the file if it does not exist.
+pub fn backup_to_file<P: AsRef<Path>, F: FnMut(Progress)>(
+    src: &Connection,
+    path: P,
+    options: BackupOptions,
+    progress: F,
+) -> Result<()> {
+    let mut dst = Connection::open(path)?;
+
+    backup(src, &mut dst, options, progress)
+}
@@ -0,0 +1,85 @@
+use crate::value::Value;
+
+use super::Condition;
+
+/// A `CASE WHEN` expression, usable in the selected columns and in ORDER BY.
+///
+/// The result values are bound to placeholders as they are, as the values of conditions are.
+pub struct Case {
+    whens: Vec<(Condition, Value)>,
+    otherwise: Option<Value>,
+    alias: Option<String>,
+}
+
+impl Case {
+    pub fn new() -> Self {
+        Case {
+            whens: Vec::new(),
+            otherwise: None,
+            alias: None,
+        }
+    }
+
+    pub fn when(mut self, condition: Condition, value: impl Into<Value>) -> Self {
+        self.whens.push((condition, value.into()));
+        self
+    }
+
+    pub fn otherwise(mut self, value: impl Into<Value>) -> Self {
+        self.otherwise = Some(value.into());
+        self
+    }
+
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.alias = Some(alias.to_string());
+        self
+    }
+
+    /// Builds the SQL for the expression, including the alias if one is set.
+    pub fn build(&self) -> String {
+        match &self.alias {
+            Some(alias) => format!("{} AS {}", self.build_expression(), alias),
+            None => self.build_expression(),
+        }
+    }
+
+    /// Builds the SQL for the expression without the alias, as used in ORDER BY.
+    pub(crate) fn build_expression(&self) -> String {
+        let whens_str: Vec<String> = self
+            .whens
+            .iter()
+            .map(|(condition, _)| format!("WHEN {} THEN ?", condition.build()))
+            .collect();
+
+        let otherwise_str = if self.otherwise.is_some() {
+            " ELSE ?"
+        } else {
+            ""
+        };
+
+        format!("CASE {}{} END", whens_str.join(" "), otherwise_str)
+    }
+
+    /// Get the values to bind to the placeholders, in the same order
+    /// as they appear in the SQL returned by `build`.
+    pub fn get_params(&self) -> Vec<Value> {
+        let mut params = Vec::new();
+
+        for (condition, value) in &self.whens {
+            params.extend(condition.get_params());
+            params.push(value.clone());
+        }
+
+        if let Some(value) = &self.otherwise {
+            params.push(value.clone());
+        }
+
+        params
+    }
+}
+
+impl Default for Case {
+    fn default() -> Self {
+        Case::new()
+    }
+}
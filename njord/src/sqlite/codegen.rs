@@ -0,0 +1,204 @@
+use crate::error::{Error, Result};
+use crate::table::ColumnSchema;
+
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use super::schema;
+use crate::migrations::MIGRATIONS_TABLE;
+
+/// Rust keywords that cannot be field names without the `r#` prefix.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while",
+];
+
+/// Generates the Rust source of a struct deriving `Table` for every table of the database,
+/// e.g. to adopt njord on an existing schema.
+///
+/// The source starts with the `use` declarations the structs need, so it can be written
+/// into its own module. The migrations table of `njord::migrations` is left out.
+pub fn generate(conn: &Connection) -> Result<String> {
+    let mut source =
+        String::from("// Generated by njord from the database schema.\n\nuse njord::table::Table;\nuse njord_derive::Table;\n");
+
+    for table in schema::tables(conn)? {
+        if table == MIGRATIONS_TABLE {
+            continue;
+        }
+
+        source.push('\n');
+        source.push_str(&generate_table(conn, &table)?);
+    }
+
+    Ok(source)
+}
+
+/// Generates the Rust source of a struct deriving `Table` for a table of the database.
+///
+/// Columns are mapped to fields by their type affinity, nullable columns to `Option` fields,
+/// and constraints and indexes to their `#[njord(...)]` attributes.
+pub fn generate_table(conn: &Connection, table: &str) -> Result<String> {
+    let columns = schema::columns(conn, table)?;
+    let indexes = schema::indexes(conn, table)?;
+
+    let struct_name = to_camel_case(table);
+    let mut source = String::from("#[derive(Table, Default, Debug, Clone)]\n");
+
+    if struct_name != table {
+        source.push_str(&format!("#[njord(table = \"{}\")]\n", table));
+    }
+
+    // indexes of a single column are declared on its field
+    for index in indexes.iter().filter(|index| index.columns.len() > 1) {
+        let kind = match index.unique {
+            true => "unique_index",
+            false => "index",
+        };
+        source.push_str(&format!(
+            "#[njord({} = \"{}\")]\n",
+            kind,
+            index.columns.join(", ")
+        ));
+    }
+
+    source.push_str(&format!("pub struct {} {{\n", struct_name));
+
+    for column in &columns {
+        let field_name = to_field_name(&column.name);
+        let mut attributes = column_attributes(column);
+
+        if field_name.trim_start_matches("r#") != column.name {
+            attributes.push(format!("column = \"{}\"", column.name));
+        }
+
+        for index in indexes
+            .iter()
+            .filter(|index| index.columns.len() == 1 && index.columns[0] == column.name)
+        {
+            attributes.push(match index.unique {
+                true => "unique_index".to_string(),
+                false => "index".to_string(),
+            });
+        }
+
+        if !attributes.is_empty() {
+            source.push_str(&format!("    #[njord({})]\n", attributes.join(", ")));
+        }
+
+        source.push_str(&format!(
+            "    pub {}: {},\n",
+            field_name,
+            field_type(column)
+        ));
+    }
+
+    source.push_str("}\n");
+
+    Ok(source)
+}
+
+/// Generates the structs of an SQLite database file into a Rust source file,
+/// e.g. from a build script.
+pub fn generate_file<P: AsRef<Path>, Q: AsRef<Path>>(database: P, output: Q) -> Result<()> {
+    let conn = Connection::open(database)?;
+    let source = generate(&conn)?;
+
+    std::fs::write(output, source).map_err(|error| Error::Execution(Box::new(error)))
+}
+
+/// Get the `#[njord(...)]` attributes of the constraints of a column.
+fn column_attributes(column: &ColumnSchema) -> Vec<String> {
+    let mut attributes = Vec::new();
+
+    if column.primary_key {
+        attributes.push("primary_key".to_string());
+    }
+    if column.auto_increment {
+        attributes.push("auto_increment".to_string());
+    }
+    if column.unique {
+        attributes.push("unique".to_string());
+    }
+    if let Some(default_value) = &column.default_value {
+        attributes.push(format!("default_value = {:?}", default_value));
+    }
+    if let Some(references) = &column.references {
+        attributes.push(format!("references = {:?}", references));
+    }
+
+    attributes
+}
+
+/// Get the Rust type of the field of a column, following the type affinity rules of SQLite.
+fn field_type(column: &ColumnSchema) -> String {
+    let column_type = column.column_type.to_uppercase();
+
+    let field_type = if column_type.contains("INT") {
+        "i64"
+    } else if column_type.contains("CHAR")
+        || column_type.contains("CLOB")
+        || column_type.contains("TEXT")
+    {
+        "String"
+    } else if column_type.contains("BLOB") || column_type.is_empty() {
+        "Vec<u8>"
+    } else if column_type.contains("REAL")
+        || column_type.contains("FLOA")
+        || column_type.contains("DOUB")
+    {
+        "f64"
+    } else {
+        // NUMERIC affinity, e.g. DATE or DECIMAL columns, which are commonly stored as text
+        "String"
+    };
+
+    match column.nullable {
+        true => format!("Option<{}>", field_type),
+        false => field_type.to_string(),
+    }
+}
+
+/// Converts a table name into a CamelCase struct name, e.g. `order_items` into `OrderItems`.
+fn to_camel_case(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Converts a column name into a snake_case field name, e.g. `UserName` into `user_name`.
+fn to_field_name(name: &str) -> String {
+    let mut field_name = String::new();
+
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if index > 0 && !field_name.ends_with('_') {
+                field_name.push('_');
+            }
+            field_name.extend(c.to_lowercase());
+        } else if c.is_alphanumeric() {
+            field_name.push(c);
+        } else {
+            field_name.push('_');
+        }
+    }
+
+    if field_name.starts_with(|c: char| c.is_ascii_digit()) {
+        field_name.insert(0, '_');
+    }
+
+    match KEYWORDS.contains(&field_name.as_str()) {
+        true => format!("r#{}", field_name),
+        false => field_name,
+    }
+}
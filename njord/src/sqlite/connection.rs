@@ -0,0 +1,442 @@
+use crate::error::{Error, Result};
+use crate::logger;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use rusqlite::{Connection, InterruptHandle, OpenFlags};
+
+use super::key::misuse;
+
+#[cfg(feature = "encryption")]
+use log::info;
+
+/// The journal modes of the `journal_mode` pragma.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    Memory,
+    Wal,
+    Off,
+}
+
+impl fmt::Display for JournalMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self {
+            JournalMode::Delete => "DELETE",
+            JournalMode::Truncate => "TRUNCATE",
+            JournalMode::Persist => "PERSIST",
+            JournalMode::Memory => "MEMORY",
+            JournalMode::Wal => "WAL",
+            JournalMode::Off => "OFF",
+        };
+
+        write!(f, "{}", mode)
+    }
+}
+
+impl FromStr for JournalMode {
+    type Err = Error;
+
+    fn from_str(mode: &str) -> Result<Self> {
+        match mode.to_ascii_uppercase().as_str() {
+            "DELETE" => Ok(JournalMode::Delete),
+            "TRUNCATE" => Ok(JournalMode::Truncate),
+            "PERSIST" => Ok(JournalMode::Persist),
+            "MEMORY" => Ok(JournalMode::Memory),
+            "WAL" => Ok(JournalMode::Wal),
+            "OFF" => Ok(JournalMode::Off),
+            _ => Err(misuse(format!("unknown journal mode {}", mode))),
+        }
+    }
+}
+
+/// The levels of the `synchronous` pragma.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Synchronous {
+    Off,
+    Normal,
+    Full,
+    Extra,
+}
+
+impl fmt::Display for Synchronous {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self {
+            Synchronous::Off => "OFF",
+            Synchronous::Normal => "NORMAL",
+            Synchronous::Full => "FULL",
+            Synchronous::Extra => "EXTRA",
+        };
+
+        write!(f, "{}", level)
+    }
+}
+
+impl FromStr for Synchronous {
+    type Err = Error;
+
+    fn from_str(level: &str) -> Result<Self> {
+        match level.to_ascii_uppercase().as_str() {
+            "OFF" | "0" => Ok(Synchronous::Off),
+            "NORMAL" | "1" => Ok(Synchronous::Normal),
+            "FULL" | "2" => Ok(Synchronous::Full),
+            "EXTRA" | "3" => Ok(Synchronous::Extra),
+            _ => Err(misuse(format!("unknown synchronous level {}", level))),
+        }
+    }
+}
+
+/// Opens a connection configured with the given pragmas, e.g.
+/// `ConnectionBuilder::new(path).journal_mode(JournalMode::Wal).foreign_keys(true).open()`.
+///
+/// Pragmas that are not set keep the defaults of SQLite. The builder can be cloned,
+/// e.g. to open the connections of a `Pool` with `Pool::from_builder`.
+#[derive(Clone)]
+pub struct ConnectionBuilder {
+    path: Option<PathBuf>,
+    read_only: bool,
+    create: bool,
+    shared_cache: bool,
+    vfs: Option<String>,
+    #[cfg(feature = "encryption")]
+    key: Option<String>,
+    journal_mode: Option<JournalMode>,
+    synchronous: Option<Synchronous>,
+    foreign_keys: Option<bool>,
+    busy_timeout: Option<Duration>,
+    statement_cache_capacity: Option<usize>,
+    pragmas: Vec<(String, String)>,
+}
+
+impl fmt::Debug for ConnectionBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ConnectionBuilder");
+        debug
+            .field("path", &self.path)
+            .field("read_only", &self.read_only)
+            .field("create", &self.create)
+            .field("shared_cache", &self.shared_cache)
+            .field("vfs", &self.vfs);
+
+        // the key is redacted so it does not end up in logs
+        #[cfg(feature = "encryption")]
+        debug.field("key", &self.key.as_ref().map(|_| "<redacted>"));
+
+        debug
+            .field("journal_mode", &self.journal_mode)
+            .field("synchronous", &self.synchronous)
+            .field("foreign_keys", &self.foreign_keys)
+            .field("busy_timeout", &self.busy_timeout)
+            .field("statement_cache_capacity", &self.statement_cache_capacity)
+            .field("pragmas", &self.pragmas)
+            .finish()
+    }
+}
+
+impl ConnectionBuilder {
+    /// Creates a builder for a connection to the database file at the given path.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self::with_path(Some(path.as_ref().to_path_buf()))
+    }
+
+    /// Creates a builder for a connection to a new in-memory database.
+    pub fn in_memory() -> Self {
+        Self::with_path(None)
+    }
+
+    /// Creates a builder from a database url, e.g.
+    /// `sqlite:///data/app.db?mode=rwc&cache=shared` or `sqlite::memory:`.
+    ///
+    /// `sqlite:///data/app.db` is an absolute path and `sqlite://app.db` a relative one.
+    /// The supported options are `mode` (`ro`, `rw`, `rwc` or `memory`), `cache`
+    /// (`shared` or `private`), `vfs`, `journal_mode`, `synchronous`, `foreign_keys` and
+    /// `busy_timeout` in milliseconds. Unknown options are reported as errors.
+    pub fn from_url(url: &str) -> Result<Self> {
+        let location = url
+            .strip_prefix("sqlite:")
+            .ok_or_else(|| misuse(format!("database url {} is not a sqlite url", url)))?;
+        let location = location.strip_prefix("//").unwrap_or(location);
+
+        let (path, options) = match location.split_once('?') {
+            Some((path, options)) => (path, options),
+            None => (location, ""),
+        };
+
+        let mut builder = match percent_decode(path)?.as_str() {
+            "" => return Err(misuse(format!("database url {} has no path", url))),
+            ":memory:" => Self::in_memory(),
+            path => Self::new(path),
+        };
+
+        for option in options.split('&').filter(|option| !option.is_empty()) {
+            let (name, value) = option.split_once('=').unwrap_or((option, ""));
+            let value = percent_decode(value)?;
+
+            builder = match (name, value.as_str()) {
+                ("mode", "ro") => builder.read_only(true),
+                ("mode", "rw") => builder.create_if_missing(false),
+                ("mode", "rwc") => builder.create_if_missing(true),
+                ("mode", "memory") => ConnectionBuilder {
+                    path: None,
+                    ..builder
+                },
+                ("cache", "shared") => builder.shared_cache(true),
+                ("cache", "private") => builder.shared_cache(false),
+                ("vfs", vfs) => builder.vfs(vfs),
+                ("journal_mode", mode) => builder.journal_mode(mode.parse()?),
+                ("synchronous", level) => builder.synchronous(level.parse()?),
+                ("foreign_keys", foreign_keys) => builder.foreign_keys(parse_bool(foreign_keys)?),
+                ("busy_timeout", milliseconds) => {
+                    let milliseconds: u64 = milliseconds.parse().map_err(|_| {
+                        misuse(format!(
+                            "busy_timeout {} is not in milliseconds",
+                            milliseconds
+                        ))
+                    })?;
+                    builder.busy_timeout(Duration::from_millis(milliseconds))
+                }
+                _ => {
+                    return Err(misuse(format!(
+                        "unknown option {} of database url {}",
+                        option, url
+                    )))
+                }
+            };
+        }
+
+        Ok(builder)
+    }
+
+    /// Creates a builder from the database url in the environment variable `name`,
+    /// e.g. `DATABASE_URL`, as in `from_url`.
+    pub fn from_env(name: &str) -> Result<Self> {
+        let url = env::var(name)
+            .map_err(|_| misuse(format!("environment variable {} is not set", name)))?;
+
+        Self::from_url(&url)
+    }
+
+    fn with_path(path: Option<PathBuf>) -> Self {
+        ConnectionBuilder {
+            path,
+            read_only: false,
+            create: true,
+            shared_cache: false,
+            vfs: None,
+            #[cfg(feature = "encryption")]
+            key: None,
+            journal_mode: None,
+            synchronous: None,
+            foreign_keys: None,
+            busy_timeout: None,
+            statement_cache_capacity: None,
+            pragmas: Vec::new(),
+        }
+    }
+
+    /// Sets whether the database is opened read-only, `false` by default.
+    ///
+    /// The write functions of the crate refuse to run on a read-only connection,
+    /// e.g. for the analytics code reading from a reporting replica.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Sets the key of an encrypted SQLCipher database, applied before any other pragma.
+    ///
+    /// A new database is encrypted with the key, and opening an existing database
+    /// with a wrong key fails with SQLITE_NOTADB.
+    #[cfg(feature = "encryption")]
+    pub fn key(mut self, key: &str) -> Self {
+        self.key = Some(key.to_string());
+        self
+    }
+
+    /// Sets whether the database file is created if it does not exist, `true` by default.
+    pub fn create_if_missing(mut self, create: bool) -> Self {
+        self.create = create;
+        self
+    }
+
+    /// Sets whether the connection shares its cache with the other connections to the
+    /// same database in the process, `false` by default.
+    pub fn shared_cache(mut self, shared_cache: bool) -> Self {
+        self.shared_cache = shared_cache;
+        self
+    }
+
+    /// Sets the VFS the database is opened with, by the name it was registered with.
+    ///
+    /// In a browser, the database is kept in memory by the default VFS of the WASM build
+    /// of SQLite, and is persisted by a VFS storing it in the Origin Private File System,
+    /// e.g. `vfs("opfs-sahpool")` once the `sahpool` VFS of `sqlite-wasm-vfs` is installed.
+    pub fn vfs(mut self, vfs: &str) -> Self {
+        self.vfs = Some(vfs.to_string());
+        self
+    }
+
+    /// Sets the `journal_mode` pragma, e.g. `JournalMode::Wal` to let readers run
+    /// alongside a writer.
+    pub fn journal_mode(mut self, journal_mode: JournalMode) -> Self {
+        self.journal_mode = Some(journal_mode);
+        self
+    }
+
+    /// Sets the `synchronous` pragma, e.g. `Synchronous::Normal`, which is safe
+    /// with the WAL journal mode.
+    pub fn synchronous(mut self, synchronous: Synchronous) -> Self {
+        self.synchronous = Some(synchronous);
+        self
+    }
+
+    /// Sets the `foreign_keys` pragma, which SQLite disables by default.
+    pub fn foreign_keys(mut self, foreign_keys: bool) -> Self {
+        self.foreign_keys = Some(foreign_keys);
+        self
+    }
+
+    /// Sets how long a statement waits for a lock held by another connection
+    /// before failing with SQLITE_BUSY.
+    pub fn busy_timeout(mut self, busy_timeout: Duration) -> Self {
+        self.busy_timeout = Some(busy_timeout);
+        self
+    }
+
+    /// Sets how many prepared statements the connection keeps in its statement cache, 16 by
+    /// default, e.g. for an application running more distinct queries on a hot path.
+    ///
+    /// The statements of the builders are prepared from the cache, keyed by their SQL, so
+    /// a statement run again is not compiled again. The least recently used statement is
+    /// dropped when the cache is full, and a capacity of 0 disables the cache.
+    pub fn statement_cache_capacity(mut self, capacity: usize) -> Self {
+        self.statement_cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets any other pragma, e.g. `pragma("cache_size", "-64000")`, applied after
+    /// the pragmas above in the order they were added.
+    pub fn pragma(mut self, name: &str, value: &str) -> Self {
+        self.pragmas.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Opens the connection and applies the pragmas.
+    pub fn open(&self) -> Result<Connection> {
+        let mut flags = OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        if self.read_only {
+            flags |= OpenFlags::SQLITE_OPEN_READ_ONLY;
+        } else if self.create {
+            flags |= OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE;
+        } else {
+            flags |= OpenFlags::SQLITE_OPEN_READ_WRITE;
+        }
+        if self.shared_cache {
+            flags |= OpenFlags::SQLITE_OPEN_SHARED_CACHE;
+        }
+
+        let conn = match (&self.path, &self.vfs) {
+            (Some(path), Some(vfs)) => {
+                Connection::open_with_flags_and_vfs(path, flags, vfs.as_str())?
+            }
+            (Some(path), None) => Connection::open_with_flags(path, flags)?,
+            (None, Some(vfs)) => {
+                Connection::open_in_memory_with_flags_and_vfs(flags, vfs.as_str())?
+            }
+            (None, None) => Connection::open_in_memory_with_flags(flags)?,
+        };
+
+        // the key is not logged, and is checked by reading the schema of the database
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &self.key {
+            conn.pragma_update(None, "key", key)?;
+            conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))?;
+        }
+
+        if let Some(busy_timeout) = self.busy_timeout {
+            conn.busy_timeout(busy_timeout)?;
+        }
+
+        if let Some(capacity) = self.statement_cache_capacity {
+            conn.set_prepared_statement_cache_capacity(capacity);
+        }
+
+        let mut pragmas: Vec<(String, String)> = Vec::new();
+        if let Some(journal_mode) = self.journal_mode {
+            pragmas.push(("journal_mode".to_string(), journal_mode.to_string()));
+        }
+        if let Some(synchronous) = self.synchronous {
+            pragmas.push(("synchronous".to_string(), synchronous.to_string()));
+        }
+        if let Some(foreign_keys) = self.foreign_keys {
+            let value = match foreign_keys {
+                true => "ON",
+                false => "OFF",
+            };
+            pragmas.push(("foreign_keys".to_string(), value.to_string()));
+        }
+        pragmas.extend(self.pragmas.iter().cloned());
+
+        for (name, value) in pragmas {
+            let statement = format!("PRAGMA {} = {}", name, value);
+
+            logger::run(&statement, &[], |sql| conn.execute_batch(sql))?;
+        }
+
+        Ok(conn)
+    }
+}
+
+/// Changes the key of an encrypted SQLCipher database opened with `ConnectionBuilder::key`,
+/// re-encrypting all of its pages with the new key.
+#[cfg(feature = "encryption")]
+pub fn rekey(conn: &Connection, new_key: &str) -> Result<()> {
+    conn.pragma_update(None, "rekey", new_key)?;
+
+    info!("Rekeyed database, done.");
+
+    Ok(())
+}
+
+/// Get a handle cancelling the statement running on the connection from another thread,
+/// e.g. the `build` of a long-running query, which then fails with `Error::Cancelled`.
+///
+/// `InterruptHandle::interrupt` does nothing if no statement is running, so a statement
+/// started afterwards runs as usual.
+pub fn interrupt_handle(conn: &Connection) -> InterruptHandle {
+    conn.get_interrupt_handle()
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "on" | "1" => Ok(true),
+        "false" | "off" | "0" => Ok(false),
+        _ => Err(misuse(format!("{} is not a boolean", value))),
+    }
+}
+
+/// Decodes the `%XX` escapes of a part of a database url.
+fn percent_decode(value: &str) -> Result<String> {
+    let invalid = || misuse(format!("invalid escape in database url part {}", value));
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3).ok_or_else(invalid)?;
+            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
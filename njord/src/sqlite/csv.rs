@@ -0,0 +1,203 @@
+use crate::error::{Error, Result};
+use crate::logger;
+use crate::sensitive::Sensitive;
+use crate::table::Table;
+use crate::util::convert_value;
+use crate::value::Value;
+
+use super::insert::max_variable_number;
+use super::key::check_writable;
+use super::query::prepare_cached;
+
+use std::io::Read;
+
+use log::info;
+use rusqlite::{params_from_iter, Connection};
+
+/// Options for loading a CSV file with `load_csv`.
+pub struct CsvOptions {
+    delimiter: u8,
+    has_headers: bool,
+    batch_size: usize,
+    coerce_types: bool,
+    empty_as_null: bool,
+}
+
+impl CsvOptions {
+    pub fn new() -> Self {
+        CsvOptions {
+            delimiter: b',',
+            has_headers: true,
+            batch_size: 500,
+            coerce_types: true,
+            empty_as_null: false,
+        }
+    }
+
+    /// Sets the field delimiter, `,` by default.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets whether the first record holds the column names, `true` by default.
+    ///
+    /// With headers the fields are matched to the columns by name, otherwise
+    /// by position in the same order as `Table::get_column_fields`.
+    pub fn has_headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// Sets the number of rows inserted by each statement, 500 by default.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets whether fields are converted to integers, reals and booleans when
+    /// they look like one, `true` by default. Otherwise every field is stored as text.
+    pub fn coerce_types(mut self, coerce_types: bool) -> Self {
+        self.coerce_types = coerce_types;
+        self
+    }
+
+    /// Sets whether empty fields are stored as NULL, `false` by default.
+    pub fn empty_as_null(mut self, empty_as_null: bool) -> Self {
+        self.empty_as_null = empty_as_null;
+        self
+    }
+
+    fn convert_field(&self, field: &str) -> Value {
+        if self.empty_as_null && field.is_empty() {
+            Value::Null
+        } else if self.coerce_types {
+            convert_value(field)
+        } else {
+            Value::Text(field.to_string())
+        }
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Streams the records of a CSV file into the table of `T` within a single transaction.
+///
+/// The records are inserted in batches through prepared multi-row statements,
+/// so only one batch is held in memory at a time. Returns the number of loaded rows.
+pub fn load_csv<T: Table + Default, R: Read>(
+    conn: &mut Connection,
+    reader: R,
+    options: CsvOptions,
+) -> Result<usize> {
+    let table = T::default();
+    check_writable(conn, &table)?;
+
+    let mut csv_reader = ::csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .has_headers(options.has_headers)
+        .from_reader(reader);
+
+    let column_fields = table.get_column_fields();
+    let columns = if options.has_headers {
+        let headers = csv_reader.headers().map_err(csv_error)?;
+        let columns: Vec<String> = headers
+            .iter()
+            .map(|header| header.trim().to_string())
+            .collect();
+
+        if let Some(column) = columns
+            .iter()
+            .find(|column| !column_fields.contains(column))
+        {
+            return Err(rusqlite::Error::InvalidColumnName(column.clone()).into());
+        }
+
+        columns
+    } else {
+        column_fields
+    };
+
+    // keep each statement within the parameter limit of the connection
+    let batch_size = options
+        .batch_size
+        .min(max_variable_number(conn)? / columns.len().max(1))
+        .max(1);
+
+    // create a transaction
+    let tx = conn.savepoint()?;
+
+    let statement = generate_statement(table.get_name(), &columns, batch_size);
+
+    let mut loaded = 0;
+    {
+        let mut values: Vec<Value> = Vec::with_capacity(columns.len() * batch_size);
+        let mut sensitive = Sensitive::new([&table as &dyn Table]);
+        let mut rows = 0;
+
+        for record in csv_reader.records() {
+            let record = record.map_err(csv_error)?;
+
+            if record.len() != columns.len() {
+                return Err(
+                    rusqlite::Error::InvalidParameterCount(record.len(), columns.len()).into(),
+                );
+            }
+
+            for (column, field) in columns.iter().zip(record.iter()) {
+                let value = options.convert_field(field);
+                sensitive.column(column, &value);
+                values.push(value);
+            }
+            rows += 1;
+
+            if rows == batch_size {
+                // the statement is compiled once, into the statement cache of the connection
+                logger::run(&statement, &sensitive.mask(&values), |sql| {
+                    prepare_cached(&tx, sql)?.execute(params_from_iter(&values))
+                })?;
+                values.clear();
+                sensitive = Sensitive::new([&table as &dyn Table]);
+                loaded += rows;
+                rows = 0;
+            }
+        }
+
+        // insert the rows of the last, partial batch
+        if rows > 0 {
+            let statement = generate_statement(table.get_name(), &columns, rows);
+            logger::run(&statement, &sensitive.mask(&values), |sql| {
+                prepare_cached(&tx, sql)?.execute(params_from_iter(&values))
+            })?;
+            loaded += rows;
+        }
+    }
+
+    // commit the transaction
+    tx.commit()?;
+
+    info!("Loaded {} rows into table, done.", loaded);
+
+    Ok(loaded)
+}
+
+fn generate_statement(table_name: &str, columns: &[String], row_count: usize) -> String {
+    let values_str = vec!["?"; columns.len()].join(", ");
+    let rows_str = vec![format!("({})", values_str); row_count].join(", ");
+
+    format!(
+        "INSERT INTO {} ({}) VALUES {};",
+        table_name,
+        columns.join(", "),
+        rows_str
+    )
+}
+
+/// Maps an error reading the CSV file into the error type used by the rest of the crate.
+fn csv_error(error: ::csv::Error) -> Error {
+    Error::Execution(Box::new(error))
+}
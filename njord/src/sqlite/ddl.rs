@@ -0,0 +1,339 @@
+use crate::backend::ddl::drop_table_statements;
+use crate::dialect::{Dialect, Sqlite};
+use crate::error::{Error, Result};
+use crate::logger;
+use crate::table::{ColumnSchema, IndexSchema, Table};
+
+use rusqlite::{ffi, Connection};
+
+use log::info;
+
+use super::key::misuse;
+
+/// Creates the table of `T` from the schema of its columns, failing if it already exists.
+///
+/// The columns are declared in the same order as `Table::get_column_fields`, with their
+/// primary key, NOT NULL, UNIQUE, DEFAULT, CHECK and REFERENCES constraints.
+pub fn create_table<T: Table + Default>(conn: &Connection) -> Result<()> {
+    execute_create_table(conn, &T::default(), false)
+}
+
+/// Creates the table of `T` from the schema of its columns, unless it already exists.
+///
+/// An existing table is left as it is, even if its columns differ from `T`.
+pub fn create_table_if_not_exists<T: Table + Default>(conn: &Connection) -> Result<()> {
+    execute_create_table(conn, &T::default(), true)
+}
+
+fn execute_create_table(conn: &Connection, table: &dyn Table, if_not_exists: bool) -> Result<()> {
+    let statement = create_table_statement(table, table.get_name(), if_not_exists);
+
+    execute_statement(conn, &statement)?;
+
+    info!("Created table {}, done.", table.get_name());
+
+    Ok(())
+}
+
+/// Generates the CREATE TABLE statement of a table with the sqlite dialect, creating it
+/// with the given name.
+pub(crate) fn create_table_statement(
+    table: &dyn Table,
+    table_name: &str,
+    if_not_exists: bool,
+) -> String {
+    crate::backend::ddl::create_table_statement(&Sqlite, table, table_name, if_not_exists)
+}
+
+/// Generates the definition of a column within a CREATE TABLE statement with the sqlite
+/// dialect.
+pub(crate) fn column_definition(
+    table: &dyn Table,
+    column: &ColumnSchema,
+    inline_primary_key: bool,
+) -> String {
+    crate::backend::ddl::column_definition(&Sqlite, table, column, inline_primary_key)
+}
+
+/// Generates the CREATE INDEX statement of an index of a table with the sqlite dialect.
+pub(crate) fn create_index_statement(table_name: &str, index: &IndexSchema) -> String {
+    Sqlite.create_index(table_name, index)
+}
+
+/// Creates the indexes of `T` declared with `#[njord(index)]` and `#[njord(unique_index)]`,
+/// unless they already exist.
+pub fn create_indexes<T: Table + Default>(conn: &Connection) -> Result<()> {
+    let table = T::default();
+
+    for index in table.get_indexes() {
+        execute_statement(conn, &create_index_statement(table.get_name(), &index))?;
+    }
+
+    Ok(())
+}
+
+/// The tables of one or more types, created together with `setup_schema`.
+///
+/// Implemented for every `Table` and for tuples of up to 12 of them, e.g. `(User, Post)`.
+pub trait Schema {
+    fn create(conn: &Connection) -> Result<()>;
+}
+
+impl<T: Table + Default> Schema for T {
+    fn create(conn: &Connection) -> Result<()> {
+        create_table::<T>(conn)?;
+        create_indexes::<T>(conn)
+    }
+}
+
+macro_rules! impl_schema_for_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Schema),+> Schema for ($($name,)+) {
+            fn create(conn: &Connection) -> Result<()> {
+                $($name::create(conn)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_schema_for_tuple!(A);
+impl_schema_for_tuple!(A, B);
+impl_schema_for_tuple!(A, B, C);
+impl_schema_for_tuple!(A, B, C, D);
+impl_schema_for_tuple!(A, B, C, D, E);
+impl_schema_for_tuple!(A, B, C, D, E, F);
+impl_schema_for_tuple!(A, B, C, D, E, F, G);
+impl_schema_for_tuple!(A, B, C, D, E, F, G, H);
+impl_schema_for_tuple!(A, B, C, D, E, F, G, H, I);
+impl_schema_for_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_schema_for_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_schema_for_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+/// Creates the tables and indexes of all types of `S` in order, e.g.
+/// `setup_schema::<(User, Post)>(&conn)` to prepare the database of a unit test.
+pub fn setup_schema<S: Schema>(conn: &Connection) -> Result<()> {
+    S::create(conn)
+}
+
+/// Drops the table of `T`, failing if it does not exist.
+pub fn drop_table<T: Table + Default>(conn: &Connection) -> Result<()> {
+    execute_drop_table(conn, &T::default(), false)
+}
+
+/// Drops the table of `T`, unless it does not exist.
+pub fn drop_table_if_exists<T: Table + Default>(conn: &Connection) -> Result<()> {
+    execute_drop_table(conn, &T::default(), true)
+}
+
+fn execute_drop_table(conn: &Connection, table: &dyn Table, if_exists: bool) -> Result<()> {
+    for statement in drop_table_statements(&Sqlite, table, if_exists) {
+        execute_statement(conn, &statement)?;
+    }
+
+    Ok(())
+}
+
+/// Renames a table, e.g. after renaming the struct mapping it.
+pub fn rename_table(conn: &Connection, from: &str, to: &str) -> Result<()> {
+    let statement = format!("ALTER TABLE {} RENAME TO {}", from, to);
+
+    execute_statement(conn, &statement)
+}
+
+/// Renames a column of the table of `T`, e.g. after renaming the field mapping it.
+pub fn rename_column<T: Table + Default>(conn: &Connection, from: &str, to: &str) -> Result<()> {
+    let statement = format!(
+        "ALTER TABLE {} RENAME COLUMN {} TO {}",
+        T::default().get_name(),
+        from,
+        to
+    );
+
+    execute_statement(conn, &statement)
+}
+
+/// Adds a column of `T` to its table, declared as in `create_table`.
+///
+/// Columns SQLite cannot add with ALTER TABLE, e.g. UNIQUE columns or columns defaulting
+/// to the current timestamp, are added by rebuilding the table as in `rebuild_table`.
+pub fn add_column<T: Table + Default>(conn: &mut Connection, column: &str) -> Result<()> {
+    let table = T::default();
+    let schema = table
+        .get_schema()
+        .into_iter()
+        .find(|schema| schema.name == column)
+        .ok_or_else(|| rusqlite::Error::InvalidColumnName(column.to_string()))?;
+
+    let statement = format!(
+        "ALTER TABLE {} ADD COLUMN {}",
+        table.get_name(),
+        column_definition(&table, &schema, true)
+    );
+
+    alter_or_rebuild(conn, &table, &statement)
+}
+
+/// Drops a column from the table of `T`, after it was removed from `T`.
+///
+/// Columns SQLite cannot drop with ALTER TABLE, e.g. UNIQUE or indexed columns, are dropped
+/// by rebuilding the table as in `rebuild_table`.
+pub fn drop_column<T: Table + Default>(conn: &mut Connection, column: &str) -> Result<()> {
+    let table = T::default();
+    let statement = format!("ALTER TABLE {} DROP COLUMN {}", table.get_name(), column);
+
+    alter_or_rebuild(conn, &table, &statement)
+}
+
+/// Rebuilds the table of `T` to match its current schema, within a single transaction.
+///
+/// A new table is created from the schema of `T`, the rows are copied for the columns both
+/// tables have, and the new table replaces the old one. This changes what ALTER TABLE cannot,
+/// e.g. the type or constraints of a column. The indexes declared on `T` are created again,
+/// while other indexes and triggers of the old table are dropped with it.
+///
+/// Foreign keys are disabled while the old table is dropped, so the rows referencing it are
+/// kept, and checked before the transaction is committed. They can only be disabled outside
+/// of a transaction, so a table with foreign keys enabled cannot be rebuilt within one.
+pub fn rebuild_table<T: Table + Default>(conn: &mut Connection) -> Result<()> {
+    execute_rebuild(conn, &T::default())
+}
+
+/// Executes an ALTER TABLE statement, falling back to rebuilding the table if SQLite
+/// cannot add or drop the column with it.
+fn alter_or_rebuild(conn: &mut Connection, table: &dyn Table, statement: &str) -> Result<()> {
+    match logger::run(statement, &[], |sql| conn.execute(sql, [])) {
+        Err(error) if is_unsupported_alter(&error) => execute_rebuild(conn, table),
+        result => {
+            result?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Whether ALTER TABLE failed because SQLite cannot add or drop the column in place, e.g. a
+/// UNIQUE column or an indexed one, rather than e.g. because the table does not exist.
+fn is_unsupported_alter(error: &Error) -> bool {
+    match error.driver_error::<rusqlite::Error>() {
+        Some(rusqlite::Error::SqliteFailure(_, Some(message))) => {
+            let message = message.to_lowercase();
+            message.starts_with("cannot add")
+                || message.starts_with("cannot drop")
+                || message.contains("after drop column")
+        }
+        _ => false,
+    }
+}
+
+/// Rebuilds a table following the procedure documented by SQLite, with foreign keys
+/// disabled around the transaction.
+fn execute_rebuild(conn: &mut Connection, table: &dyn Table) -> Result<()> {
+    let foreign_keys: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+
+    if foreign_keys {
+        // the pragma has no effect within a transaction, where the drop would cascade
+        if !conn.is_autocommit() {
+            return Err(misuse(format!(
+                "table {} cannot be rebuilt within a transaction while foreign keys are enabled",
+                table.get_name()
+            )));
+        }
+
+        execute_statement(conn, "PRAGMA foreign_keys = OFF")?;
+    }
+
+    let rebuilt = execute_rebuild_transaction(conn, table, foreign_keys);
+
+    if foreign_keys {
+        // a failed rebuild is reported over a failure to enable foreign keys again
+        let restored = execute_statement(conn, "PRAGMA foreign_keys = ON");
+        return rebuilt.and(restored);
+    }
+
+    rebuilt
+}
+
+fn execute_rebuild_transaction(
+    conn: &mut Connection,
+    table: &dyn Table,
+    check_foreign_keys: bool,
+) -> Result<()> {
+    // create a transaction
+    let tx = conn.savepoint()?;
+
+    execute_rebuild_table(&tx, table)?;
+
+    // the rows are checked against the foreign keys, which were not enforced while copying
+    if check_foreign_keys {
+        let violation: Option<String> = tx
+            .prepare("PRAGMA foreign_key_check")?
+            .query_map([], |row| row.get(0))?
+            .next()
+            .transpose()?;
+
+        if let Some(violating_table) = violation {
+            return Err(rusqlite::Error::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_CONSTRAINT_FOREIGNKEY),
+                Some(format!(
+                    "rebuilding table {} violates a foreign key of table {}",
+                    table.get_name(),
+                    violating_table
+                )),
+            )
+            .into());
+        }
+    }
+
+    // commit the transaction
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn execute_rebuild_table(conn: &Connection, table: &dyn Table) -> Result<()> {
+    let table_name = table.get_name();
+    let rebuilt_name = format!("_njord_rebuild_{}", table_name);
+
+    let existing_columns: Vec<String> = conn
+        .prepare("SELECT name FROM pragma_table_info(?)")?
+        .query_map([table_name], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    let copied_columns: Vec<String> = table
+        .get_column_fields()
+        .into_iter()
+        .filter(|column| existing_columns.contains(column))
+        .collect();
+
+    execute_statement(conn, &create_table_statement(table, &rebuilt_name, false))?;
+
+    if !copied_columns.is_empty() {
+        let copy_statement = format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {}",
+            rebuilt_name,
+            copied_columns.join(", "),
+            copied_columns.join(", "),
+            table_name
+        );
+        execute_statement(conn, &copy_statement)?;
+    }
+
+    execute_statement(conn, &format!("DROP TABLE {}", table_name))?;
+    execute_statement(
+        conn,
+        &format!("ALTER TABLE {} RENAME TO {}", rebuilt_name, table_name),
+    )?;
+
+    for index in table.get_indexes() {
+        execute_statement(conn, &create_index_statement(table_name, &index))?;
+    }
+
+    Ok(())
+}
+
+fn execute_statement(conn: &Connection, statement: &str) -> Result<()> {
+    logger::run(statement, &[], |sql| conn.execute(sql, []))?;
+
+    Ok(())
+}
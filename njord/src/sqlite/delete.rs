@@ -0,0 +1,261 @@
+use crate::condition::Subquery;
+use crate::dialect::{Dialect, Sqlite};
+use crate::error::Result;
+use crate::logger;
+use crate::sensitive::Sensitive;
+use crate::statement::delete_statement;
+use crate::table::Table;
+
+use rusqlite::{params_from_iter, Connection};
+use std::collections::HashMap;
+
+use log::info;
+
+use super::key::{check_writable, order_without_limit, primary_key_condition, PrimaryKey};
+use super::query::prepare_cached;
+use super::Condition;
+
+pub fn delete<'a>(conn: &'a mut Connection) -> DeleteBuilder<'a> {
+    DeleteBuilder::new(conn)
+}
+
+pub struct DeleteBuilder<'a> {
+    conn: &'a mut Connection,
+    table: Option<&'a dyn Table>,
+    where_condition: Option<Condition>,
+    order_by: Option<HashMap<Vec<String>, String>>,
+    limit: Option<usize>,
+    force: bool,
+}
+
+impl<'a> DeleteBuilder<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        DeleteBuilder {
+            conn,
+            table: None,
+            where_condition: None,
+            order_by: None,
+            limit: None,
+            force: false,
+        }
+    }
+
+    pub fn from(mut self, table: &'a dyn Table) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    pub fn where_clause(mut self, condition: Condition) -> Self {
+        self.where_condition = Some(condition);
+        self
+    }
+
+    /// Orders the rows picked by `limit`, which it requires.
+    pub fn order_by(mut self, col_and_order: HashMap<Vec<String>, String>) -> Self {
+        self.order_by = Some(col_and_order);
+        self
+    }
+
+    /// Deletes at most the given number of rows, e.g. to chunk large cleanups in a loop.
+    pub fn limit(mut self, count: usize) -> Self {
+        self.limit = Some(count);
+        self
+    }
+
+    /// Removes the rows even if the table soft deletes them.
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+
+    /// Deletes the rows and returns the number of deleted rows.
+    ///
+    /// Returns `Error::Generation` for an `order_by` without a `limit`, which would not
+    /// change the deleted rows.
+    pub fn build(self) -> Result<usize> {
+        if let Some(table) = self.table {
+            check_writable(self.conn, table)?;
+        }
+
+        if self.order_by.is_some() && self.limit.is_none() {
+            return Err(order_without_limit("a delete"));
+        }
+
+        let mut sensitive = Sensitive::new(self.table);
+
+        if let Some(condition) = &self.where_condition {
+            sensitive.condition(condition);
+        }
+
+        let condition = match self.limit {
+            Some(count) => {
+                // rows that are already soft deleted are not picked by the limit
+                let soft_delete_condition = match self.force {
+                    true => None,
+                    false => self
+                        .table
+                        .and_then(|t| t.get_soft_delete_column())
+                        .map(Condition::IsNull),
+                };
+                let condition = self
+                    .where_condition
+                    .into_iter()
+                    .chain(soft_delete_condition)
+                    .reduce(Condition::and);
+
+                Some(limit_rows(self.table, condition, &self.order_by, count))
+            }
+            None => self.where_condition,
+        };
+
+        let (query, params) = delete_statement(&Sqlite, self.table, condition.as_ref(), self.force);
+
+        // create a transaction
+        let tx = self.conn.savepoint()?;
+
+        let count = logger::run(&query, &sensitive.mask(&params), |sql| {
+            prepare_cached(&tx, sql)?.execute(params_from_iter(&params))
+        })?;
+
+        // commit the transaction
+        tx.commit()?;
+
+        info!("Deleted {} rows from table, done.", count);
+
+        Ok(count)
+    }
+}
+
+/// Builds a condition picking at most `count` of the rows matched by the given one.
+///
+/// The bundled sqlite is not compiled with SQLITE_ENABLE_UPDATE_DELETE_LIMIT,
+/// so the order and limit are applied through a subquery of the primary key instead,
+/// which a WITHOUT ROWID table has as well, or of the rowid of a table without one.
+pub(crate) fn limit_rows(
+    table: Option<&dyn Table>,
+    condition: Option<Condition>,
+    order_by: &Option<HashMap<Vec<String>, String>>,
+    count: usize,
+) -> Condition {
+    let table_name_str = table.map_or(String::new(), |table| {
+        Sqlite.quote_identifier(table.get_name())
+    });
+
+    let key_columns: Vec<String> = table
+        .map(|table| table.get_primary_key_columns())
+        .unwrap_or_default()
+        .iter()
+        .map(|column| Sqlite.quote_identifier(column))
+        .collect();
+
+    let key_str = match key_columns.is_empty() {
+        true => "rowid".to_string(),
+        false => key_columns.join(", "),
+    };
+
+    let where_condition_str = condition.as_ref().map_or(String::new(), |condition| {
+        format!("WHERE {}", condition.build())
+    });
+
+    let order_by_str = order_by
+        .iter()
+        .flatten()
+        .map(|(columns, order)| format!("{} {}", columns.join(", "), order))
+        .collect::<Vec<String>>();
+
+    let order_by_str = if order_by_str.is_empty() {
+        String::new()
+    } else {
+        format!("ORDER BY {}", order_by_str.join(", "))
+    };
+
+    let subquery = [
+        format!("SELECT {} FROM {}", key_str, table_name_str),
+        where_condition_str,
+        order_by_str,
+        format!("LIMIT {}", count),
+    ]
+    .into_iter()
+    .filter(|clause| !clause.is_empty())
+    .collect::<Vec<String>>()
+    .join(" ");
+
+    let params = condition.map_or(Vec::new(), |condition| condition.get_params());
+
+    // a composite key is compared as a row value, e.g. ("a", "b") IN (SELECT "a", "b" ...)
+    let key_str = match key_columns.len() {
+        0 | 1 => key_str,
+        _ => format!("({})", key_str),
+    };
+
+    Condition::InSubquery(key_str, Subquery::new(subquery, params))
+}
+
+/// Deletes the row of `T` with the given primary key, given as a tuple for composite keys.
+///
+/// Returns the number of deleted rows, which is 0 if there is no such row.
+pub fn delete_by_id<T: Table + Default, K: PrimaryKey>(
+    conn: &mut Connection,
+    id: K,
+) -> Result<usize> {
+    let table = T::default();
+    check_writable(conn, &table)?;
+
+    let values = id.into_values();
+    let condition = primary_key_condition(&table, &values)?;
+
+    let (query, params) = delete_statement(&Sqlite, Some(&table), Some(&condition), false);
+
+    let sensitive = Sensitive::primary_key(&table, &values);
+
+    let count = logger::run(&query, &sensitive.mask(&params), |sql| {
+        prepare_cached(conn, sql)?.execute(params_from_iter(&params))
+    })?;
+
+    info!("Deleted {} rows from table, done.", count);
+
+    Ok(count)
+}
+
+/// Deletes every row of `T`, including soft deleted rows, and returns the number of deleted rows.
+///
+/// With `reset_sequence` the AUTOINCREMENT counter of the table is reset as well,
+/// so new rows are numbered from 1 again.
+pub fn delete_all<T: Table + Default>(
+    conn: &mut Connection,
+    reset_sequence: bool,
+) -> Result<usize> {
+    let table = T::default();
+    check_writable(conn, &table)?;
+
+    // create a transaction
+    let tx = conn.savepoint()?;
+
+    // without a WHERE clause sqlite truncates the table instead of deleting row by row
+    let (query, _) = delete_statement(&Sqlite, Some(&table), None, true);
+
+    let count = logger::run(&query, &[], |sql| tx.execute(sql, []))?;
+
+    if reset_sequence {
+        // sqlite_sequence only exists once a table with AUTOINCREMENT was created
+        let has_sequence = tx
+            .prepare(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'sqlite_sequence'",
+            )?
+            .exists([])?;
+
+        if has_sequence {
+            tx.execute(
+                "DELETE FROM sqlite_sequence WHERE name = ?",
+                [table.get_name()],
+            )?;
+        }
+    }
+
+    // commit the transaction
+    tx.commit()?;
+
+    info!("Deleted {} rows from table, done.", count);
+
+    Ok(count)
+}
@@ -0,0 +1,40 @@
+/// A step of a query plan, as returned by `EXPLAIN QUERY PLAN`.
+#[derive(Debug, Clone)]
+pub struct QueryPlanStep {
+    pub id: i64,
+    pub parent: i64,
+    pub detail: String,
+}
+
+impl QueryPlanStep {
+    /// Returns `true` if the step looks rows up through an index instead of scanning the table.
+    pub fn uses_index(&self) -> bool {
+        self.detail.contains(" USING INDEX ")
+            || self.detail.contains(" USING COVERING INDEX ")
+            || self.detail.contains(" USING INTEGER PRIMARY KEY ")
+            || self.detail.contains(" USING PRIMARY KEY ")
+    }
+
+    /// Returns `true` if the step scans the whole table.
+    pub fn is_full_scan(&self) -> bool {
+        self.detail.starts_with("SCAN ") && !self.uses_index()
+    }
+}
+
+/// The plan SQLite would use to run a query.
+#[derive(Debug, Clone)]
+pub struct QueryPlan {
+    pub steps: Vec<QueryPlanStep>,
+}
+
+impl QueryPlan {
+    /// Returns `true` if any step of the plan uses an index.
+    pub fn uses_index(&self) -> bool {
+        self.steps.iter().any(|step| step.uses_index())
+    }
+
+    /// Returns `true` if any step of the plan scans a whole table.
+    pub fn has_full_scan(&self) -> bool {
+        self.steps.iter().any(|step| step.is_full_scan())
+    }
+}
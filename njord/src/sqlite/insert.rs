@@ -1,63 +1,440 @@
+use crate::dialect::{Dialect, Sqlite};
+use crate::error::{Error, Result};
+use crate::insert::{get_insert_columns, get_insert_values};
+use crate::logger;
+use crate::sensitive::Sensitive;
+use crate::statement::insert_statement;
 use crate::table::Table;
-use crate::util::convert_insert_values;
+use crate::trace;
+use crate::value::Value;
+
+use super::key::check_writable;
+use super::query::{prepare_cached, query_rows, QueryBuilder};
 
 use log::info;
-use rusqlite::{Connection, Result};
-use std::fmt::Error;
+use rusqlite::limits::Limit;
+use rusqlite::{params_from_iter, CachedStatement, Connection, OptionalExtension, StatementStatus};
+use std::marker::PhantomData;
 
-pub fn insert(mut conn: Connection, table_row: &dyn Table) -> Result<()> {
-    // create a transaction
-    let tx = conn.transaction()?;
+/// The maximum number of host parameters in a statement of the connection, which is 32766
+/// for the bundled sqlite unless it was lowered with `Connection::set_limit`.
+pub(crate) fn max_variable_number(conn: &Connection) -> Result<usize> {
+    let limit = conn.limit(Limit::SQLITE_LIMIT_VARIABLE_NUMBER)?;
 
-    let statement = generate_statement(table_row);
+    Ok(usize::try_from(limit).unwrap_or_default().max(1))
+}
 
-    let generated_statement = match statement {
-        Ok(statement) => statement,
-        Err(error) => panic!("Problem generating statement: {:?}.", error),
-    };
+/// The conflict resolution algorithm of an insert, e.g. `INSERT OR IGNORE`.
+pub enum InsertMode {
+    Abort,
+    Fail,
+    Ignore,
+    Replace,
+    Rollback,
+}
 
-    tx.execute(generated_statement.as_str(), [])?;
+impl InsertMode {
+    /// Builds the SQL keywords that start the insert.
+    pub fn build(&self) -> String {
+        match self {
+            InsertMode::Abort => "INSERT OR ABORT".to_string(),
+            InsertMode::Fail => "INSERT OR FAIL".to_string(),
+            InsertMode::Ignore => "INSERT OR IGNORE".to_string(),
+            InsertMode::Replace => "INSERT OR REPLACE".to_string(),
+            InsertMode::Rollback => "INSERT OR ROLLBACK".to_string(),
+        }
+    }
+}
 
-    // commit the transaction
-    tx.commit()?;
+/// Inserts a row. An auto-increment primary key is assigned by the database and
+/// written back into the row.
+pub fn insert<'a>(conn: &'a mut Connection, table_row: &'a mut dyn Table) -> InsertBuilder<'a> {
+    InsertBuilder::new(conn, table_row)
+}
 
-    info!("Inserted into table, done.");
+pub struct InsertBuilder<'a> {
+    conn: &'a mut Connection,
+    table_row: &'a mut dyn Table,
+    mode: Option<InsertMode>,
+    conflict_columns: Option<Vec<String>>,
+    update_columns: Vec<String>,
+}
 
-    Ok(())
+impl<'a> InsertBuilder<'a> {
+    pub fn new(conn: &'a mut Connection, table_row: &'a mut dyn Table) -> Self {
+        InsertBuilder {
+            conn,
+            table_row,
+            mode: None,
+            conflict_columns: None,
+            update_columns: Vec::new(),
+        }
+    }
+
+    /// Sets the conflict resolution algorithm, e.g. `InsertMode::Ignore` for idempotent imports.
+    pub fn mode(mut self, mode: InsertMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Sets the columns of the unique constraint that can conflict with the inserted row.
+    ///
+    /// Without `do_update` the conflicting row is left as it is.
+    pub fn on_conflict(mut self, columns: &[&str]) -> Self {
+        self.conflict_columns = Some(columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    /// Updates the given columns of the conflicting row with the values of the inserted row.
+    pub fn do_update(mut self, columns: &[&str]) -> Self {
+        self.update_columns = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    /// Leaves the conflicting row as it is.
+    pub fn do_nothing(mut self) -> Self {
+        self.update_columns.clear();
+        self
+    }
+
+    /// Inserts the row and returns the number of inserted or updated rows, which is
+    /// 0 if the row was skipped, e.g. with `do_nothing`.
+    pub fn build(self) -> Result<usize> {
+        if self.table_row.get_auto_increment_column().is_some() {
+            return self.execute_returning_id().map(|id| id.map_or(0, |_| 1));
+        }
+
+        check_writable(self.conn, self.table_row)?;
+
+        // create a transaction
+        let tx = self.conn.savepoint()?;
+
+        let generated_statement = generate_builder_statement(
+            self.table_row,
+            self.mode.as_ref(),
+            &self.conflict_columns,
+            &self.update_columns,
+        );
+
+        // bind the column values to the statement parameters
+        let values = get_insert_values(self.table_row)?;
+
+        let sensitive = Sensitive::rows([&*self.table_row]);
+
+        let count = logger::run(&generated_statement, &sensitive.mask(&values), |sql| {
+            prepare_cached(&tx, sql)?.execute(params_from_iter(&values))
+        })?;
+
+        // commit the transaction
+        tx.commit()?;
+
+        info!("Inserted into table, done.");
+
+        Ok(count)
+    }
+
+    /// Inserts the row and returns it as stored, including generated keys and column defaults.
+    ///
+    /// Returns `Error::NotFound` if the row was not inserted, e.g. with `do_nothing`.
+    pub fn build_returning<T: Table + Default>(self) -> Result<T> {
+        check_writable(self.conn, self.table_row)?;
+
+        let generated_statement = self.generate_returning_statement("*");
+
+        // create a transaction
+        let tx = self.conn.savepoint()?;
+
+        let values = get_insert_values(self.table_row)?;
+
+        let sensitive = Sensitive::rows([&*self.table_row]);
+
+        let mut rows = query_rows::<T>(&tx, generated_statement.as_str(), &values, &sensitive)?;
+
+        // commit the transaction
+        tx.commit()?;
+
+        info!("Inserted into table, done.");
+
+        rows.pop().ok_or(Error::NotFound)
+    }
+
+    /// Inserts the row and returns the rowid assigned to it, i.e. its `INTEGER PRIMARY KEY`.
+    ///
+    /// Returns `Error::NotFound` if the row was not inserted, e.g. with `do_nothing`.
+    pub fn build_returning_id(self) -> Result<i64> {
+        self.execute_returning_id()?.ok_or(Error::NotFound)
+    }
+
+    fn execute_returning_id(self) -> Result<Option<i64>> {
+        check_writable(self.conn, self.table_row)?;
+
+        let generated_statement = self.generate_returning_statement("rowid");
+
+        // create a transaction
+        let tx = self.conn.savepoint()?;
+
+        let values = get_insert_values(self.table_row)?;
+
+        // the rowid is returned even when a conflicting row is updated instead
+        let sensitive = Sensitive::rows([&*self.table_row]);
+
+        let id = logger::run(&generated_statement, &sensitive.mask(&values), |sql| {
+            prepare_cached(&tx, sql)?
+                .query_row(params_from_iter(&values), |row| row.get(0))
+                .optional()
+        })?;
+
+        // commit the transaction
+        tx.commit()?;
+
+        if let Some(id) = id {
+            set_generated_id(self.table_row, id)?;
+        }
+
+        info!("Inserted into table, done.");
+
+        Ok(id)
+    }
+
+    fn generate_returning_statement(&self, columns: &str) -> String {
+        let generated_statement = generate_builder_statement(
+            self.table_row,
+            self.mode.as_ref(),
+            &self.conflict_columns,
+            &self.update_columns,
+        );
+
+        // RETURNING requires sqlite 3.35 or newer
+        format!("{} RETURNING {}", generated_statement, columns)
+    }
+}
+
+/// Inserts a row and returns it as stored by the database, see `InsertBuilder::build_returning`.
+pub fn insert_returning<T: Table + Default>(conn: &mut Connection, table_row: &mut T) -> Result<T> {
+    insert(conn, table_row).build_returning()
+}
+
+/// An insert statement that is prepared once and executed for many rows of the same table.
+///
+/// The statement is taken from the statement cache of the connection and held until it is
+/// dropped, while the SQL rewritten by a `Middleware` is compiled into the cache instead.
+/// Prepare it on a `Transaction` to insert all rows within a single transaction.
+pub struct InsertStatement<'a, T: Table> {
+    conn: &'a Connection,
+    sql: String,
+    statement: CachedStatement<'a>,
+    table: PhantomData<T>,
 }
 
-fn generate_statement(table_row: &dyn Table) -> Result<String, Error> {
-    // generate string for columns
-    let mut columns_str = String::new();
-    for column_name in table_row.get_column_fields() {
-        columns_str.push_str(&format!("{}, ", column_name));
+impl<'a, T: Table + Default> InsertStatement<'a, T> {
+    pub fn new(conn: &'a Connection) -> Result<Self> {
+        let table = T::default();
+        check_writable(conn, &table)?;
+
+        let generated_statement = generate_statement(&table, 1, None);
+
+        let statement = trace::prepare(generated_statement.as_str(), || {
+            conn.prepare_cached(generated_statement.as_str())
+        })?;
+
+        Ok(InsertStatement {
+            conn,
+            sql: generated_statement,
+            statement,
+            table: PhantomData,
+        })
     }
 
-    // surround single quotes of text
-    let converted_values = convert_insert_values(table_row.get_column_values());
+    /// Inserts the row by binding its column values to the prepared statement, and returns
+    /// the number of inserted rows, which is 0 if a conflict clause of the table skipped it.
+    pub fn execute(&mut self, table_row: &mut T) -> Result<usize> {
+        let values = get_insert_values(table_row)?;
 
-    // // generate values string
-    let mut values_str = String::new();
-    for value in converted_values {
-        let data_type_str = value.to_string();
-        values_str.push_str(&data_type_str);
-        values_str.push_str(", ");
+        let sensitive = Sensitive::rows([&*table_row]);
+
+        let count = logger::run(&self.sql, &sensitive.mask(&values), |sql| {
+            if sql != self.sql {
+                return prepare_cached(self.conn, sql)?.execute(params_from_iter(&values));
+            }
+
+            // the statement has only not run yet on its first row
+            logger::record_cache_hit(self.statement.get_status(StatementStatus::Run) > 0);
+
+            self.statement.execute(params_from_iter(&values))
+        })?;
+
+        if count > 0 {
+            set_generated_id(table_row, self.conn.last_insert_rowid())?;
+        }
+
+        Ok(count)
     }
+}
+
+/// Inserts the rows of a select query into the target table, without loading them.
+///
+/// The selected columns are matched to the given target columns by position;
+/// with no target columns they are matched to all columns of the target table.
+pub fn insert_from_select(
+    target_table: &dyn Table,
+    columns: Vec<String>,
+    select_builder: QueryBuilder,
+) -> Result<usize> {
+    let conn = select_builder.get_conn()?;
+    check_writable(conn, target_table)?;
 
-    // remove the trailing comma and space
-    columns_str.pop();
-    columns_str.pop();
-    values_str.pop();
-    values_str.pop();
+    let (select_query, params) = select_builder.build_query();
 
-    let sql = format!(
-        "INSERT INTO {} ({}) VALUES ({});",
-        table_row.get_name(),
+    let columns_str = if columns.is_empty() {
+        String::new()
+    } else {
+        let columns: Vec<String> = columns
+            .iter()
+            .map(|column| Sqlite.quote_identifier(column))
+            .collect();
+        format!(" ({})", columns.join(", "))
+    };
+
+    let statement = format!(
+        "INSERT INTO {}{} {}",
+        Sqlite.quote_identifier(target_table.get_name()),
         columns_str,
-        values_str
+        select_query
     );
 
-    println!("{}", sql);
+    // a single statement is atomic, so no transaction is needed
+    let count = logger::run(
+        &statement,
+        &select_builder.sensitive().mask(&params),
+        |sql| prepare_cached(conn, sql)?.execute(params_from_iter(&params)),
+    )?;
+
+    info!("Inserted into table, done.");
+
+    Ok(count)
+}
+
+/// Inserts many rows of the same table within a single transaction.
+///
+/// The rows are inserted with multi-row `INSERT ... VALUES (...), (...)` statements,
+/// each holding as many rows as the sqlite parameter limit allows.
+pub fn insert_many<T: Table>(conn: &mut Connection, table_rows: &mut [T]) -> Result<usize> {
+    insert_rows(conn, table_rows, None)
+}
+
+/// Inserts many rows of the same table within a single transaction,
+/// resolving conflicts with the given algorithm.
+pub fn insert_many_with_mode<T: Table>(
+    conn: &mut Connection,
+    table_rows: &mut [T],
+    mode: InsertMode,
+) -> Result<usize> {
+    insert_rows(conn, table_rows, Some(&mode))
+}
+
+fn insert_rows<T: Table>(
+    conn: &mut Connection,
+    table_rows: &mut [T],
+    mode: Option<&InsertMode>,
+) -> Result<usize> {
+    let first_row = match table_rows.first() {
+        Some(row) => row,
+        None => return Ok(0),
+    };
+
+    check_writable(conn, first_row)?;
+
+    let column_count = get_insert_columns(first_row).len();
+    let rows_per_statement = match column_count {
+        // rows without columns are inserted with DEFAULT VALUES, one at a time
+        0 => 1,
+        _ => (max_variable_number(conn)? / column_count).max(1),
+    };
+
+    // create a transaction
+    let tx = conn.savepoint()?;
+
+    let mut count = 0;
+    for chunk in table_rows.chunks_mut(rows_per_statement) {
+        let generated_statement = generate_statement(&chunk[0], chunk.len(), mode);
+
+        // bind the column values of every row to the statement parameters
+        let values = chunk
+            .iter_mut()
+            .map(|row| get_insert_values(row))
+            .collect::<Result<Vec<_>>>()?
+            .concat();
+
+        let sensitive = Sensitive::rows(chunk.iter());
+
+        if chunk[0].get_auto_increment_column().is_none() {
+            count += logger::run(&generated_statement, &sensitive.mask(&values), |sql| {
+                prepare_cached(&tx, sql)?.execute(params_from_iter(&values))
+            })?;
+
+            continue;
+        }
+
+        // the rowids are returned in the order of the rows, e.g. of a REPLACE that
+        // removed a conflicting row, instead of being counted back from the last one
+        let generated_statement = format!("{} RETURNING rowid", generated_statement);
+
+        let ids = logger::run(&generated_statement, &sensitive.mask(&values), |sql| {
+            prepare_cached(&tx, sql)?
+                .query_map(params_from_iter(&values), |row| row.get::<_, i64>(0))?
+                .collect::<rusqlite::Result<Vec<i64>>>()
+        })?;
+        count += ids.len();
+
+        // the rowids can only be matched with their rows when none of them was skipped
+        if ids.len() == chunk.len() {
+            for (row, id) in chunk.iter_mut().zip(ids) {
+                set_generated_id(row, id)?;
+            }
+        }
+    }
+
+    // commit the transaction
+    tx.commit()?;
 
-    Ok(sql)
+    info!("Inserted {} rows into table, done.", count);
+
+    Ok(count)
+}
+
+fn generate_statement(
+    table_row: &dyn Table,
+    row_count: usize,
+    mode: Option<&InsertMode>,
+) -> String {
+    let generated_statement = insert_statement(&Sqlite, table_row, row_count);
+
+    // the conflict resolution algorithm follows the INSERT keyword, e.g. INSERT OR IGNORE
+    match mode {
+        Some(mode) => generated_statement.replacen("INSERT", &mode.build(), 1),
+        None => generated_statement,
+    }
+}
+
+/// Writes the rowid assigned by the database into the auto-increment primary key.
+fn set_generated_id(table_row: &mut dyn Table, id: i64) -> Result<()> {
+    if let Some(column) = table_row.get_auto_increment_column() {
+        table_row.set_column_value(&column, Value::Integer(id))?;
+    }
+
+    Ok(())
+}
+
+fn generate_builder_statement(
+    table_row: &dyn Table,
+    mode: Option<&InsertMode>,
+    conflict_columns: &Option<Vec<String>>,
+    update_columns: &[String],
+) -> String {
+    let generated_statement = generate_statement(table_row, 1, mode);
+
+    match conflict_columns {
+        Some(columns) => Sqlite.upsert(&generated_statement, columns, update_columns, None),
+        None => generated_statement,
+    }
 }
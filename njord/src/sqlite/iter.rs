@@ -0,0 +1,104 @@
+use crate::error::Result;
+use crate::sensitive::Sensitive;
+use crate::table::Table;
+use crate::value::Value;
+use std::collections::VecDeque;
+
+use rusqlite::Connection;
+
+use super::query::{missing_conn, query_rows};
+
+/// A lazy iterator over the rows of a query.
+///
+/// Rows are fetched in chunks by wrapping the query with `LIMIT` and `OFFSET`,
+/// so only one chunk is held in memory at a time.
+pub struct QueryIter<'a, T: Table + Default> {
+    conn: Option<&'a Connection>,
+    query: String,
+    params: Vec<Value>,
+    sensitive: Sensitive,
+    chunk_size: usize,
+    offset: usize,
+    rows: VecDeque<T>,
+    in_savepoint: bool,
+    done: bool,
+}
+
+impl<'a, T: Table + Default> QueryIter<'a, T> {
+    pub(crate) fn new(
+        conn: Option<&'a Connection>,
+        query: String,
+        params: Vec<Value>,
+        sensitive: Sensitive,
+        chunk_size: usize,
+    ) -> Self {
+        QueryIter {
+            conn,
+            query,
+            params,
+            sensitive,
+            chunk_size: chunk_size.max(1),
+            offset: 0,
+            rows: VecDeque::new(),
+            in_savepoint: false,
+            done: false,
+        }
+    }
+
+    fn fetch_chunk(&mut self) -> Result<()> {
+        let conn = self.conn.ok_or_else(missing_conn)?;
+
+        if !self.in_savepoint {
+            conn.execute_batch("SAVEPOINT njord_iter")?;
+            self.in_savepoint = true;
+        }
+
+        let query = format!(
+            "SELECT * FROM ({}) LIMIT {} OFFSET {}",
+            self.query, self.chunk_size, self.offset
+        );
+        let rows = query_rows::<T>(conn, &query, &self.params, &self.sensitive)?;
+
+        if rows.len() < self.chunk_size {
+            self.finish()?;
+        }
+
+        self.offset += rows.len();
+        self.rows.extend(rows);
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.done = true;
+
+        if let Some(conn) = self.conn.filter(|_| self.in_savepoint) {
+            self.in_savepoint = false;
+            conn.execute_batch("RELEASE njord_iter")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, T: Table + Default> Iterator for QueryIter<'a, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rows.is_empty() && !self.done {
+            if let Err(error) = self.fetch_chunk() {
+                // stop after the first error instead of retrying the same chunk
+                let _ = self.finish();
+                return Some(Err(error));
+            }
+        }
+
+        self.rows.pop_front().map(Ok)
+    }
+}
+
+impl<'a, T: Table + Default> Drop for QueryIter<'a, T> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
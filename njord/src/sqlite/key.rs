@@ -0,0 +1,142 @@
+use crate::condition::Condition;
+use crate::dialect::{Dialect, Sqlite};
+use crate::error::{Error, Result};
+use crate::table::Table;
+use crate::value::Value;
+
+use rusqlite::{ffi, Connection};
+
+/// A primary key value, given as a single value or as a tuple of the values
+/// of a composite primary key, e.g. `(order_id, line_no)`.
+pub trait PrimaryKey {
+    /// Get the values of the primary key columns, in the order they are declared.
+    fn into_values(self) -> Vec<Value>;
+}
+
+macro_rules! single_primary_key {
+    ($($t:ty),*) => {
+        $(
+            impl PrimaryKey for $t {
+                fn into_values(self) -> Vec<Value> {
+                    vec![self.into()]
+                }
+            }
+        )*
+    };
+}
+
+single_primary_key!(
+    Value,
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    isize,
+    u8,
+    u16,
+    u32,
+    String,
+    Vec<u8>
+);
+
+// a UUID key stored as TEXT is given as a string instead
+#[cfg(feature = "uuid")]
+single_primary_key!(uuid::Uuid);
+
+impl PrimaryKey for &str {
+    fn into_values(self) -> Vec<Value> {
+        vec![Value::Text(self.to_string())]
+    }
+}
+
+macro_rules! composite_primary_key {
+    ($($name:ident),*) => {
+        impl<$($name: Into<Value>),*> PrimaryKey for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn into_values(self) -> Vec<Value> {
+                let ($($name,)*) = self;
+                vec![$($name.into()),*]
+            }
+        }
+    };
+}
+
+composite_primary_key!(A, B);
+composite_primary_key!(A, B, C);
+composite_primary_key!(A, B, C, D);
+
+/// Builds the condition matching the row with the given primary key values,
+/// e.g. `("order_id" = ?) AND ("line_no" = ?)`, with one placeholder per column.
+pub(crate) fn primary_key_condition(table_row: &dyn Table, values: &[Value]) -> Result<Condition> {
+    let primary_key_columns = table_row.get_primary_key_columns();
+
+    if primary_key_columns.is_empty() {
+        return Err(missing_primary_key(table_row.get_name()));
+    }
+
+    if primary_key_columns.len() != values.len() {
+        return Err(misuse(format!(
+            "table {} has {} primary key columns, but {} values were given",
+            table_row.get_name(),
+            primary_key_columns.len(),
+            values.len()
+        )));
+    }
+
+    let condition = primary_key_columns
+        .iter()
+        .zip(values)
+        .map(|(column, value)| Condition::Eq(Sqlite.quote_identifier(column), value.clone()))
+        .reduce(Condition::and);
+
+    condition.ok_or_else(|| missing_primary_key(table_row.get_name()))
+}
+
+/// Returns an error if the table maps a read-only view, or the connection was opened
+/// read-only, so the table cannot be written to.
+pub(crate) fn check_writable(conn: &Connection, table_row: &dyn Table) -> Result<()> {
+    // a table of an attached database is written to that database
+    let database = match table_row.get_name().split_once('.') {
+        Some((schema, _)) => schema,
+        None => "main",
+    };
+
+    if conn.is_readonly(database)? {
+        return Err(rusqlite::Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_READONLY),
+            Some(format!(
+                "cannot write to table {}, the connection is read-only",
+                table_row.get_name()
+            )),
+        )
+        .into());
+    }
+
+    if table_row.is_readonly() {
+        return Err(misuse(format!(
+            "table {} is read-only",
+            table_row.get_name()
+        )));
+    }
+
+    Ok(())
+}
+
+/// The error returned when a helper keyed on the primary key is used on a table without one.
+pub(crate) fn missing_primary_key(table_name: &str) -> Error {
+    misuse(format!("table {} has no primary key", table_name))
+}
+
+/// The error returned when an update or delete is ordered without a limit, which only
+/// orders the rows picked by the limit.
+pub(crate) fn order_without_limit(statement: &str) -> Error {
+    misuse(format!(
+        "the order_by of {} requires a limit, as it only orders the rows picked by it",
+        statement
+    ))
+}
+
+pub(crate) fn misuse(message: String) -> Error {
+    Error::Generation(message)
+}
@@ -0,0 +1,106 @@
+use crate::error::Result;
+use crate::table::Table;
+use crate::value::Value;
+
+/// An opaque position in a keyset paginated query.
+///
+/// Holds the key values of the last seen row, in the same order as the key columns
+/// given to `QueryBuilder::keyset`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    values: Vec<Value>,
+}
+
+impl Cursor {
+    pub fn new(values: Vec<Value>) -> Self {
+        Cursor { values }
+    }
+
+    /// Creates a cursor pointing at the given row, e.g. the last row of a page.
+    ///
+    /// Columns may be qualified with the table name; unknown columns are set to NULL.
+    pub fn from_row(row: &dyn Table, columns: &[String]) -> Result<Self> {
+        let fields = row.get_column_fields();
+        let values = row.get_column_values()?;
+
+        let values = columns
+            .iter()
+            .map(|column| {
+                let name = column.rsplit('.').next().unwrap_or(column);
+                fields
+                    .iter()
+                    .position(|field| field == name)
+                    .map_or(Value::Null, |index| values[index].clone())
+            })
+            .collect();
+
+        Ok(Cursor { values })
+    }
+
+    /// Get the key values of the cursor.
+    pub fn get_values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Encodes the cursor into an opaque string, e.g. to be passed in a URL.
+    pub fn encode(&self) -> String {
+        self.values
+            .iter()
+            .map(|value| match value {
+                Value::Null => "n".to_string(),
+                Value::Integer(i) => format!("i{}", encode_hex(i.to_string().as_bytes())),
+                Value::Real(f) => format!("r{}", encode_hex(f.to_string().as_bytes())),
+                Value::Text(s) => format!("t{}", encode_hex(s.as_bytes())),
+                Value::Blob(b) => format!("b{}", encode_hex(b)),
+            })
+            .collect::<Vec<String>>()
+            .join(".")
+    }
+
+    /// Decodes a cursor from a string returned by `encode`.
+    ///
+    /// Returns `None` if the string is not a valid cursor.
+    pub fn decode(encoded: &str) -> Option<Self> {
+        if encoded.is_empty() {
+            return Some(Cursor { values: Vec::new() });
+        }
+
+        let values = encoded
+            .split('.')
+            .map(|part| {
+                // the tag is split off as a char, as the string may not be a cursor at all
+                let mut chars = part.chars();
+                let tag = chars.next();
+                let payload = chars.as_str();
+                match tag {
+                    Some('n') if payload.is_empty() => Some(Value::Null),
+                    Some('i') => decode_text(payload)?.parse().ok().map(Value::Integer),
+                    Some('r') => decode_text(payload)?.parse().ok().map(Value::Real),
+                    Some('t') => decode_text(payload).map(Value::Text),
+                    Some('b') => decode_hex(payload).map(Value::Blob),
+                    _ => None,
+                }
+            })
+            .collect::<Option<Vec<Value>>>()?;
+
+        Some(Cursor { values })
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [high, low] => u8::from_str_radix(std::str::from_utf8(&[*high, *low]).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+fn decode_text(hex: &str) -> Option<String> {
+    String::from_utf8(decode_hex(hex)?).ok()
+}
@@ -1,17 +1,83 @@
 use std::env;
 
-use rusqlite::{Connection, Error};
+use rusqlite::OpenFlags;
+
+/// The connection the sqlite functions run on.
+pub use rusqlite::Connection;
+
+/// The error and result the sqlite functions return, e.g. in the relation accessors
+/// generated by `derive(Table)`, with the `rusqlite::Error` of the driver as the source.
+pub use crate::error::{Error, Result};
 
 pub mod insert;
-pub use insert::insert;
+pub use insert::{
+    insert, insert_from_select, insert_many, insert_many_with_mode, insert_returning, InsertMode,
+    InsertStatement,
+};
 pub mod select;
-pub use select::select;
-pub mod condition;
-pub use condition::Condition;
+pub use select::{find, select, subquery};
+pub mod codegen;
+pub mod connection;
+pub use crate::condition::{self, Condition};
+pub use crate::join::{self, JoinType};
+#[cfg(feature = "encryption")]
+pub use connection::rekey;
+pub use connection::{interrupt_handle, ConnectionBuilder, JournalMode, Synchronous};
+/// The handle cancelling the running statement of a connection, from `interrupt_handle`.
+pub use rusqlite::InterruptHandle;
+#[cfg(feature = "tokio")]
+pub mod asynchronous;
+#[cfg(feature = "tokio")]
+pub use asynchronous::AsyncConnection;
+pub mod aggregate;
+pub use aggregate::Aggregate;
+pub mod attach;
+pub use attach::{attach, detach, Qualified};
+pub mod backup;
+pub use backup::{backup, backup_to_file, BackupOptions};
+pub mod case;
+pub use case::Case;
+pub mod window;
+pub use window::{Window, WindowFunction};
+pub mod ddl;
+pub use ddl::{
+    add_column, create_indexes, create_table, create_table_if_not_exists, drop_column, drop_table,
+    drop_table_if_exists, rebuild_table, rename_column, rename_table, setup_schema, Schema,
+};
+pub mod explain;
+pub mod iter;
+pub mod page;
+pub use page::Page;
+pub mod keyset;
+pub use keyset::Cursor;
 pub mod query;
+pub mod raw;
+pub use raw::query_as;
+pub mod update;
+pub use update::{update, update_many};
+pub mod delete;
+pub use delete::{delete, delete_all, delete_by_id};
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "csv")]
+pub use csv::{load_csv, CsvOptions};
+pub(crate) mod key;
+pub use key::PrimaryKey;
+pub mod relation;
+pub use relation::{HasMany, Lazy};
+// the backoff between attempts blocks the thread, which a browser cannot do
+#[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+pub mod retry;
+#[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+pub use retry::RetryPolicy;
+pub mod save;
+pub use save::save;
+pub mod schema;
+pub mod session;
+pub use session::Session;
 
 /// Open a database connection
-pub fn open(db_name: &str) -> Result<Connection, Error> {
+pub fn open(db_name: &str) -> Result<Connection> {
     let target_dir = env::var("OUT_DIR").unwrap_or_else(|_| "../target".to_string());
     let db_file_path = format!("{}/{}", target_dir, &db_name);
     let conn = Connection::open(db_file_path)?;
@@ -19,9 +85,41 @@ pub fn open(db_name: &str) -> Result<Connection, Error> {
     Ok(conn)
 }
 
+/// Open a read-only database connection, on which the write functions refuse to run
+pub fn open_read_only(db_name: &str) -> Result<Connection> {
+    let target_dir = env::var("OUT_DIR").unwrap_or_else(|_| "../target".to_string());
+    let db_file_path = format!("{}/{}", target_dir, &db_name);
+    let conn = Connection::open_with_flags(
+        db_file_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI,
+    )?;
+
+    Ok(conn)
+}
+
+/// Open a database connection from a url, e.g. `sqlite:///data/app.db?mode=rwc`,
+/// as in `ConnectionBuilder::from_url`
+pub fn from_url(url: &str) -> Result<Connection> {
+    ConnectionBuilder::from_url(url)?.open()
+}
+
+/// Open a database connection from the url in an environment variable, e.g. `DATABASE_URL`
+pub fn from_env(name: &str) -> Result<Connection> {
+    ConnectionBuilder::from_env(name)?.open()
+}
+
 /// Open an in-memory database connection
-pub fn open_in_memory() -> Result<Connection, Error> {
+pub fn open_in_memory() -> Result<Connection> {
     let conn = Connection::open_in_memory()?;
 
     Ok(conn)
 }
+
+/// Open an isolated in-memory database with the tables of `S` already created,
+/// e.g. `open_in_memory_with_schema::<(User, Post)>()` in a unit test
+pub fn open_in_memory_with_schema<S: Schema>() -> Result<Connection> {
+    let conn = open_in_memory()?;
+    setup_schema::<S>(&conn)?;
+
+    Ok(conn)
+}
@@ -0,0 +1,23 @@
+/// A page of rows returned by `QueryBuilder::paginate`.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The number of rows matched by the query across all pages.
+    pub total: u64,
+    /// The current page, starting at 1.
+    pub page: u64,
+    pub per_page: u64,
+    pub total_pages: u64,
+}
+
+impl<T> Page<T> {
+    /// Returns `true` if there is a page after this one.
+    pub fn has_next(&self) -> bool {
+        self.page < self.total_pages
+    }
+
+    /// Returns `true` if there is a page before this one.
+    pub fn has_previous(&self) -> bool {
+        self.page > 1
+    }
+}
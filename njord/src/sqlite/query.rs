@@ -1,60 +1,151 @@
+use crate::dialect::{Dialect, Sqlite};
+use crate::logger;
+use crate::sensitive::Sensitive;
 use crate::table::Table;
+use crate::trace;
+use crate::value::Value;
 use std::collections::HashMap;
 
-use rusqlite::{Connection, Result};
+use rusqlite::{params_from_iter, CachedStatement, Connection, Params, StatementStatus};
 
-use log::info;
-use rusqlite::types::Value;
+use rusqlite::types::FromSql;
 
-use super::Condition;
+use super::aggregate::AggregateRow;
+use super::explain::{QueryPlan, QueryPlanStep};
+use super::iter::QueryIter;
+use super::key::misuse;
+use super::keyset::Cursor;
+use super::page::Page;
+use super::relation::WithRelated;
+use super::{Aggregate, Case, Condition, JoinType, Window};
+use crate::condition::Subquery;
+use crate::error::{Error, Result};
+
+/// Which rows of a soft deleting table a query returns.
+enum SoftDeleted {
+    Exclude,
+    Include,
+    Only,
+}
 
 pub struct QueryBuilder<'a> {
-    conn: Connection,
+    conn: Option<&'a Connection>,
     table: Option<&'a dyn Table>,
+    joins: Vec<(JoinType, &'a dyn Table, Option<Condition>)>,
     columns: Vec<String>,
+    column_params: Vec<Value>,
     where_condition: Option<Condition>,
     selected: bool,
     distinct: bool,
     group_by: Option<Vec<String>>,
     order_by: Option<HashMap<Vec<String>, String>>,
+    order_by_cases: Vec<(Case, String)>,
     limit: Option<usize>,
     offset: Option<usize>,
     having_condition: Option<Condition>,
+    compound: Vec<(String, Subquery)>,
+    ctes: Vec<(String, Vec<String>, Subquery)>,
+    recursive: bool,
+    keyset: Vec<String>,
+    cursor: Option<(String, Cursor)>,
+    soft_deleted: SoftDeleted,
 }
 
 impl<'a> QueryBuilder<'a> {
-    pub fn new(conn: Connection, columns: Vec<String>) -> Self {
+    pub fn new(conn: &'a Connection, columns: Vec<String>) -> Self {
+        Self::with_conn(Some(conn), columns)
+    }
+
+    pub(crate) fn with_conn(conn: Option<&'a Connection>, columns: Vec<String>) -> Self {
         QueryBuilder {
             conn,
             table: None,
+            joins: Vec::new(),
             columns,
+            column_params: Vec::new(),
             where_condition: None,
             selected: false,
             distinct: false,
             group_by: None,
             order_by: None,
+            order_by_cases: Vec::new(),
             limit: None,
             offset: None,
             having_condition: None,
+            compound: Vec::new(),
+            ctes: Vec::new(),
+            recursive: false,
+            keyset: Vec::new(),
+            cursor: None,
+            soft_deleted: SoftDeleted::Exclude,
         }
     }
 
     pub fn select(mut self, columns: Vec<String>) -> Self {
         self.columns = columns;
+        self.column_params.clear();
         self.selected = true;
         self
     }
 
+    /// Adds aggregate functions to the selected columns.
+    ///
+    /// The result should be fetched with `build_aggregate` or `build_aggregates`
+    /// instead of being mapped into a struct.
+    pub fn select_aggregates(mut self, aggregates: Vec<Aggregate>) -> Self {
+        self.columns
+            .extend(aggregates.iter().map(|aggregate| aggregate.build()));
+        self
+    }
+
+    /// Adds window function expressions to the selected columns.
+    pub fn select_windows(mut self, windows: Vec<Window>) -> Self {
+        self.columns
+            .extend(windows.iter().map(|window| window.build()));
+        self
+    }
+
+    /// Adds `CASE WHEN` expressions to the selected columns.
+    pub fn select_cases(mut self, cases: Vec<Case>) -> Self {
+        for case in cases {
+            self.columns.push(case.build());
+            self.column_params.extend(case.get_params());
+        }
+        self
+    }
+
     pub fn distinct(mut self) -> Self {
         self.distinct = true;
         self
     }
 
+    /// Includes the soft deleted rows of the table, which are left out by default.
+    pub fn with_deleted(mut self) -> Self {
+        self.soft_deleted = SoftDeleted::Include;
+        self
+    }
+
+    /// Keeps only the soft deleted rows of the table.
+    pub fn only_deleted(mut self) -> Self {
+        self.soft_deleted = SoftDeleted::Only;
+        self
+    }
+
     pub fn from(mut self, table: &'a dyn Table) -> Self {
         self.table = Some(table);
         self
     }
 
+    pub fn join(mut self, join_type: JoinType, table: &'a dyn Table, on: Condition) -> Self {
+        self.joins.push((join_type, table, Some(on)));
+        self
+    }
+
+    pub fn cross_join(mut self, table: &'a dyn Table) -> Self {
+        self.joins.push((JoinType::Cross, table, None));
+        self
+    }
+
     pub fn where_clause(mut self, condition: Condition) -> Self {
         self.where_condition = Some(condition);
         self
@@ -70,6 +161,32 @@ impl<'a> QueryBuilder<'a> {
         self
     }
 
+    /// Orders by a `CASE WHEN` expression, after any columns given to `order_by`.
+    pub fn order_by_case(mut self, case: Case, order: &str) -> Self {
+        self.order_by_cases.push((case, order.to_string()));
+        self
+    }
+
+    /// Orders the query by the given key columns, which should be unique and indexed,
+    /// so pages can be fetched with `after` and `before` instead of OFFSET.
+    pub fn keyset(mut self, columns: Vec<String>) -> Self {
+        self.keyset = columns;
+        self
+    }
+
+    /// Keeps only the rows after the cursor, in ascending key order.
+    pub fn after(mut self, cursor: Cursor) -> Self {
+        self.cursor = Some((">".to_string(), cursor));
+        self
+    }
+
+    /// Keeps only the rows before the cursor, in descending key order,
+    /// so the rows nearest to the cursor come first.
+    pub fn before(mut self, cursor: Cursor) -> Self {
+        self.cursor = Some(("<".to_string(), cursor));
+        self
+    }
+
     pub fn limit(mut self, count: usize) -> Self {
         self.limit = Some(count);
         self
@@ -85,7 +202,53 @@ impl<'a> QueryBuilder<'a> {
         self
     }
 
-    pub fn build<T: Table + Default>(self) -> Result<Vec<T>> {
+    /// Adds a common table expression, making the rows of another query available
+    /// under the given name, e.g. as the table of a join.
+    pub fn with(mut self, name: &str, query: QueryBuilder) -> Self {
+        self.ctes.push((name.to_string(), Vec::new(), query.into()));
+        self
+    }
+
+    /// Adds a recursive common table expression with the given columns.
+    ///
+    /// The query is usually a `union_all` of an initial select and a select that
+    /// joins on the expression itself, e.g. to walk a tree of rows.
+    pub fn with_recursive(mut self, name: &str, columns: Vec<String>, query: QueryBuilder) -> Self {
+        self.ctes.push((name.to_string(), columns, query.into()));
+        self.recursive = true;
+        self
+    }
+
+    /// Combines the rows of this query with the rows of another query, removing duplicates.
+    ///
+    /// The ORDER BY, LIMIT and OFFSET of this query apply to the combined result,
+    /// so the other query should not set them.
+    pub fn union(self, other: QueryBuilder) -> Self {
+        self.compound("UNION", other)
+    }
+
+    /// Combines the rows of this query with the rows of another query, keeping duplicates.
+    pub fn union_all(self, other: QueryBuilder) -> Self {
+        self.compound("UNION ALL", other)
+    }
+
+    /// Keeps only the rows returned by both this query and another query.
+    pub fn intersect(self, other: QueryBuilder) -> Self {
+        self.compound("INTERSECT", other)
+    }
+
+    /// Keeps only the rows of this query that are not returned by another query.
+    pub fn except(self, other: QueryBuilder) -> Self {
+        self.compound("EXCEPT", other)
+    }
+
+    fn compound(mut self, operator: &str, other: QueryBuilder) -> Self {
+        self.compound.push((operator.to_string(), other.into()));
+        self
+    }
+
+    /// Generates the SQL for the query along with the values bound to its placeholders.
+    pub(crate) fn build_query(&self) -> (String, Vec<Value>) {
         let columns_str = self.columns.join(", ");
 
         let table_name_str = self
@@ -95,10 +258,92 @@ impl<'a> QueryBuilder<'a> {
 
         let distinct_str = if self.distinct { "DISTINCT " } else { "" };
 
-        let where_condition_str = if let Some(condition) = self.where_condition {
-            format!("WHERE {}", condition.build())
-        } else {
+        // values bound to the placeholders, in the order they appear in the query
+        let mut params: Vec<Value> = Vec::new();
+
+        let with_str = if self.ctes.is_empty() {
             String::new()
+        } else {
+            let ctes_str = self
+                .ctes
+                .iter()
+                .map(|(name, columns, subquery)| {
+                    params.extend(subquery.get_params());
+                    if columns.is_empty() {
+                        format!("{} AS ({})", name, subquery.get_sql())
+                    } else {
+                        format!(
+                            "{}({}) AS ({})",
+                            name,
+                            columns.join(", "),
+                            subquery.get_sql()
+                        )
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+
+            // RECURSIVE applies to the whole WITH clause
+            let recursive_str = if self.recursive { "RECURSIVE " } else { "" };
+            format!("WITH {}{} ", recursive_str, ctes_str)
+        };
+
+        params.extend(self.column_params.iter().cloned());
+
+        let join_str = self
+            .joins
+            .iter()
+            .map(|(join_type, table, on)| match on {
+                Some(condition) => {
+                    params.extend(condition.get_params());
+                    format!(
+                        "{} {} ON {}",
+                        join_type.build(),
+                        table.get_name(),
+                        condition.build()
+                    )
+                }
+                None => format!("{} {}", join_type.build(), table.get_name()),
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let mut where_str: Vec<String> = Vec::new();
+
+        if let Some(condition) = &self.where_condition {
+            params.extend(condition.get_params());
+            where_str.push(condition.build());
+        }
+
+        // compare the key columns with the cursor as row values, e.g. (a, b) > (?, ?)
+        if let Some((operator, cursor)) = &self.cursor {
+            let placeholders: Vec<&str> = cursor.get_values().iter().map(|_| "?").collect();
+            params.extend(cursor.get_values().iter().cloned());
+            where_str.push(format!(
+                "({}) {} ({})",
+                self.keyset.join(", "),
+                operator,
+                placeholders.join(", ")
+            ));
+        }
+
+        // soft deleted rows of the selected table are left out unless asked for
+        let soft_delete_column = self.table.and_then(|t| {
+            t.get_soft_delete_column()
+                .map(|column| format!("{}.{}", t.get_name(), column))
+        });
+        if let Some(column) = soft_delete_column {
+            match self.soft_deleted {
+                SoftDeleted::Exclude => where_str.push(Condition::IsNull(column).build()),
+                SoftDeleted::Only => where_str.push(Condition::IsNotNull(column).build()),
+                SoftDeleted::Include => {}
+            }
+        }
+
+        let where_condition_str = match where_str.len() {
+            0 => String::new(),
+            1 => format!("WHERE {}", where_str[0]),
+            _ => format!("WHERE ({})", where_str.join(") AND (")),
         };
 
         let group_by_str = match &self.group_by {
@@ -106,72 +351,391 @@ impl<'a> QueryBuilder<'a> {
             None => String::new(),
         };
 
-        let order_by_str = if let Some(order_by) = &self.order_by {
-            let order_by_str: Vec<String> = order_by
+        // the key columns are ordered before any other columns
+        let keyset_order = match &self.cursor {
+            Some((operator, _)) if operator == "<" => "DESC",
+            _ => "ASC",
+        };
+        let mut order_by_str: Vec<String> = self
+            .keyset
+            .iter()
+            .map(|column| format!("{} {}", column, keyset_order))
+            .collect();
+
+        order_by_str.extend(
+            self.order_by
                 .iter()
-                .map(|(columns, order)| format!("{} {}", columns.join(", "), order))
-                .collect();
-            if !order_by_str.is_empty() {
-                format!("ORDER BY {}", order_by_str.join(", "))
-            } else {
-                String::new()
-            }
+                .flatten()
+                .map(|(columns, order)| format!("{} {}", columns.join(", "), order)),
+        );
+
+        // case expressions in ORDER BY bind their values after the rest of the query
+        let mut order_by_params: Vec<Value> = Vec::new();
+        for (case, order) in &self.order_by_cases {
+            order_by_str.push(format!("{} {}", case.build_expression(), order));
+            order_by_params.extend(case.get_params());
+        }
+
+        let order_by_str = if !order_by_str.is_empty() {
+            format!("ORDER BY {}", order_by_str.join(", "))
         } else {
             String::new()
         };
 
-        let limit_str = self
-            .limit
-            .map_or(String::new(), |count| format!("LIMIT {}", count));
-        let offset_str = self
-            .offset
-            .map_or(String::new(), |offset| format!("OFFSET {}", offset));
+        let limit_str = Sqlite.limit_offset(self.limit, self.offset);
 
         // having should only be added if group_by is present
-        let having_str = if self.group_by.is_some() && self.having_condition.is_some() {
-            format!("HAVING {}", self.having_condition.unwrap().build())
-        } else {
-            String::new()
+        let having_str = match (&self.group_by, &self.having_condition) {
+            (Some(_), Some(condition)) => {
+                params.extend(condition.get_params());
+                format!("HAVING {}", condition.build())
+            }
+            _ => String::new(),
         };
 
-        // construct the query based on defined variables above
-        let query = format!(
-            "SELECT {}{} FROM {} {} {} {} {} {}",
-            distinct_str,
-            columns_str,
-            table_name_str,
+        // compound selects come before ORDER BY and LIMIT, which then apply to the whole result
+        let compound_str = self
+            .compound
+            .iter()
+            .map(|(operator, subquery)| {
+                params.extend(subquery.get_params());
+                format!("{} {}", operator, subquery.get_sql())
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        params.extend(order_by_params);
+
+        // construct the query based on defined variables above, skipping the empty clauses
+        let query = [
+            format!(
+                "{}SELECT {}{} FROM {}",
+                with_str, distinct_str, columns_str, table_name_str
+            ),
+            join_str,
             where_condition_str,
             group_by_str,
             having_str,
+            compound_str,
             order_by_str,
-            format!("{} {}", limit_str, offset_str),
-        );
+            limit_str,
+        ]
+        .into_iter()
+        .filter(|clause| !clause.is_empty())
+        .collect::<Vec<String>>()
+        .join(" ");
 
-        info!("{}", query);
-        println!("{}", query);
+        (query, params)
+    }
+
+    /// Get the generated SQL and the values bound to its placeholders, without executing the query.
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        self.build_query()
+    }
+
+    /// Get the values of the sensitive columns the conditions of the query compare with.
+    pub(crate) fn sensitive(&self) -> Sensitive {
+        let tables = self
+            .table
+            .into_iter()
+            .chain(self.joins.iter().map(|join| join.1));
+        let mut sensitive = Sensitive::new(tables);
+
+        let join_conditions = self.joins.iter().filter_map(|join| join.2.as_ref());
+        for condition in join_conditions
+            .chain(&self.where_condition)
+            .chain(&self.having_condition)
+        {
+            sensitive.condition(condition);
+        }
+
+        sensitive
+    }
+
+    /// Get the connection the query runs on.
+    ///
+    /// Fails for a query created with `subquery`, which has no connection.
+    pub(crate) fn get_conn(&self) -> Result<&'a Connection> {
+        self.conn.ok_or_else(missing_conn)
+    }
+
+    /// Runs `EXPLAIN QUERY PLAN` for the query and returns the plan, without executing the query.
+    pub fn explain(&self) -> Result<QueryPlan> {
+        let (query, params) = self.build_query();
+        let query = format!("EXPLAIN QUERY PLAN {}", query);
+
+        let conn = self.get_conn()?;
+
+        let steps = logger::run(&query, &self.sensitive().mask(&params), |sql| {
+            let mut stmt = trace::prepare(sql, || conn.prepare(sql))?;
+
+            let iter = stmt.query_map(params_from_iter(&params), |row| {
+                Ok(QueryPlanStep {
+                    id: row.get("id")?,
+                    parent: row.get("parent")?,
+                    detail: row.get("detail")?,
+                })
+            })?;
+
+            iter.collect::<rusqlite::Result<Vec<QueryPlanStep>>>()
+        })?;
+
+        Ok(QueryPlan { steps })
+    }
+
+    pub fn build<T: Table + Default>(self) -> Result<Vec<T>> {
+        let (query, params) = self.build_query();
+
+        query_rows::<T>(self.get_conn()?, &query, &params, &self.sensitive())
+    }
+
+    /// Executes the query and returns the first row, if any.
+    pub fn first<T: Table + Default>(self) -> Result<Option<T>> {
+        let (query, params) = self.build_query();
+        let query = format!("SELECT * FROM ({}) LIMIT 1", query);
+
+        let rows = query_rows::<T>(self.get_conn()?, &query, &params, &self.sensitive())?;
+
+        Ok(rows.into_iter().next())
+    }
+
+    /// Executes the query and returns its only row.
+    ///
+    /// Returns `Error::NotFound` if there are no rows and `Error::TooManyRows` if there is
+    /// more than one, which stops counting at the second row.
+    pub fn one<T: Table + Default>(self) -> Result<T> {
+        let (query, params) = self.build_query();
+        let query = format!("SELECT * FROM ({}) LIMIT 2", query);
+
+        let mut rows = query_rows::<T>(self.get_conn()?, &query, &params, &self.sensitive())?;
+
+        match rows.len() {
+            0 => Err(Error::NotFound),
+            1 => Ok(rows.remove(0)),
+            count => Err(Error::TooManyRows(count)),
+        }
+    }
+
+    /// Executes the query and returns `true` if it has at least one row.
+    pub fn exists(self) -> Result<bool> {
+        let (query, params) = self.build_query();
+        let query = format!("SELECT EXISTS ({})", query);
+
+        let exists = query_value(self.get_conn()?, &query, &params, &self.sensitive())?;
+
+        Ok(exists)
+    }
+
+    /// Executes the query and returns the number of rows it has.
+    pub fn count(self) -> Result<u64> {
+        let (query, params) = self.build_query();
+        let query = format!("SELECT COUNT(*) FROM ({})", query);
+
+        let count = query_value(self.get_conn()?, &query, &params, &self.sensitive())?;
+
+        Ok(count)
+    }
+
+    /// Executes the query for a single page of rows, along with a count of all matching rows.
+    ///
+    /// Pages start at 1, and the LIMIT and OFFSET of the query are replaced by the page bounds.
+    /// Returns `Error::Generation` for a page or page size of 0, or a page whose offset
+    /// overflows.
+    pub fn paginate<T: Table + Default>(mut self, page: u64, per_page: u64) -> Result<Page<T>> {
+        // the bounds are user input, so they are checked rather than overflowing, and
+        // kept within the 64-bit signed integers of sqlite
+        let bounds = page
+            .checked_sub(1)
+            .and_then(|page| page.checked_mul(per_page))
+            .and_then(|offset| i64::try_from(offset).ok())
+            .and_then(|offset| usize::try_from(offset).ok())
+            .zip(
+                i64::try_from(per_page)
+                    .ok()
+                    .filter(|&limit| limit > 0)
+                    .and_then(|limit| usize::try_from(limit).ok()),
+            );
+
+        let (offset, limit) = bounds.ok_or_else(|| {
+            Error::Generation(format!(
+                "page {} of {} rows is out of range, pages and their sizes start at 1",
+                page, per_page
+            ))
+        })?;
 
-        // prepare sql statement
-        let mut stmt = self.conn.prepare(query.as_str())?;
+        // count every matching row, ignoring the page bounds
+        self.limit = None;
+        self.offset = None;
+        let (count_query, count_params) = self.build_query();
+        let count_query = format!("SELECT COUNT(*) FROM ({})", count_query);
 
-        let iter = stmt.query_map((), |row| {
+        let total: u64 = query_value(
+            self.get_conn()?,
+            &count_query,
+            &count_params,
+            &self.sensitive(),
+        )?;
+
+        self.limit = Some(limit);
+        self.offset = Some(offset);
+        let (query, params) = self.build_query();
+
+        let items = query_rows::<T>(self.get_conn()?, &query, &params, &self.sensitive())?;
+
+        Ok(Page {
+            items,
+            total,
+            page,
+            per_page,
+            total_pages: total.div_ceil(per_page),
+        })
+    }
+
+    /// Executes the query lazily, fetching `chunk_size` rows at a time as the iterator advances.
+    ///
+    /// The chunks are read within a savepoint, so they see a consistent snapshot of the database.
+    pub fn build_iter<T: Table + Default>(self, chunk_size: usize) -> QueryIter<'a, T> {
+        let (query, params) = self.build_query();
+
+        QueryIter::new(self.conn, query, params, self.sensitive(), chunk_size)
+    }
+
+    /// Returns the rows of the query with their related rows of `C`, declared with
+    /// `#[njord(has_many = "...")]` on the selected table.
+    ///
+    /// The related rows are selected with a second query for all rows at once, instead of
+    /// one query per row.
+    pub fn with_related<C: Table + Default>(self) -> WithRelated<'a, C> {
+        WithRelated::new(self)
+    }
+
+    /// Executes the query and returns the first selected value of the first row,
+    /// e.g. the result of a single `COUNT(*)`.
+    pub fn build_aggregate<V: FromSql>(self) -> Result<V> {
+        let (query, params) = self.build_query();
+
+        let value = query_value(self.get_conn()?, &query, &params, &self.sensitive())?;
+
+        Ok(value)
+    }
+
+    /// Executes the query and returns every row with its selected values.
+    pub fn build_aggregates(self) -> Result<Vec<AggregateRow>> {
+        let (query, params) = self.build_query();
+
+        let conn = self.get_conn()?;
+
+        let rows = logger::run(&query, &self.sensitive().mask(&params), |sql| {
+            // prepare sql statement
+            let mut stmt = prepare_cached(conn, sql)?;
+            let column_count = stmt.column_count();
+
+            let iter = stmt.query_map(params_from_iter(&params), |row| {
+                let values = (0..column_count)
+                    .map(|index| row.get::<usize, Value>(index))
+                    .collect::<rusqlite::Result<Vec<Value>>>()?;
+
+                Ok(AggregateRow::new(values))
+            })?;
+
+            iter.collect::<rusqlite::Result<Vec<AggregateRow>>>()
+        })?;
+
+        Ok(rows)
+    }
+}
+
+/// The error of running a query created with `subquery`.
+pub(crate) fn missing_conn() -> Error {
+    misuse(
+        "a query without a connection can only be embedded in another query or run with build_async"
+            .to_string(),
+    )
+}
+
+/// Executes a query and maps each of its rows into an instance of the struct.
+pub(crate) fn query_rows<T: Table + Default>(
+    conn: &Connection,
+    query: &str,
+    params: &[Value],
+    sensitive: &Sensitive,
+) -> Result<Vec<T>> {
+    logger::run(query, &sensitive.mask(params), |sql| {
+        map_rows(conn, sql, params_from_iter(params))
+    })
+}
+
+/// Executes a query and returns the first value of its first row.
+fn query_value<V: FromSql>(
+    conn: &Connection,
+    query: &str,
+    params: &[Value],
+    sensitive: &Sensitive,
+) -> Result<V> {
+    let [value] = logger::run(query, &sensitive.mask(params), |sql| {
+        prepare_cached(conn, sql)?.query_row(params_from_iter(params), |row| Ok([row.get(0)?]))
+    })?;
+
+    Ok(value)
+}
+
+/// Prepares a statement from the statement cache of the connection, within a `prepare`
+/// span, and reports to the query logger whether it was found in the cache.
+pub(crate) fn prepare_cached<'c>(
+    conn: &'c Connection,
+    sql: &str,
+) -> rusqlite::Result<CachedStatement<'c>> {
+    let stmt = trace::prepare(sql, || conn.prepare_cached(sql))?;
+
+    // a statement compiled by this call has not run yet, unlike one found in the cache
+    logger::record_cache_hit(stmt.get_status(StatementStatus::Run) > 0);
+
+    Ok(stmt)
+}
+
+/// Executes a query like `query_rows`, with parameters that are not njord values, without
+/// reporting it to the query logger.
+pub(crate) fn map_rows<T: Table + Default, P: Params>(
+    conn: &Connection,
+    query: &str,
+    params: P,
+) -> Result<Vec<T>> {
+    // prepare sql statement
+    let mut stmt = prepare_cached(conn, query)?;
+
+    // result columns are mapped by name, so joined columns are set on the struct
+    // field with the same name (use `AS` to alias columns that collide)
+    let columns: Vec<String> = stmt
+        .column_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+
+    let rows = stmt
+        .query_map(params, |row| {
+            (0..columns.len())
+                .map(|index| row.get::<usize, Value>(index))
+                .collect::<rusqlite::Result<Vec<Value>>>()
+        })?
+        .collect::<rusqlite::Result<Vec<Vec<Value>>>>()?;
+
+    rows.into_iter()
+        .map(|values| {
             // dynamically create an instance of the struct based on the Table trait
             let mut instance = T::default();
-            let columns = instance.get_column_fields();
-            println!("{:?}", columns);
 
-            for (index, column) in columns.iter().enumerate() {
-                // use the index to get the value from the row and set it in the struct
-                let value = row.get::<usize, Value>(index + 1)?;
-                instance.set_column_value(column, value);
+            for (column, value) in columns.iter().zip(values) {
+                instance.set_column_value(column, value)?;
             }
 
             Ok(instance)
-        })?;
-
-        let result: Result<Vec<T>> = iter
-            .map(|row_result| row_result.and_then(|row| Ok(row)))
-            .collect::<Result<Vec<T>>>();
+        })
+        .collect()
+}
 
-        result.map_err(|err| err.into())
+impl<'a> From<QueryBuilder<'a>> for Subquery {
+    fn from(query: QueryBuilder<'a>) -> Self {
+        let (sql, params) = query.build_query();
+        Subquery::new(sql, params)
     }
 }
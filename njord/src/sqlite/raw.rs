@@ -0,0 +1,93 @@
+use crate::backend::{Executor, Row, SAVEPOINT_STATEMENTS};
+use crate::dialect::{Dialect, Sqlite};
+use crate::error::{Error, Result};
+use crate::logger;
+use crate::table::Table;
+use crate::value::Value;
+
+use rusqlite::{params_from_iter, Connection, ErrorCode, Params, StatementStatus};
+
+use super::query::map_rows;
+
+/// Executes a raw SQL query and maps each of its rows into an instance of the struct.
+///
+/// The parameters are bound to the `?` placeholders of the query, and the result columns
+/// are mapped to the struct fields with the same name.
+pub fn query_as<T: Table + Default, P: Params>(
+    conn: &Connection,
+    query: &str,
+    params: P,
+) -> Result<Vec<T>> {
+    logger::run(query, &[], |sql| map_rows::<T, P>(conn, sql, params))
+}
+
+/// Runs the builders of the `backend` module on a sqlite connection, e.g. in code written
+/// against any `Executor`.
+///
+/// Statements are compiled into the statement cache of the connection, so a statement
+/// is only compiled once for every time it is run. Its transactions are savepoints, which
+/// also nest within a transaction of the connection.
+impl Executor for Connection {
+    type Statement = String;
+
+    fn dialect(&self) -> &'static dyn Dialect {
+        &Sqlite
+    }
+
+    fn prepare(&mut self, statement: &str) -> Result<String> {
+        let stmt = self.prepare_cached(statement)?;
+
+        // as in `query::prepare_cached`, only a statement found in the cache has run before
+        logger::record_cache_hit(stmt.get_status(StatementStatus::Run) > 0);
+
+        Ok(statement.to_string())
+    }
+
+    fn execute(&mut self, statement: &String, params: &[Value]) -> Result<u64> {
+        let count = self
+            .prepare_cached(statement)?
+            .execute(params_from_iter(params))?;
+
+        Ok(count as u64)
+    }
+
+    fn query(&mut self, statement: &String, params: &[Value]) -> Result<Vec<Row>> {
+        let mut stmt = self.prepare_cached(statement)?;
+
+        let columns: Vec<String> = stmt
+            .column_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        let rows = stmt.query_map(params_from_iter(params), |row| {
+            columns
+                .iter()
+                .enumerate()
+                .map(|(index, column)| Ok((column.clone(), row.get::<usize, Value>(index)?)))
+                .collect()
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    fn execute_batch(&mut self, statement: &str) -> Result<()> {
+        Ok(Connection::execute_batch(self, statement)?)
+    }
+
+    fn transaction_statements(&self) -> [&'static str; 3] {
+        SAVEPOINT_STATEMENTS
+    }
+}
+
+/// The violations of a constraint, whose code is SQLITE_CONSTRAINT or one of its extended
+/// codes, are returned as `Error::Constraint`, and the statements interrupted with an
+/// `InterruptHandle` as `Error::Cancelled`.
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        match error.sqlite_error_code() {
+            Some(ErrorCode::OperationInterrupted) => Error::Cancelled,
+            code => Error::from_driver(error, code == Some(ErrorCode::ConstraintViolation)),
+        }
+    }
+}
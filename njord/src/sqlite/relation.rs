@@ -0,0 +1,437 @@
+use crate::error::Result;
+use crate::logger;
+use crate::sensitive::Sensitive;
+use crate::table::{RelationKind, Table};
+use crate::value::{FromSqlResult, FromValue, ToValue, Value, ValueRef};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use rusqlite::{params_from_iter, Connection};
+
+use super::find;
+use super::insert::max_variable_number;
+use super::key::misuse;
+use super::query::{prepare_cached, query_rows, QueryBuilder};
+
+/// Finds the row of `T` referenced by the value of a foreign key, as generated for
+/// `#[njord(belongs_to = "...")]` relations.
+///
+/// Returns `None` if the foreign key is NULL or there is no such row.
+pub fn belongs_to<T: Table + Default>(
+    conn: &Connection,
+    foreign_key_value: Value,
+) -> Result<Option<T>> {
+    if foreign_key_value == Value::Null {
+        return Ok(None);
+    }
+
+    find::<T, _>(conn, foreign_key_value)
+}
+
+/// Finds the rows of `T` whose foreign key column references the given primary key value,
+/// as generated for `#[njord(has_many = "...")]` relations.
+pub fn has_many<T: Table + Default>(
+    conn: &Connection,
+    foreign_key: &str,
+    primary_key_value: Value,
+) -> Result<Vec<T>> {
+    let table = T::default();
+
+    // soft deleted rows are not found
+    let soft_delete_str = table
+        .get_soft_delete_column()
+        .map_or(String::new(), |column| format!(" AND {} IS NULL", column));
+
+    let query = format!(
+        "SELECT * FROM {} WHERE {} = ?{}",
+        table.get_name(),
+        foreign_key,
+        soft_delete_str
+    );
+
+    query_rows::<T>(conn, &query, &[primary_key_value], &Sensitive::default())
+}
+
+/// A query whose rows are returned with their related rows of `C`, created by
+/// `QueryBuilder::with_related`.
+pub struct WithRelated<'a, C: Table + Default> {
+    query: QueryBuilder<'a>,
+    related: PhantomData<C>,
+}
+
+impl<'a, C: Table + Default> WithRelated<'a, C> {
+    pub(crate) fn new(query: QueryBuilder<'a>) -> Self {
+        WithRelated {
+            query,
+            related: PhantomData,
+        }
+    }
+
+    /// Executes the query and returns each row with its related rows.
+    pub fn build<T: Table + Default>(self) -> Result<Vec<(T, Vec<C>)>> {
+        let conn = self.query.get_conn()?;
+        let rows = self.query.build::<T>()?;
+
+        load_has_many(conn, rows)
+    }
+}
+
+/// Loads the related rows of `C` of each of the given rows of `T`, declared with
+/// `#[njord(has_many = "...")]` on `T`.
+///
+/// The related rows of all given rows are selected at once with an `IN` list, instead of
+/// one query per row, and returned next to the row they belong to.
+pub fn load_has_many<T: Table + Default, C: Table + Default>(
+    conn: &Connection,
+    rows: Vec<T>,
+) -> Result<Vec<(T, Vec<C>)>> {
+    let table = T::default();
+    let related_table = C::default();
+
+    let foreign_key = table
+        .get_relations()
+        .into_iter()
+        .find(|relation| {
+            relation.kind == RelationKind::HasMany && relation.table == related_table.get_name()
+        })
+        .map(|relation| relation.foreign_key)
+        .ok_or_else(|| {
+            misuse(format!(
+                "table {} has no has_many relation to table {}",
+                table.get_name(),
+                related_table.get_name()
+            ))
+        })?;
+
+    let primary_key_count = table.get_primary_key_columns().len();
+    if primary_key_count != 1 {
+        return Err(misuse(format!(
+            "table {} has {} primary key columns, but has_many requires one",
+            table.get_name(),
+            primary_key_count
+        )));
+    }
+
+    let primary_keys: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            Ok(row
+                .get_primary_key()?
+                .into_iter()
+                .next()
+                .map_or(Value::Null, |(_, value)| value))
+        })
+        .collect::<Result<_>>()?;
+
+    // soft deleted rows are not found
+    let soft_delete_str = related_table
+        .get_soft_delete_column()
+        .map_or(String::new(), |column| format!(" AND {} IS NULL", column));
+
+    // group the related rows by their foreign key, selected in chunks within the parameter limit
+    let mut related: HashMap<RelationKey, Vec<C>> = HashMap::new();
+    let keys: Vec<Value> = primary_keys
+        .iter()
+        .filter(|value| RelationKey::new(value).is_some())
+        .cloned()
+        .collect();
+
+    for chunk in keys.chunks(max_variable_number(conn)?) {
+        let query = format!(
+            "SELECT * FROM {} WHERE {} IN ({}){}",
+            related_table.get_name(),
+            foreign_key,
+            vec!["?"; chunk.len()].join(", "),
+            soft_delete_str
+        );
+
+        let related_rows = query_rows::<C>(conn, &query, chunk, &Sensitive::default())?;
+
+        for related_row in related_rows {
+            let key = related_row
+                .get_column_fields()
+                .iter()
+                .zip(related_row.get_column_values()?)
+                .find(|(column, _)| **column == foreign_key)
+                .and_then(|(_, value)| RelationKey::new(&value));
+
+            if let Some(key) = key {
+                related.entry(key).or_default().push(related_row);
+            }
+        }
+    }
+
+    let rows = rows
+        .into_iter()
+        .zip(primary_keys)
+        .map(|(row, primary_key)| {
+            let related_rows = RelationKey::new(&primary_key)
+                .and_then(|key| related.remove(&key))
+                .unwrap_or_default();
+            (row, related_rows)
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+/// A primary key value the related rows are grouped by.
+///
+/// Only integer, text and blob values are keys, since NULL never matches and
+/// reals are not used as keys.
+#[derive(PartialEq, Eq, Hash)]
+enum RelationKey {
+    Integer(i64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl RelationKey {
+    fn new(value: &Value) -> Option<Self> {
+        match value {
+            Value::Integer(value) => Some(RelationKey::Integer(*value)),
+            Value::Text(value) => Some(RelationKey::Text(value.clone())),
+            Value::Blob(value) => Some(RelationKey::Blob(value.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the rows of `T` related to the given primary key value through a join table,
+/// as generated for `#[njord(many_to_many = "...")]` relations.
+///
+/// The `foreign_key` column of the join table references the given primary key and
+/// its `related_key` column the primary key of `T`.
+pub fn many_to_many<T: Table + Default>(
+    conn: &Connection,
+    through: &str,
+    foreign_key: &str,
+    related_key: &str,
+    primary_key_value: Value,
+) -> Result<Vec<T>> {
+    let table = T::default();
+    let related_primary_key = single_primary_key_column(&table)?;
+
+    // soft deleted rows are not found
+    let soft_delete_str = table
+        .get_soft_delete_column()
+        .map_or(String::new(), |column| {
+            format!(" AND {}.{} IS NULL", table.get_name(), column)
+        });
+
+    let query = format!(
+        "SELECT {table}.* FROM {table} INNER JOIN {through} ON {through}.{related_key} = {table}.{primary_key} WHERE {through}.{foreign_key} = ?{soft_delete}",
+        table = table.get_name(),
+        through = through,
+        related_key = related_key,
+        primary_key = related_primary_key,
+        foreign_key = foreign_key,
+        soft_delete = soft_delete_str
+    );
+
+    query_rows::<T>(conn, &query, &[primary_key_value], &Sensitive::default())
+}
+
+/// Relates the given row to the given primary key value by inserting both into a join table,
+/// as generated for `#[njord(many_to_many = "...")]` relations.
+///
+/// Rows that are already related are ignored if the join table has a primary key or a
+/// UNIQUE constraint on both columns. Returns the number of inserted rows.
+pub fn attach(
+    conn: &mut Connection,
+    through: &str,
+    foreign_key: &str,
+    related_key: &str,
+    primary_key_value: Value,
+    related: &dyn Table,
+) -> Result<usize> {
+    let related_value = single_primary_key_value(related)?;
+
+    let query = format!(
+        "INSERT OR IGNORE INTO {} ({}, {}) VALUES (?, ?)",
+        through, foreign_key, related_key
+    );
+
+    let params = [primary_key_value, related_value];
+
+    logger::run(&query, &params, |sql| {
+        prepare_cached(conn, sql)?.execute(params_from_iter(&params))
+    })
+}
+
+/// Removes the relation of the given row to the given primary key value from a join table,
+/// as generated for `#[njord(many_to_many = "...")]` relations.
+///
+/// Returns the number of deleted rows, which is 0 if they were not related.
+pub fn detach(
+    conn: &mut Connection,
+    through: &str,
+    foreign_key: &str,
+    related_key: &str,
+    primary_key_value: Value,
+    related: &dyn Table,
+) -> Result<usize> {
+    let related_value = single_primary_key_value(related)?;
+
+    let query = format!(
+        "DELETE FROM {} WHERE {} = ? AND {} = ?",
+        through, foreign_key, related_key
+    );
+
+    let params = [primary_key_value, related_value];
+
+    logger::run(&query, &params, |sql| {
+        prepare_cached(conn, sql)?.execute(params_from_iter(&params))
+    })
+}
+
+/// Get the primary key column of a table referenced through a join table, which needs exactly one.
+fn single_primary_key_column(table: &dyn Table) -> Result<String> {
+    let mut primary_key_columns = table.get_primary_key_columns();
+
+    match primary_key_columns.len() {
+        1 => Ok(primary_key_columns.remove(0)),
+        count => Err(misuse(format!(
+            "table {} has {} primary key columns, but many_to_many requires one",
+            table.get_name(),
+            count
+        ))),
+    }
+}
+
+/// Get the primary key value of a row referenced through a join table.
+fn single_primary_key_value(table: &dyn Table) -> Result<Value> {
+    single_primary_key_column(table)?;
+
+    Ok(table
+        .get_primary_key()?
+        .into_iter()
+        .next()
+        .map_or(Value::Null, |(_, value)| value))
+}
+
+/// A foreign key field loading the row of `T` it references on first access,
+/// e.g. `author: Lazy<Author>` stored in an `author_id` column with `#[njord(column = "author_id")]`.
+///
+/// Only the foreign key is selected with the row, and the referenced row is found by
+/// `get` once and cached afterwards.
+#[derive(Debug, Clone)]
+pub struct Lazy<T> {
+    key: Value,
+    loaded: OnceCell<Option<T>>,
+}
+
+impl<T> Lazy<T> {
+    /// Creates a handle referencing the row with the given primary key.
+    pub fn new<K: Into<Value>>(key: K) -> Self {
+        Lazy {
+            key: key.into(),
+            loaded: OnceCell::new(),
+        }
+    }
+
+    /// Get the foreign key, i.e. the primary key of the referenced row.
+    pub fn key(&self) -> &Value {
+        &self.key
+    }
+
+    /// Whether the referenced row was already loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.get().is_some()
+    }
+}
+
+impl<T: Table + Default> Lazy<T> {
+    /// Get the referenced row, finding it on the first call.
+    ///
+    /// Returns `None` if the foreign key is NULL or there is no such row.
+    pub fn get(&self, conn: &Connection) -> Result<Option<&T>> {
+        if self.loaded.get().is_none() {
+            let row = belongs_to::<T>(conn, self.key.clone())?;
+            let _ = self.loaded.set(row);
+        }
+
+        Ok(self.loaded.get().and_then(Option::as_ref))
+    }
+}
+
+impl<T> Default for Lazy<T> {
+    fn default() -> Self {
+        Lazy::new(Value::Null)
+    }
+}
+
+impl<T> ToValue for Lazy<T> {
+    fn to_value(&self) -> Option<Value> {
+        Some(self.key.clone())
+    }
+}
+
+impl<T> FromValue for Lazy<T> {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Ok(Lazy::new(Value::from(value)))
+    }
+}
+
+/// A field loading the rows of `T` referencing its row on first access, marked with
+/// `#[njord(foreign_key = "author_id")]` naming the foreign key column of `T`,
+/// e.g. `posts: HasMany<Post>`.
+///
+/// The field is not a column. It is keyed by the primary key of its row when the row is
+/// selected or inserted, and the related rows are found by `get` once and cached afterwards.
+#[derive(Debug, Clone)]
+pub struct HasMany<T> {
+    foreign_key: String,
+    key: Value,
+    loaded: OnceCell<Vec<T>>,
+}
+
+impl<T> HasMany<T> {
+    /// Creates a handle to the rows whose `foreign_key` column is the given primary key.
+    pub fn new<K: Into<Value>>(foreign_key: &str, key: K) -> Self {
+        HasMany {
+            foreign_key: foreign_key.to_string(),
+            key: key.into(),
+            loaded: OnceCell::new(),
+        }
+    }
+
+    /// Keys the handle by the primary key of its row, discarding the rows loaded for a previous key.
+    pub fn set_key<K: Into<Value>>(&mut self, foreign_key: &str, key: K) {
+        *self = HasMany::new(foreign_key, key);
+    }
+
+    /// Get the primary key the related rows reference.
+    pub fn key(&self) -> &Value {
+        &self.key
+    }
+
+    /// Whether the related rows were already loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.get().is_some()
+    }
+}
+
+impl<T: Table + Default> HasMany<T> {
+    /// Get the related rows, finding them on the first call.
+    ///
+    /// A handle without a key, e.g. of a row that is not inserted yet, has no related rows.
+    pub fn get(&self, conn: &Connection) -> Result<&[T]> {
+        if self.loaded.get().is_none() {
+            let rows = match self.key {
+                Value::Null => Vec::new(),
+                _ => has_many::<T>(conn, &self.foreign_key, self.key.clone())?,
+            };
+            let _ = self.loaded.set(rows);
+        }
+
+        Ok(self.loaded.get().map_or(&[], Vec::as_slice))
+    }
+}
+
+impl<T> Default for HasMany<T> {
+    fn default() -> Self {
+        HasMany::new("", Value::Null)
+    }
+}
@@ -0,0 +1,105 @@
+use crate::error::{Error, Result};
+use std::thread;
+use std::time::Duration;
+
+use rusqlite::{Connection, ErrorCode};
+
+use log::info;
+
+use super::Session;
+
+/// How to retry work failing because another connection holds a lock on the database,
+/// e.g. `database is locked` errors of writers in several processes.
+///
+/// Failed attempts are retried after a backoff, doubled after each attempt up to a
+/// maximum. Other errors are returned immediately.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+
+    /// Sets the number of attempts before the error is returned, 5 by default.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the backoff before the first retry, 10 milliseconds by default.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the maximum backoff between retries, 1 second by default.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Runs `f`, retrying it while it fails with SQLITE_BUSY or SQLITE_LOCKED,
+    /// e.g. `policy.run(|| insert(&mut conn, &mut row).build())`.
+    pub fn run<R, F: FnMut() -> Result<R>>(&self, mut f: F) -> Result<R> {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+
+        loop {
+            match f() {
+                Err(error) if is_busy(&error) && attempt < self.max_attempts => {
+                    info!(
+                        "Database is locked, retrying in {:?} (attempt {} of {}).",
+                        backoff, attempt, self.max_attempts
+                    );
+
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Runs `f` within a `Session` as in `run`, committing its writes if `f` succeeds.
+    ///
+    /// A transaction failing with SQLITE_BUSY is rolled back and run again from the start,
+    /// so `f` may be called several times and should only write through the session.
+    pub fn transaction<R, F>(&self, conn: &mut Connection, mut f: F) -> Result<R>
+    where
+        F: FnMut(&mut Session<'_>) -> Result<R>,
+    {
+        self.run(|| {
+            let mut session = Session::new(conn)?;
+            let result = f(&mut session)?;
+            session.commit()?;
+
+            Ok(result)
+        })
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the error was caused by a lock held by another connection, so retrying may succeed.
+pub fn is_busy(error: &Error) -> bool {
+    matches!(
+        error
+            .driver_error::<rusqlite::Error>()
+            .and_then(rusqlite::Error::sqlite_error_code),
+        Some(ErrorCode::DatabaseBusy) | Some(ErrorCode::DatabaseLocked)
+    )
+}
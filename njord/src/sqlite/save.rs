@@ -0,0 +1,177 @@
+use crate::dialect::{Dialect, Sqlite};
+use crate::error::{Error, Result};
+use crate::insert::get_timestamp_columns;
+use crate::logger;
+use crate::sensitive::Sensitive;
+use crate::statement::insert_columns_statement;
+use crate::table::Table;
+use crate::timestamp::current_timestamp;
+use crate::value::Value;
+
+use rusqlite::{params_from_iter, Connection, OptionalExtension};
+
+use log::info;
+
+use super::insert;
+use super::key::{check_writable, missing_primary_key};
+use super::query::prepare_cached;
+
+/// Saves the row, identified by its primary key.
+///
+/// A row whose auto-increment primary key is unset, i.e. still holds its default value,
+/// is inserted and gets the assigned rowid written back. Any other row is inserted or,
+/// if a row with the same primary key exists, updated with the current values, apart from
+/// its `#[njord(default)]`, soft delete and `created_at` columns, which are kept.
+///
+/// A row with a version column is only updated if the stored version still matches,
+/// and gets the bumped version written back. Otherwise `Error::VersionConflict` is returned.
+///
+/// The timestamp columns are written back as they are stored, i.e. the time the row was
+/// saved at, except for the `created_at` of an updated row, which is kept.
+///
+/// Returns the number of inserted or updated rows.
+pub fn save<T: Table + Default>(conn: &mut Connection, table_row: &mut T) -> Result<usize> {
+    check_writable(conn, table_row)?;
+
+    let primary_key_columns = table_row.get_primary_key_columns();
+
+    if primary_key_columns.is_empty() {
+        return Err(missing_primary_key(table_row.get_name()));
+    }
+
+    // a generated primary key makes sure a new row is inserted
+    table_row.generate_values();
+
+    if table_row.get_auto_increment_column().is_some() && is_primary_key_unset(table_row)? {
+        return insert(conn, table_row).build();
+    }
+
+    let column_fields = table_row.get_column_fields();
+    let version_column = table_row.get_version_column();
+
+    let created_at_column = table_row.get_created_at_column();
+    let timestamp_columns = get_timestamp_columns(table_row);
+
+    // an updated row keeps the columns it was given by the database or by a delete, i.e.
+    // its `#[njord(default)]` columns, its soft delete column and its created_at
+    let default_columns = table_row.get_default_columns();
+
+    let mut set_str: Vec<String> = column_fields
+        .iter()
+        .filter(|column| !primary_key_columns.contains(column))
+        .filter(|column| Some(*column) != version_column.as_ref())
+        .filter(|column| Some(*column) != created_at_column.as_ref())
+        .filter(|column| !default_columns.contains(column))
+        .map(|column| Sqlite.quote_identifier(column))
+        .map(|column| format!("{} = excluded.{}", column, column))
+        .collect();
+
+    // a versioned row is only updated if nobody else updated it since it was read
+    let conflict_str = match &version_column {
+        Some(column) => {
+            let column = Sqlite.quote_identifier(column);
+            set_str.push(format!("{} = {} + 1", column, column));
+            format!(
+                "DO UPDATE SET {} WHERE {}.{} = excluded.{}",
+                set_str.join(", "),
+                Sqlite.quote_identifier(table_row.get_name()),
+                column,
+                column
+            )
+        }
+        // a table made of primary key columns only has nothing to update
+        None if set_str.is_empty() => "DO NOTHING".to_string(),
+        None => format!("DO UPDATE SET {}", set_str.join(", ")),
+    };
+
+    let primary_key_str: Vec<String> = primary_key_columns
+        .iter()
+        .map(|column| Sqlite.quote_identifier(column))
+        .collect();
+
+    // the version and the timestamps are returned as stored, to be written back
+    let returned_columns: Vec<String> = version_column
+        .iter()
+        .chain(&timestamp_columns)
+        .cloned()
+        .collect();
+
+    let returning_str = match returned_columns.is_empty() {
+        true => String::new(),
+        false => {
+            let columns: Vec<String> = returned_columns
+                .iter()
+                .map(|column| Sqlite.quote_identifier(column))
+                .collect();
+            format!(" RETURNING {}", columns.join(", "))
+        }
+    };
+
+    // every column is inserted, including the primary key the row already has
+    let query = format!(
+        "{} ON CONFLICT({}) {}{}",
+        insert_columns_statement(&Sqlite, table_row, &column_fields, 1),
+        primary_key_str.join(", "),
+        conflict_str,
+        returning_str
+    );
+
+    // the timestamp columns are bound to the time the row is saved at
+    let values: Vec<Value> = table_row
+        .get_column_fields()
+        .into_iter()
+        .zip(table_row.get_column_values()?)
+        .map(
+            |(column, value)| match timestamp_columns.contains(&column) {
+                true => current_timestamp(table_row, &column),
+                false => value,
+            },
+        )
+        .collect();
+
+    let sensitive = Sensitive::rows([&*table_row]);
+    let logged_values = sensitive.mask(&values);
+
+    let count = match returned_columns.is_empty() {
+        true => logger::run(&query, &logged_values, |sql| {
+            prepare_cached(conn, sql)?.execute(params_from_iter(&values))
+        })?,
+        false => {
+            let returned: Option<Vec<Value>> = logger::run(&query, &logged_values, |sql| {
+                prepare_cached(conn, sql)?
+                    .query_row(params_from_iter(&values), |row| {
+                        (0..returned_columns.len())
+                            .map(|index| row.get(index))
+                            .collect()
+                    })
+                    .optional()
+            })?;
+
+            match returned {
+                Some(returned) => {
+                    for (column, value) in returned_columns.iter().zip(returned) {
+                        table_row.set_column_value(column, value)?;
+                    }
+
+                    1
+                }
+                None if version_column.is_some() => {
+                    return Err(Error::VersionConflict {
+                        table: table_row.get_name().to_string(),
+                    })
+                }
+                // a row skipped by DO NOTHING is not returned
+                None => 0,
+            }
+        }
+    };
+
+    info!("Saved row, done.");
+
+    Ok(count)
+}
+
+/// Returns `true` if every primary key column still holds the default value of its field.
+fn is_primary_key_unset<T: Table + Default>(table_row: &T) -> Result<bool> {
+    Ok(table_row.get_primary_key()? == T::default().get_primary_key()?)
+}
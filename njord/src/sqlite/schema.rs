@@ -0,0 +1,290 @@
+use crate::error::Result;
+use crate::table::{ColumnSchema, IndexSchema, Table};
+
+use rusqlite::Connection;
+
+use super::ddl::{column_definition, create_index_statement, create_table_statement};
+
+/// The differences between the schema of a struct deriving `Table` and its table in the database,
+/// returned by `diff`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaDiff {
+    /// The name of the table.
+    pub table: String,
+    /// Whether the table does not exist.
+    pub missing_table: bool,
+    /// The columns of the struct the table does not have.
+    pub missing_columns: Vec<ColumnSchema>,
+    /// The columns of the table the struct does not have.
+    pub extra_columns: Vec<String>,
+    /// The columns whose type, nullability or primary key differ, as declared by the struct
+    /// and as found in the table.
+    pub changed_columns: Vec<(ColumnSchema, ColumnSchema)>,
+    /// The indexes of the struct the table does not have.
+    pub missing_indexes: Vec<IndexSchema>,
+    /// The CREATE TABLE, ALTER TABLE and CREATE INDEX statements adding what is missing.
+    ///
+    /// Extra and changed columns are not covered, since SQLite can only change them by
+    /// rebuilding the table, e.g. with `rebuild_table`.
+    pub statements: Vec<String>,
+}
+
+impl SchemaDiff {
+    /// Whether the table matches the struct.
+    pub fn is_empty(&self) -> bool {
+        !self.missing_table
+            && self.missing_columns.is_empty()
+            && self.extra_columns.is_empty()
+            && self.changed_columns.is_empty()
+            && self.missing_indexes.is_empty()
+    }
+}
+
+/// Compares the schema of `T` with its table in the database, e.g. to catch drift at startup.
+///
+/// Only the column types, nullability and primary keys are compared, since SQLite does not
+/// report all constraints of a column.
+pub fn diff<T: Table + Default>(conn: &Connection) -> Result<SchemaDiff> {
+    let table = T::default();
+    let table_name = table.get_name();
+    let expected_columns = table.get_schema();
+    let expected_indexes = table.get_indexes();
+
+    if !table_exists(conn, table_name)? {
+        let mut statements = vec![create_table_statement(&table, table_name, false)];
+        statements.extend(
+            expected_indexes
+                .iter()
+                .map(|index| create_index_statement(table_name, index)),
+        );
+
+        return Ok(SchemaDiff {
+            table: table_name.to_string(),
+            missing_table: true,
+            missing_columns: expected_columns,
+            extra_columns: Vec::new(),
+            changed_columns: Vec::new(),
+            missing_indexes: expected_indexes,
+            statements,
+        });
+    }
+
+    let actual_columns = columns(conn, table_name)?;
+    let actual_indexes = indexes(conn, table_name)?;
+
+    let mut missing_columns = Vec::new();
+    let mut changed_columns = Vec::new();
+    for expected in expected_columns.iter() {
+        match actual_columns
+            .iter()
+            .find(|actual| actual.name == expected.name)
+        {
+            Some(actual) => {
+                let changed = !actual
+                    .column_type
+                    .eq_ignore_ascii_case(&expected.column_type)
+                    || actual.nullable != expected.nullable
+                    || actual.primary_key != expected.primary_key;
+                if changed {
+                    changed_columns.push((expected.clone(), actual.clone()));
+                }
+            }
+            None => missing_columns.push(expected.clone()),
+        }
+    }
+
+    let extra_columns = actual_columns
+        .iter()
+        .filter(|actual| {
+            !expected_columns
+                .iter()
+                .any(|expected| expected.name == actual.name)
+        })
+        .map(|actual| actual.name.clone())
+        .collect();
+
+    // indexes are matched by name, like `create_indexes` skips existing ones
+    let missing_indexes: Vec<IndexSchema> = expected_indexes
+        .into_iter()
+        .filter(|expected| {
+            !actual_indexes
+                .iter()
+                .any(|actual| actual.name == expected.name)
+        })
+        .collect();
+
+    let mut statements: Vec<String> = missing_columns
+        .iter()
+        .map(|column| {
+            format!(
+                "ALTER TABLE {} ADD COLUMN {}",
+                table_name,
+                column_definition(&table, column, true)
+            )
+        })
+        .collect();
+    statements.extend(
+        missing_indexes
+            .iter()
+            .map(|index| create_index_statement(table_name, index)),
+    );
+
+    Ok(SchemaDiff {
+        table: table_name.to_string(),
+        missing_table: false,
+        missing_columns,
+        extra_columns,
+        changed_columns,
+        missing_indexes,
+        statements,
+    })
+}
+
+/// Get the names of the tables of the database, in alphabetical order.
+///
+/// The internal tables of SQLite, e.g. `sqlite_sequence`, are left out.
+pub fn tables(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )?;
+    let tables = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    Ok(tables)
+}
+
+/// Whether the database has a table with the given name.
+pub fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    let exists = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?")?
+        .exists([table])?;
+
+    Ok(exists)
+}
+
+/// Get the schema of the columns of a table, in the order they are declared.
+///
+/// Primary key columns are never nullable, and `check` is never set since SQLite
+/// does not report CHECK constraints. Returns no columns if there is no such table.
+pub fn columns(conn: &Connection, table: &str) -> Result<Vec<ColumnSchema>> {
+    let sql: Option<String> = conn
+        .prepare("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?")?
+        .query_map([table], |row| row.get(0))?
+        .next()
+        .transpose()?;
+    let has_autoincrement = sql.is_some_and(|sql| sql.to_uppercase().contains("AUTOINCREMENT"));
+
+    // UNIQUE constraints of a single column
+    let unique_columns: Vec<String> = indexes_of(conn, table, "u")?
+        .into_iter()
+        .filter(|index| index.unique && index.columns.len() == 1)
+        .flat_map(|index| index.columns)
+        .collect();
+
+    let mut stmt = conn.prepare(
+        "SELECT \"from\", \"table\", \"to\" FROM pragma_foreign_key_list(?) ORDER BY id, seq",
+    )?;
+    let foreign_keys = stmt
+        .query_map([table], |row| {
+            let to: Option<String> = row.get(2)?;
+            let references: String = row.get(1)?;
+            let references = match to {
+                Some(to) => format!("{}({})", references, to),
+                None => references,
+            };
+            Ok((row.get::<usize, String>(0)?, references))
+        })?
+        .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?) ORDER BY cid",
+    )?;
+    let rows = stmt
+        .query_map([table], |row| {
+            Ok((
+                row.get::<usize, String>(0)?,
+                row.get::<usize, String>(1)?,
+                row.get::<usize, bool>(2)?,
+                row.get::<usize, Option<String>>(3)?,
+                row.get::<usize, i64>(4)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let primary_key_count = rows.iter().filter(|row| row.4 > 0).count();
+
+    let columns = rows
+        .into_iter()
+        .map(
+            |(name, column_type, not_null, default_value, primary_key)| {
+                let primary_key = primary_key > 0;
+                // only a single INTEGER PRIMARY KEY can be declared with AUTOINCREMENT
+                let auto_increment = primary_key
+                    && primary_key_count == 1
+                    && has_autoincrement
+                    && column_type.eq_ignore_ascii_case("INTEGER");
+
+                ColumnSchema {
+                    unique: unique_columns.contains(&name),
+                    references: foreign_keys
+                        .iter()
+                        .find(|(from, _)| *from == name)
+                        .map(|(_, references)| references.clone()),
+                    name,
+                    column_type,
+                    nullable: !not_null && !primary_key,
+                    primary_key,
+                    auto_increment,
+                    default_value,
+                    check: None,
+                }
+            },
+        )
+        .collect();
+
+    Ok(columns)
+}
+
+/// Get the indexes of a table, e.g. those created by `create_indexes`.
+///
+/// The indexes SQLite creates for PRIMARY KEY and UNIQUE constraints are left out.
+pub fn indexes(conn: &Connection, table: &str) -> Result<Vec<IndexSchema>> {
+    indexes_of(conn, table, "c")
+}
+
+/// Get the indexes of a table with the given origin, `c` for indexes created by CREATE INDEX,
+/// `u` for UNIQUE constraints and `pk` for PRIMARY KEY constraints.
+fn indexes_of(conn: &Connection, table: &str, origin: &str) -> Result<Vec<IndexSchema>> {
+    let mut stmt =
+        conn.prepare("SELECT name, \"unique\", origin FROM pragma_index_list(?) ORDER BY name")?;
+    let index_list = stmt
+        .query_map([table], |row| {
+            Ok((
+                row.get::<usize, String>(0)?,
+                row.get::<usize, bool>(1)?,
+                row.get::<usize, String>(2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut indexes = Vec::new();
+    for (name, unique, index_origin) in index_list {
+        if index_origin != origin {
+            continue;
+        }
+
+        let columns = conn
+            .prepare("SELECT name FROM pragma_index_info(?) ORDER BY seqno")?
+            .query_map([&name], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        indexes.push(IndexSchema {
+            name,
+            columns,
+            unique,
+        });
+    }
+
+    Ok(indexes)
+}
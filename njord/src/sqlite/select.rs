@@ -1,7 +1,49 @@
-use crate::sqlite::query::QueryBuilder;
+use crate::dialect::{Dialect, Sqlite};
+use crate::error::Result;
+use crate::sensitive::Sensitive;
+use crate::sqlite::query::{query_rows, QueryBuilder};
+use crate::table::Table;
 
 use rusqlite::Connection;
 
-pub fn select<'a>(conn: Connection, columns: Vec<String>) -> QueryBuilder<'a> {
+use super::key::{primary_key_condition, PrimaryKey};
+
+pub fn select<'a>(conn: &'a Connection, columns: Vec<String>) -> QueryBuilder<'a> {
     QueryBuilder::new(conn, columns)
 }
+
+/// Creates a query without a connection, to be embedded in a condition of another query,
+/// e.g. `Condition::in_subquery("id", subquery(columns).from(&table))`, or to be run on an
+/// `AsyncConnection` with `build_async`.
+pub fn subquery<'a>(columns: Vec<String>) -> QueryBuilder<'a> {
+    QueryBuilder::with_conn(None, columns)
+}
+
+/// Finds the row of `T` with the given primary key, given as a tuple for composite keys.
+///
+/// Returns `None` if there is no such row.
+pub fn find<T: Table + Default, K: PrimaryKey>(conn: &Connection, id: K) -> Result<Option<T>> {
+    let table = T::default();
+    let values = id.into_values();
+    let primary_key_str = primary_key_condition(&table, &values)?.build();
+
+    // soft deleted rows are not found
+    let soft_delete_str = table
+        .get_soft_delete_column()
+        .map_or(String::new(), |column| {
+            format!(" AND {} IS NULL", Sqlite.quote_identifier(&column))
+        });
+
+    let query = format!(
+        "SELECT * FROM {} WHERE {}{}",
+        Sqlite.quote_identifier(table.get_name()),
+        primary_key_str,
+        soft_delete_str
+    );
+
+    let sensitive = Sensitive::primary_key(&table, &values);
+
+    let mut rows = query_rows::<T>(conn, &query, &values, &sensitive)?;
+
+    Ok(rows.pop())
+}
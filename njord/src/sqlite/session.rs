@@ -0,0 +1,87 @@
+use crate::error::Result;
+use crate::table::Table;
+use crate::trace::TransactionSpan;
+
+use rusqlite::Connection;
+
+use log::info;
+
+use super::delete::DeleteBuilder;
+use super::insert::InsertBuilder;
+use super::update::UpdateBuilder;
+use super::{delete, insert, insert_many, update};
+
+/// A transaction shared by many writes, committed or rolled back as a whole.
+///
+/// Every write opens a savepoint, which nests inside the session transaction
+/// instead of committing on its own. Dropping the session without calling
+/// `commit` rolls back all writes made through it.
+pub struct Session<'a> {
+    conn: &'a mut Connection,
+    finished: bool,
+    span: TransactionSpan,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(conn: &'a mut Connection) -> Result<Self> {
+        // the span lasts until the session is committed, rolled back or dropped
+        let span = TransactionSpan::new();
+        span.in_scope(|| conn.execute_batch("BEGIN"))?;
+
+        Ok(Session {
+            conn,
+            finished: false,
+            span,
+        })
+    }
+
+    /// Get the connection of the session, e.g. to select rows written in it.
+    pub fn conn(&self) -> &Connection {
+        self.conn
+    }
+
+    pub fn insert<'b>(&'b mut self, table_row: &'b mut dyn Table) -> InsertBuilder<'b> {
+        insert(self.conn, table_row)
+    }
+
+    pub fn insert_many<T: Table>(&mut self, table_rows: &mut [T]) -> Result<usize> {
+        insert_many(self.conn, table_rows)
+    }
+
+    pub fn update(&mut self) -> UpdateBuilder<'_> {
+        update(self.conn)
+    }
+
+    pub fn delete(&mut self) -> DeleteBuilder<'_> {
+        delete(self.conn)
+    }
+
+    /// Commits all writes made through the session.
+    ///
+    /// If the commit fails, e.g. with SQLITE_BUSY, the writes are rolled back when the
+    /// session is dropped.
+    pub fn commit(mut self) -> Result<()> {
+        self.span.in_scope(|| self.conn.execute_batch("COMMIT"))?;
+        self.finished = true;
+
+        info!("Committed session, done.");
+
+        Ok(())
+    }
+
+    /// Rolls back all writes made through the session.
+    pub fn rollback(mut self) -> Result<()> {
+        self.finished = true;
+        self.span.in_scope(|| self.conn.execute_batch("ROLLBACK"))?;
+
+        Ok(())
+    }
+}
+
+impl<'a> Drop for Session<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.span.in_scope(|| self.conn.execute_batch("ROLLBACK"));
+        }
+    }
+}
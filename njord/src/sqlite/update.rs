@@ -0,0 +1,280 @@
+use crate::dialect::{Dialect, Sqlite};
+use crate::error::{Error, Result};
+use crate::logger;
+use crate::sensitive::Sensitive;
+use crate::statement::{update_statement, Assignment};
+use crate::table::Table;
+use crate::timestamp::current_timestamp;
+use crate::value::Value;
+
+use rusqlite::{params_from_iter, Connection};
+use std::collections::HashMap;
+
+use log::info;
+
+use super::delete::limit_rows;
+use super::key::{check_writable, missing_primary_key, order_without_limit};
+use super::query::prepare_cached;
+use super::Condition;
+
+pub fn update<'a>(conn: &'a mut Connection) -> UpdateBuilder<'a> {
+    UpdateBuilder::new(conn)
+}
+
+pub struct UpdateBuilder<'a> {
+    conn: &'a mut Connection,
+    table: Option<&'a dyn Table>,
+    columns: Vec<(String, Assignment)>,
+    where_condition: Option<Condition>,
+    order_by: Option<HashMap<Vec<String>, String>>,
+    limit: Option<usize>,
+}
+
+impl<'a> UpdateBuilder<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        UpdateBuilder {
+            conn,
+            table: None,
+            columns: Vec::new(),
+            where_condition: None,
+            order_by: None,
+            limit: None,
+        }
+    }
+
+    pub fn table(mut self, table: &'a dyn Table) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    /// Sets the column to a value, bound as it is, e.g. `"00501"` as TEXT.
+    pub fn set(mut self, column: String, value: impl Into<Value>) -> Self {
+        self.columns.push((column, Assignment::Value(value.into())));
+        self
+    }
+
+    /// Sets the column to a typed value, e.g. a `Vec<u8>` bound as a BLOB.
+    pub fn set_value<V: Into<Value>>(mut self, column: &str, value: V) -> Self {
+        self.columns
+            .push((column.to_string(), Assignment::Value(value.into())));
+        self
+    }
+
+    /// Sets the column to an SQL expression evaluated by the database, e.g. `counter + 1`.
+    ///
+    /// The expression is inserted into the query as it is, so it must not contain user input.
+    pub fn set_expr(mut self, column: &str, expression: &str) -> Self {
+        self.columns.push((
+            column.to_string(),
+            Assignment::Expression(expression.to_string()),
+        ));
+        self
+    }
+
+    /// Adds the amount to the current value of the column, atomically within the update.
+    pub fn increment(mut self, column: &str, amount: i64) -> Self {
+        self.columns
+            .push((column.to_string(), Assignment::Add(amount)));
+        self
+    }
+
+    /// Subtracts the amount from the current value of the column, atomically within the update.
+    pub fn decrement(mut self, column: &str, amount: i64) -> Self {
+        self.columns
+            .push((column.to_string(), Assignment::Subtract(amount)));
+        self
+    }
+
+    pub fn where_clause(mut self, condition: Condition) -> Self {
+        self.where_condition = Some(condition);
+        self
+    }
+
+    /// Orders the rows picked by `limit`, which it requires.
+    pub fn order_by(mut self, col_and_order: HashMap<Vec<String>, String>) -> Self {
+        self.order_by = Some(col_and_order);
+        self
+    }
+
+    /// Updates at most the given number of rows, e.g. to chunk large updates.
+    pub fn limit(mut self, count: usize) -> Self {
+        self.limit = Some(count);
+        self
+    }
+
+    /// Updates the rows and returns the number of updated rows.
+    ///
+    /// Returns `Error::Generation` for an `order_by` without a `limit`, which would not
+    /// change the updated rows.
+    pub fn build(self) -> Result<usize> {
+        if let Some(table) = self.table {
+            check_writable(self.conn, table)?;
+        }
+
+        if self.order_by.is_some() && self.limit.is_none() {
+            return Err(order_without_limit("an update"));
+        }
+
+        let sensitive = self.sensitive();
+
+        let condition = match self.limit {
+            Some(count) => Some(limit_rows(
+                self.table,
+                self.where_condition,
+                &self.order_by,
+                count,
+            )),
+            None => self.where_condition,
+        };
+
+        let (query, params) =
+            update_statement(&Sqlite, self.table, &self.columns, condition.as_ref());
+
+        // create a transaction
+        let tx = self.conn.savepoint()?;
+
+        let count = logger::run(&query, &sensitive.mask(&params), |sql| {
+            prepare_cached(&tx, sql)?.execute(params_from_iter(&params))
+        })?;
+
+        // commit the transaction
+        tx.commit()?;
+
+        info!("Updated {} rows in table, done.", count);
+
+        Ok(count)
+    }
+
+    /// Get the values assigned to the sensitive columns, and compared with them by the
+    /// condition.
+    fn sensitive(&self) -> Sensitive {
+        let mut sensitive = Sensitive::new(self.table);
+
+        for (column, assignment) in &self.columns {
+            if let Assignment::Value(value) = assignment {
+                sensitive.column(column, value);
+            }
+        }
+
+        if let Some(condition) = &self.where_condition {
+            sensitive.condition(condition);
+        }
+
+        sensitive
+    }
+}
+
+/// Updates many rows of the same table by their primary key, within a single transaction.
+///
+/// Every non-key column is set to the current value of the struct through one prepared
+/// statement, executed once per row. Versioned rows get the bumped version written back,
+/// and a stale row rolls back all updates with `Error::VersionConflict`. The time the rows
+/// were updated at is written back into their `updated_at` column.
+/// Returns the number of updated rows.
+pub fn update_many<T: Table>(conn: &mut Connection, table_rows: &mut [T]) -> Result<usize> {
+    let first_row = match table_rows.first() {
+        Some(row) => row,
+        None => return Ok(0),
+    };
+
+    check_writable(conn, first_row)?;
+
+    let primary_key_columns = first_row.get_primary_key_columns();
+    if primary_key_columns.is_empty() {
+        return Err(missing_primary_key(first_row.get_name()));
+    }
+
+    let version_column = first_row.get_version_column();
+    let created_at_column = first_row.get_created_at_column();
+    let updated_at_column = first_row.get_updated_at_column();
+
+    let column_fields = first_row.get_column_fields();
+    let set_columns: Vec<&String> = column_fields
+        .iter()
+        .filter(|column| !primary_key_columns.contains(column))
+        .filter(|column| Some(*column) != version_column.as_ref())
+        .filter(|column| Some(*column) != created_at_column.as_ref())
+        .filter(|column| Some(*column) != updated_at_column.as_ref())
+        .collect();
+
+    // create a transaction
+    let tx = conn.savepoint()?;
+
+    let mut count = 0;
+    let mut versions: Vec<i64> = Vec::new();
+    let mut timestamps: Vec<Value> = Vec::new();
+    {
+        for table_row in table_rows.iter() {
+            let values: HashMap<String, Value> = table_row
+                .get_column_fields()
+                .into_iter()
+                .zip(table_row.get_column_values()?)
+                .collect();
+            let value = |column: &String| values.get(column).cloned().unwrap_or(Value::Null);
+
+            let mut assignments: Vec<(String, Assignment)> = set_columns
+                .iter()
+                .map(|column| (column.to_string(), Assignment::Value(value(column))))
+                .collect();
+
+            if let Some(column) = &updated_at_column {
+                let timestamp = current_timestamp(table_row, column);
+                assignments.push((column.clone(), Assignment::Value(timestamp.clone())));
+                timestamps.push(timestamp);
+            }
+
+            // the row is matched by its primary key and the version it was read with
+            let condition = primary_key_columns
+                .iter()
+                .chain(&version_column)
+                .map(|column| Condition::Eq(Sqlite.quote_identifier(column), value(column)))
+                .reduce(Condition::and);
+
+            // the statement is the same for every row, so it is compiled only once
+            let (query, params) =
+                update_statement(&Sqlite, Some(table_row), &assignments, condition.as_ref());
+
+            let sensitive = Sensitive::rows([table_row]);
+
+            let updated = logger::run(&query, &sensitive.mask(&params), |sql| {
+                prepare_cached(&tx, sql)?.execute(params_from_iter(&params))
+            })?;
+
+            if let Some(column) = &version_column {
+                if updated == 0 {
+                    return Err(Error::VersionConflict {
+                        table: table_row.get_name().to_string(),
+                    });
+                }
+
+                let version = match values.get(column) {
+                    Some(Value::Integer(version)) => *version,
+                    _ => 0,
+                };
+                versions.push(version + 1);
+            }
+
+            count += updated;
+        }
+    }
+
+    // commit the transaction
+    tx.commit()?;
+
+    // the bumped versions and timestamps are only written back once all updates are committed
+    if let Some(column) = &version_column {
+        for (table_row, version) in table_rows.iter_mut().zip(versions) {
+            table_row.set_column_value(column, Value::Integer(version))?;
+        }
+    }
+
+    if let Some(column) = &updated_at_column {
+        for (table_row, timestamp) in table_rows.iter_mut().zip(timestamps) {
+            table_row.set_column_value(column, timestamp)?;
+        }
+    }
+
+    info!("Updated {} rows in table, done.", count);
+
+    Ok(count)
+}
@@ -0,0 +1,88 @@
+use super::Aggregate;
+
+pub enum WindowFunction {
+    RowNumber,
+    Rank,
+    DenseRank,
+    /// An aggregate evaluated over the window, e.g. `SUM(price) OVER (...)`.
+    Aggregate(Aggregate),
+}
+
+impl WindowFunction {
+    /// Builds the SQL for the function call.
+    pub fn build(&self) -> String {
+        match self {
+            WindowFunction::RowNumber => "ROW_NUMBER()".to_string(),
+            WindowFunction::Rank => "RANK()".to_string(),
+            WindowFunction::DenseRank => "DENSE_RANK()".to_string(),
+            WindowFunction::Aggregate(aggregate) => aggregate.build(),
+        }
+    }
+}
+
+/// A window function expression, selectable alongside normal columns.
+///
+/// Set an alias to map the result into a struct field with the same name.
+pub struct Window {
+    function: WindowFunction,
+    partition_by: Vec<String>,
+    order_by: Vec<(String, String)>,
+    alias: Option<String>,
+}
+
+impl Window {
+    pub fn new(function: WindowFunction) -> Self {
+        Window {
+            function,
+            partition_by: Vec::new(),
+            order_by: Vec::new(),
+            alias: None,
+        }
+    }
+
+    pub fn partition_by(mut self, columns: Vec<String>) -> Self {
+        self.partition_by = columns;
+        self
+    }
+
+    /// Orders the rows of the window, as pairs of column and `ASC`/`DESC`.
+    pub fn order_by(mut self, col_and_order: Vec<(String, String)>) -> Self {
+        self.order_by = col_and_order;
+        self
+    }
+
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.alias = Some(alias.to_string());
+        self
+    }
+
+    /// Builds the SQL for the window expression.
+    pub fn build(&self) -> String {
+        let mut over = Vec::new();
+
+        if !self.partition_by.is_empty() {
+            over.push(format!("PARTITION BY {}", self.partition_by.join(", ")));
+        }
+
+        if !self.order_by.is_empty() {
+            let order_by_str: Vec<String> = self
+                .order_by
+                .iter()
+                .map(|(column, order)| format!("{} {}", column, order))
+                .collect();
+            over.push(format!("ORDER BY {}", order_by_str.join(", ")));
+        }
+
+        let alias_str = self
+            .alias
+            .as_ref()
+            .map_or(String::new(), |alias| format!(" AS {}", alias));
+
+        format!(
+            "{} OVER ({}){}",
+            self.function.build(),
+            over.join(" "),
+            alias_str
+        )
+    }
+}
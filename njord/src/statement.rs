@@ -0,0 +1,504 @@
+use crate::condition::Condition;
+use crate::dialect::Dialect;
+use crate::insert::get_insert_columns;
+use crate::join::JoinType;
+use crate::sensitive::Sensitive;
+use crate::table::Table;
+use crate::timestamp::current_timestamp;
+use crate::value::Value;
+
+use std::collections::HashMap;
+
+/// The parts of a select query, shared by the query builders of the backends.
+pub(crate) struct Select<'a> {
+    pub(crate) table: Option<&'a dyn Table>,
+    pub(crate) joins: Vec<(JoinType, &'a dyn Table, Option<Condition>)>,
+    pub(crate) columns: Vec<String>,
+    pub(crate) where_condition: Option<Condition>,
+    pub(crate) distinct: bool,
+    pub(crate) group_by: Option<Vec<String>>,
+    pub(crate) order_by: Option<HashMap<Vec<String>, String>>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) offset: Option<usize>,
+    pub(crate) having_condition: Option<Condition>,
+    pub(crate) with_deleted: bool,
+}
+
+impl<'a> Select<'a> {
+    pub(crate) fn new(columns: Vec<String>) -> Self {
+        Select {
+            table: None,
+            joins: Vec::new(),
+            columns,
+            where_condition: None,
+            distinct: false,
+            group_by: None,
+            order_by: None,
+            limit: None,
+            offset: None,
+            having_condition: None,
+            with_deleted: false,
+        }
+    }
+
+    /// Get the values of the sensitive columns the conditions of the query compare with.
+    pub(crate) fn sensitive(&self) -> Sensitive {
+        let tables = self
+            .table
+            .into_iter()
+            .chain(self.joins.iter().map(|join| join.1));
+        let mut sensitive = Sensitive::new(tables);
+
+        let join_conditions = self.joins.iter().filter_map(|join| join.2.as_ref());
+        for condition in join_conditions
+            .chain(&self.where_condition)
+            .chain(&self.having_condition)
+        {
+            sensitive.condition(condition);
+        }
+
+        sensitive
+    }
+
+    /// Generates the SQL for the query with `?` placeholders, along with the values bound
+    /// to them.
+    pub(crate) fn to_sql(&self, dialect: &dyn Dialect) -> (String, Vec<Value>) {
+        let table_name_str = self.table.map_or(String::new(), |table| {
+            dialect.quote_identifier(table.get_name())
+        });
+        let distinct_str = if self.distinct { "DISTINCT " } else { "" };
+        let top_str = match dialect.top(self.limit, self.offset) {
+            top if top.is_empty() => top,
+            top => format!("{} ", top),
+        };
+
+        let columns_str: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| dialect.quote_identifier(column))
+            .collect();
+
+        // values bound to the placeholders, in the order they appear in the query
+        let mut params: Vec<Value> = Vec::new();
+
+        let join_str = self
+            .joins
+            .iter()
+            .map(|(join_type, table, on)| {
+                let table_name = dialect.quote_identifier(table.get_name());
+
+                match on {
+                    Some(condition) => {
+                        params.extend(condition.get_params());
+                        format!(
+                            "{} {} ON {}",
+                            join_type.build(),
+                            table_name,
+                            condition.build()
+                        )
+                    }
+                    None => format!("{} {}", join_type.build(), table_name),
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let mut where_str: Vec<String> = Vec::new();
+
+        if let Some(condition) = &self.where_condition {
+            params.extend(condition.get_params());
+            where_str.push(condition.build());
+        }
+
+        // soft deleted rows of the selected table are left out unless asked for
+        if let Some(table) = self.table.filter(|_| !self.with_deleted) {
+            if let Some(column) = table.get_soft_delete_column() {
+                let column = format!("{}.{}", table.get_name(), column);
+                where_str.push(format!("{} IS NULL", dialect.quote_identifier(&column)));
+            }
+        }
+
+        let where_condition_str = match where_str.len() {
+            0 => String::new(),
+            1 => format!("WHERE {}", where_str[0]),
+            _ => format!("WHERE ({})", where_str.join(") AND (")),
+        };
+
+        let group_by_str = match &self.group_by {
+            Some(columns) => format!("GROUP BY {}", columns.join(", ")),
+            None => String::new(),
+        };
+
+        // having should only be added if group_by is present
+        let having_str = match (&self.group_by, &self.having_condition) {
+            (Some(_), Some(condition)) => {
+                params.extend(condition.get_params());
+                format!("HAVING {}", condition.build())
+            }
+            _ => String::new(),
+        };
+
+        let order_by_str: Vec<String> = self
+            .order_by
+            .iter()
+            .flatten()
+            .map(|(columns, order)| format!("{} {}", columns.join(", "), order))
+            .collect();
+        let order_by_str = if !order_by_str.is_empty() {
+            format!("ORDER BY {}", order_by_str.join(", "))
+        } else if self.offset.is_some() && dialect.offset_requires_order_by() {
+            // the skipped rows are in no particular order, as they are in the other databases
+            "ORDER BY (SELECT NULL)".to_string()
+        } else {
+            String::new()
+        };
+
+        let query = [
+            format!(
+                "SELECT {}{}{} FROM {}",
+                distinct_str,
+                top_str,
+                columns_str.join(", "),
+                table_name_str
+            ),
+            join_str,
+            where_condition_str,
+            group_by_str,
+            having_str,
+            order_by_str,
+            dialect.limit_offset(self.limit, self.offset),
+        ]
+        .into_iter()
+        .filter(|clause| !clause.is_empty())
+        .collect::<Vec<String>>()
+        .join(" ");
+
+        (query, params)
+    }
+}
+
+/// Generates the INSERT statement of `row_count` rows of the table, with `?` placeholders
+/// for the values of `get_insert_values` of each row.
+pub(crate) fn insert_statement(
+    dialect: &dyn Dialect,
+    table_row: &dyn Table,
+    row_count: usize,
+) -> String {
+    insert_columns_statement(
+        dialect,
+        table_row,
+        &get_insert_columns(table_row),
+        row_count,
+    )
+}
+
+/// Generates the INSERT statement of `row_count` rows of the given columns of the table,
+/// e.g. of a saved row whose primary key is set, with `?` placeholders for their values.
+pub(crate) fn insert_columns_statement(
+    dialect: &dyn Dialect,
+    table_row: &dyn Table,
+    column_fields: &[String],
+    row_count: usize,
+) -> String {
+    let table_name_str = dialect.quote_identifier(table_row.get_name());
+
+    // a row whose columns are all assigned by the database has nothing to insert
+    if column_fields.is_empty() {
+        return format!(
+            "INSERT INTO {} {}",
+            table_name_str,
+            dialect.default_values(row_count)
+        );
+    }
+
+    let values_str = vec!["?"; column_fields.len()];
+
+    // repeat the placeholders for every row
+    let rows_str = vec![format!("({})", values_str.join(", ")); row_count].join(", ");
+
+    let columns_str: Vec<String> = column_fields
+        .iter()
+        .map(|column| dialect.quote_identifier(column))
+        .collect();
+
+    format!(
+        "INSERT INTO {} ({}) VALUES {}",
+        table_name_str,
+        columns_str.join(", "),
+        rows_str
+    )
+}
+
+/// The new value of an updated column.
+pub(crate) enum Assignment {
+    /// A value bound to a placeholder.
+    Value(Value),
+    /// An SQL expression, inserted into the statement as it is.
+    Expression(String),
+    /// The current value of the column plus an amount.
+    Add(i64),
+    /// The current value of the column minus an amount.
+    Subtract(i64),
+}
+
+/// Generates the UPDATE statement of the rows of a table matching the condition, with
+/// `?` placeholders, along with the values bound to them.
+///
+/// Every update of a versioned table bumps the version of the updated rows, and one of a
+/// table with an `updated_at` column sets it to the current time, unless it is assigned.
+pub(crate) fn update_statement(
+    dialect: &dyn Dialect,
+    table: Option<&dyn Table>,
+    assignments: &[(String, Assignment)],
+    condition: Option<&Condition>,
+) -> (String, Vec<Value>) {
+    let table_name_str = table.map_or(String::new(), |table| {
+        dialect.quote_identifier(table.get_name())
+    });
+
+    // values bound to the placeholders, in the order they appear in the statement
+    let mut params: Vec<Value> = Vec::new();
+
+    let mut set_str = assignments
+        .iter()
+        .map(|(column, assignment)| {
+            let column = dialect.quote_identifier(column);
+
+            match assignment {
+                Assignment::Value(value) => {
+                    params.push(value.clone());
+                    format!("{} = ?", column)
+                }
+                Assignment::Expression(expression) => format!("{} = {}", column, expression),
+                Assignment::Add(amount) => {
+                    params.push(Value::Integer(*amount));
+                    format!("{} = {} + ?", column, column)
+                }
+                Assignment::Subtract(amount) => {
+                    params.push(Value::Integer(*amount));
+                    format!("{} = {} - ?", column, column)
+                }
+            }
+        })
+        .collect::<Vec<String>>();
+
+    if let Some(column) = table.and_then(|t| t.get_version_column()) {
+        let column = dialect.quote_identifier(&column);
+        set_str.push(format!("{} = {} + 1", column, column));
+    }
+
+    let updated_at_column = table
+        .and_then(|t| t.get_updated_at_column().map(|column| (t, column)))
+        .filter(|(_, column)| !assignments.iter().any(|(assigned, _)| assigned == column));
+
+    if let Some((table, column)) = updated_at_column {
+        params.push(current_timestamp(table, &column));
+        set_str.push(format!("{} = ?", dialect.quote_identifier(&column)));
+    }
+
+    let where_condition_str = match condition {
+        Some(condition) => {
+            params.extend(condition.get_params());
+            format!(" WHERE {}", condition.build())
+        }
+        None => String::new(),
+    };
+
+    let statement = format!(
+        "UPDATE {} SET {}{}",
+        table_name_str,
+        set_str.join(", "),
+        where_condition_str
+    );
+
+    (statement, params)
+}
+
+/// Generates the DELETE statement of the rows of a table matching the condition, with
+/// `?` placeholders, along with the values bound to them.
+///
+/// The rows of a table with a soft delete column are marked as deleted with an UPDATE
+/// setting it to the current time instead, unless `force` is set.
+pub(crate) fn delete_statement(
+    dialect: &dyn Dialect,
+    table: Option<&dyn Table>,
+    condition: Option<&Condition>,
+    force: bool,
+) -> (String, Vec<Value>) {
+    let table_name_str = table.map_or(String::new(), |table| {
+        dialect.quote_identifier(table.get_name())
+    });
+
+    // values bound to the placeholders, in the order they appear in the statement
+    let mut params: Vec<Value> = Vec::new();
+
+    let soft_delete_column = match force {
+        true => None,
+        false => table.and_then(|t| t.get_soft_delete_column().map(|column| (t, column))),
+    };
+
+    // the timestamp is bound before the values of the condition
+    let soft_delete_column = soft_delete_column.map(|(table, column)| {
+        params.push(current_timestamp(table, &column));
+        dialect.quote_identifier(&column)
+    });
+
+    let mut where_str: Vec<String> = Vec::new();
+
+    if let Some(condition) = condition {
+        params.extend(condition.get_params());
+        where_str.push(condition.build());
+    }
+
+    // rows that are already soft deleted keep their original timestamp
+    if let Some(column) = &soft_delete_column {
+        where_str.push(format!("{} IS NULL", column));
+    }
+
+    let where_condition_str = match where_str.len() {
+        0 => String::new(),
+        1 => format!(" WHERE {}", where_str[0]),
+        _ => format!(" WHERE ({})", where_str.join(") AND (")),
+    };
+
+    let statement = match &soft_delete_column {
+        Some(column) => format!(
+            "UPDATE {} SET {} = ?{}",
+            table_name_str, column, where_condition_str
+        ),
+        None => format!("DELETE FROM {}{}", table_name_str, where_condition_str),
+    };
+
+    (statement, params)
+}
+
+/// Whether a statement writes rows, i.e. is an INSERT, UPDATE, DELETE or REPLACE, so that
+/// the rows it returns or counts are the rows it changed.
+///
+/// The statement is classified by its main keyword, i.e. after its leading comments, e.g.
+/// a `/* request-id */` prepended by a middleware, and after the common table expressions
+/// of a `WITH`.
+pub(crate) fn is_write(sql: &str) -> bool {
+    let keyword = first_word(main_statement(sql));
+
+    ["INSERT", "UPDATE", "DELETE", "REPLACE"]
+        .iter()
+        .any(|write| keyword.eq_ignore_ascii_case(write))
+}
+
+/// Get the main statement of a SQL statement, i.e. the SQL from its main keyword on, after
+/// its leading whitespace and comments, and after the common table expressions of a `WITH`,
+/// whose main keyword is the first SELECT, INSERT, UPDATE, DELETE, REPLACE or VALUES
+/// outside of their parentheses.
+fn main_statement(sql: &str) -> &str {
+    let sql = skip_comments(sql);
+    if !first_word(sql).eq_ignore_ascii_case("WITH") {
+        return sql;
+    }
+
+    let bytes = sql.as_bytes();
+    let is_word = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_';
+
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            // quoted literals and identifiers are skipped, along with their parentheses
+            quote @ (b'\'' | b'"' | b'`' | b'[') => {
+                let close = if quote == b'[' { b']' } else { quote };
+                i = bytes[i + 1..]
+                    .iter()
+                    .position(|&byte| byte == close)
+                    .map_or(bytes.len(), |end| i + end + 2);
+            }
+            b'-' | b'/' => {
+                let rest = skip_comments(&sql[i..]);
+                i = match rest.len() < bytes.len() - i {
+                    true => bytes.len() - rest.len(),
+                    false => i + 1,
+                };
+            }
+            b'(' => {
+                depth += 1;
+                i += 1;
+            }
+            b')' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            byte if is_word(byte) => {
+                let word = first_word(&sql[i..]);
+                if depth == 0
+                    && ["SELECT", "INSERT", "UPDATE", "DELETE", "REPLACE", "VALUES"]
+                        .iter()
+                        .any(|keyword| word.eq_ignore_ascii_case(keyword))
+                {
+                    return &sql[i..];
+                }
+
+                i += word.len();
+            }
+            _ => i += 1,
+        }
+    }
+
+    sql
+}
+
+/// Get a statement without its leading whitespace and `--` and `/* */` comments.
+fn skip_comments(sql: &str) -> &str {
+    let mut sql = sql.trim_start();
+    loop {
+        sql = if let Some(rest) = sql.strip_prefix("--") {
+            rest.split_once('\n').map_or("", |(_, rest)| rest)
+        } else if let Some(rest) = sql.strip_prefix("/*") {
+            rest.split_once("*/").map_or("", |(_, rest)| rest)
+        } else {
+            return sql;
+        }
+        .trim_start();
+    }
+}
+
+/// Get the first word of a statement, i.e. its leading letters, digits and underscores.
+fn first_word(sql: &str) -> &str {
+    let end = sql
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(sql.len());
+
+    &sql[..end]
+}
+
+/// Get the kind of a statement, i.e. its main keyword as in `is_write`, e.g. `SELECT`, and
+/// the name of its table, i.e. the name following the first FROM, INTO, UPDATE, TABLE or
+/// ON keyword of its main statement outside of a subquery, which is that of the table of
+/// the generated statements.
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+pub(crate) fn describe(sql: &str) -> (String, Option<&str>) {
+    let sql = main_statement(sql);
+    let kind = first_word(sql).to_ascii_uppercase();
+
+    let mut words = sql.split_whitespace();
+    while let Some(word) = words.next() {
+        if ["FROM", "INTO", "UPDATE", "TABLE", "ON"]
+            .iter()
+            .any(|keyword| word.eq_ignore_ascii_case(keyword))
+        {
+            // skip the IF NOT EXISTS of a CREATE TABLE and the IF EXISTS of a DROP TABLE
+            let name = words.find(|word| {
+                !["IF", "NOT", "EXISTS"]
+                    .iter()
+                    .any(|keyword| word.eq_ignore_ascii_case(keyword))
+            });
+
+            // the table of a subquery is searched for in the subquery
+            if let Some(name) = name.filter(|name| !name.starts_with('(')) {
+                let name = name.split(['(', ';', ',']).next().unwrap_or(name);
+
+                return (kind, Some(name.trim_matches(['"', '`', '[', ']'])));
+            }
+        }
+    }
+
+    (kind, None)
+}
@@ -1,10 +1,66 @@
-use rusqlite::types::Value;
+use crate::error::Result;
+use crate::value::Value;
 use std::collections::HashMap;
 
 #[cfg(feature = "derive")]
 #[allow(unused_imports)]
 use njord_derive::Table;
 
+/// The schema of a column, used to generate the DDL of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSchema {
+    /// The name of the column.
+    pub name: String,
+    /// The SQL type of the column, e.g. `INTEGER` or `TEXT`.
+    pub column_type: String,
+    /// Whether the column accepts NULL, i.e. its field is an `Option`.
+    pub nullable: bool,
+    /// Whether the column is part of the primary key.
+    pub primary_key: bool,
+    /// Whether the column is an auto-increment primary key.
+    pub auto_increment: bool,
+    /// Whether the column has a UNIQUE constraint.
+    pub unique: bool,
+    /// The SQL expression of the `DEFAULT` of the column, if any.
+    pub default_value: Option<String>,
+    /// The SQL expression of the `CHECK` constraint of the column, if any.
+    pub check: Option<String>,
+    /// The table and column referenced by the column as a foreign key, e.g. `users(id)`, if any.
+    pub references: Option<String>,
+}
+
+/// An index of a table, declared with `#[njord(index)]` or `#[njord(unique_index)]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexSchema {
+    /// The name of the index.
+    pub name: String,
+    /// The indexed columns, in order.
+    pub columns: Vec<String>,
+    /// Whether the index is a UNIQUE index.
+    pub unique: bool,
+}
+
+/// A relation to another table, declared with `#[njord(belongs_to = "...")]` or
+/// `#[njord(has_many = "...")]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relation {
+    /// Whether this table references the related table or is referenced by it.
+    pub kind: RelationKind,
+    /// The name of the related table.
+    pub table: String,
+    /// The foreign key column, of this table for `BelongsTo` and of the related table for `HasMany`.
+    pub foreign_key: String,
+}
+
+/// The kind of a relation to another table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationKind {
+    /// This table has a foreign key referencing the primary key of the related table.
+    BelongsTo,
+    /// The related table has a foreign key referencing the primary key of this table.
+    HasMany,
+}
+
 /// The Table trait.
 ///
 /// It is used for structs that want need the behaviour of an SQL Table.
@@ -28,15 +84,148 @@ pub trait Table {
 
     /// Get the values of the columns.
     ///
-    /// Returns a `Vec<String>` containing the values of the columns in the same order
-    /// as they appear in the table.
-    fn get_column_values(&self) -> Vec<String>;
+    /// Returns a `Vec<Value>` containing the values of the columns in the same order
+    /// as they appear in the table, with `None` fields as NULL, or `Error::Mapping` if a
+    /// field cannot be converted into the value of its column, e.g. a decimal too large
+    /// for its scaled INTEGER.
+    fn get_column_values(&self) -> Result<Vec<Value>>;
 
     /// Set the values of the columns.
     ///
-    /// Returns a `Vec<String>` containing the values of the columns in the same order
-    /// as they appear in the table.
-    fn set_column_value(&mut self, column: &str, value: Value);
+    /// Returns `Error::Mapping` if the value cannot be converted into the field of the
+    /// column, which is left as it was.
+    fn set_column_value(&mut self, column: &str, value: Value) -> Result<()>;
+
+    /// Get the names of the primary key columns.
+    ///
+    /// Returns an empty `Vec` if the table has no primary key.
+    fn get_primary_key_columns(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Get the primary key columns with their current values.
+    ///
+    /// Returns the pairs in the same order as `get_primary_key_columns`,
+    /// or an empty `Vec` if the table has no primary key.
+    fn get_primary_key(&self) -> Result<Vec<(String, Value)>> {
+        let primary_key_columns = self.get_primary_key_columns();
+        let mut values: HashMap<String, Value> = self
+            .get_column_fields()
+            .into_iter()
+            .zip(self.get_column_values()?)
+            .collect();
+
+        Ok(primary_key_columns
+            .into_iter()
+            .map(|column| {
+                let value = values.remove(&column).unwrap_or(Value::Null);
+                (column, value)
+            })
+            .collect())
+    }
+
+    /// Get the name of the auto-increment primary key column, if any.
+    ///
+    /// The column is left out of inserts so the database assigns it, and the
+    /// assigned rowid is written back into it afterwards.
+    fn get_auto_increment_column(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the name of the column marking soft deleted rows, if any.
+    ///
+    /// Deletes set the column to the current timestamp instead of removing rows,
+    /// and queries leave out the rows where it is not NULL.
+    fn get_soft_delete_column(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the name of the column holding the version of the row, if any.
+    ///
+    /// Updates bump the version, and saving a row fails if its version is stale.
+    fn get_version_column(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the name of the column set to the current timestamp when the row is inserted, if any.
+    fn get_created_at_column(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the name of the column set to the current timestamp when the row is
+    /// inserted or updated, if any.
+    fn get_updated_at_column(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the names of the columns filled by their database `DEFAULT` on insert.
+    ///
+    /// The columns are left out of inserts, but are still selected and updated.
+    fn get_default_columns(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Get the names of the columns holding sensitive values, e.g. passwords or tokens,
+    /// marked with `#[njord(sensitive)]`.
+    ///
+    /// Their values are masked wherever the parameters of a statement are reported, e.g.
+    /// to the query logger.
+    fn get_sensitive_columns(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Get the schema of the columns, in the same order as `get_column_fields`.
+    ///
+    /// Columns of tables not deriving `Table` are assumed to be NOT NULL, unless they
+    /// are soft delete columns.
+    fn get_schema(&self) -> Vec<ColumnSchema> {
+        let columns = self.get_columns();
+        let primary_key_columns = self.get_primary_key_columns();
+        let auto_increment_column = self.get_auto_increment_column();
+        let soft_delete_column = self.get_soft_delete_column();
+
+        self.get_column_fields()
+            .into_iter()
+            .map(|name| ColumnSchema {
+                column_type: columns.get(&name).cloned().unwrap_or_default(),
+                nullable: Some(&name) == soft_delete_column.as_ref(),
+                primary_key: primary_key_columns.contains(&name),
+                auto_increment: Some(&name) == auto_increment_column.as_ref(),
+                unique: false,
+                default_value: None,
+                check: None,
+                references: None,
+                name,
+            })
+            .collect()
+    }
+
+    /// Get the `CHECK` constraints of the table spanning several columns,
+    /// declared with `#[njord(check = "...")]` on the struct.
+    fn get_checks(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Get the indexes of the table.
+    fn get_indexes(&self) -> Vec<IndexSchema> {
+        Vec::new()
+    }
+
+    /// Get the relations of the table to other tables.
+    fn get_relations(&self) -> Vec<Relation> {
+        Vec::new()
+    }
+
+    /// Whether the table maps a read-only view or projection, set by `#[njord(readonly)]`.
+    ///
+    /// Inserts, updates and deletes of a read-only table fail with an error.
+    fn is_readonly(&self) -> bool {
+        false
+    }
+
+    /// Generate the values of the columns filled by njord on insert that are still unset,
+    /// e.g. UUIDs marked with `#[njord(default = "uuid_v4")]`.
+    fn generate_values(&mut self) {}
 }
 
 // #[test]
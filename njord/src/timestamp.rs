@@ -0,0 +1,53 @@
+use crate::table::Table;
+use crate::value::Value;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Get the current time in the format of a timestamp column of the table, i.e. the seconds
+/// since the unix epoch for an INTEGER column, e.g. of `#[njord(datetime = "unix")]`, and
+/// `YYYY-MM-DD HH:MM:SS` text in UTC otherwise.
+pub(crate) fn current_timestamp(table: &dyn Table, column: &str) -> Value {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            i64::try_from(elapsed.as_secs()).unwrap_or(i64::MAX)
+        });
+
+    match table.get_columns().get(column).map(String::as_str) {
+        Some("INTEGER") => Value::Integer(seconds),
+        _ => {
+            let time = seconds.rem_euclid(SECONDS_PER_DAY);
+
+            Value::Text(format!(
+                "{} {:02}:{:02}:{:02}",
+                format_date(seconds.div_euclid(SECONDS_PER_DAY)),
+                time / 3600,
+                time / 60 % 60,
+                time % 60
+            ))
+        }
+    }
+}
+
+/// Formats the days since the Unix epoch as `YYYY-MM-DD`.
+pub(crate) fn format_date(days: i64) -> String {
+    // the civil date of a day number, shifted to eras starting on March 1st
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
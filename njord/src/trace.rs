@@ -0,0 +1,140 @@
+use crate::logger::RowCount;
+#[cfg(feature = "tracing")]
+use crate::statement::describe;
+
+#[cfg(feature = "tracing")]
+use tracing::{field, Span};
+
+/// Runs a statement with `f`, within an `execute` span with the tracing feature.
+///
+/// The span has the `kind` and `table` of the statement, and the `rows` it returned or
+/// changed once it succeeded.
+pub(crate) fn execute<R: RowCount, E>(
+    sql: &str,
+    f: impl FnOnce() -> std::result::Result<R, E>,
+) -> std::result::Result<R, E> {
+    #[cfg(feature = "tracing")]
+    {
+        let span = statement_span(sql);
+        let result = span.in_scope(f);
+
+        record_rows(&span, &result);
+
+        result
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = sql;
+
+        f()
+    }
+}
+
+/// Runs a statement with the future `f`, within an `execute` span as in `execute`.
+#[cfg(any(feature = "libsql", feature = "mssql"))]
+pub(crate) async fn execute_async<R: RowCount, E>(
+    sql: &str,
+    f: impl std::future::Future<Output = std::result::Result<R, E>>,
+) -> std::result::Result<R, E> {
+    #[cfg(feature = "tracing")]
+    {
+        use tracing::Instrument;
+
+        let span = statement_span(sql);
+        let result = f.instrument(span.clone()).await;
+
+        record_rows(&span, &result);
+
+        result
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = sql;
+
+        f.await
+    }
+}
+
+/// Prepares a statement with `f`, within a `prepare` span with the `kind` and `table` of
+/// the statement with the tracing feature.
+pub(crate) fn prepare<R>(sql: &str, f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "tracing")]
+    {
+        let (kind, table) = describe(sql);
+
+        tracing::debug_span!("prepare", kind = kind.as_str(), table).in_scope(f)
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = sql;
+
+        f()
+    }
+}
+
+/// Runs a transaction with `f`, within a `transaction` span with the tracing feature.
+pub(crate) fn transaction<R>(f: impl FnOnce() -> R) -> R {
+    TransactionSpan::new().in_scope(f)
+}
+
+/// Runs a transaction with the future `f`, within a `transaction` span as in `transaction`.
+#[cfg(any(feature = "libsql", feature = "mssql"))]
+pub(crate) async fn transaction_async<R>(f: impl std::future::Future<Output = R>) -> R {
+    #[cfg(feature = "tracing")]
+    {
+        use tracing::Instrument;
+
+        f.instrument(TransactionSpan::new().span).await
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        f.await
+    }
+}
+
+/// The `transaction` span of a transaction run by many calls, e.g. of a `sqlite::Session`
+/// from its BEGIN to its COMMIT or ROLLBACK, which is empty without the tracing feature.
+pub(crate) struct TransactionSpan {
+    #[cfg(feature = "tracing")]
+    span: Span,
+}
+
+impl TransactionSpan {
+    pub(crate) fn new() -> Self {
+        TransactionSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!("transaction"),
+        }
+    }
+
+    /// Runs a statement of the transaction with `f`, within its span.
+    pub(crate) fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "tracing")]
+        {
+            self.span.in_scope(f)
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        {
+            f()
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn statement_span(sql: &str) -> Span {
+    let (kind, table) = describe(sql);
+
+    tracing::debug_span!("execute", kind = kind.as_str(), table, rows = field::Empty)
+}
+
+#[cfg(feature = "tracing")]
+fn record_rows<R: RowCount, E>(span: &Span, result: &std::result::Result<R, E>) {
+    if let Some(rows) = result.as_ref().ok().and_then(RowCount::row_count) {
+        span.record("rows", rows);
+    }
+}
@@ -1,29 +1,38 @@
-/// Converts values for SQL INSERT
+use crate::value::Value;
+
+/// Converts a single value into a bindable SQL value
 ///
 /// # Arguments
 ///
-/// * 'values' - A vector of strings to be parsed.
+/// * 'value' - A string to be parsed.
 ///
 /// # Returns
 ///
-/// A new vector of strings with surrounding single quotes if it contains text.
-pub fn convert_insert_values(values: Vec<String>) -> Vec<String> {
-    let mut result = Vec::new();
-
-    for item in values {
-        if let Ok(parsed_int) = item.parse::<i32>() {
-            result.push(parsed_int.to_string());
-        } else if let Ok(parsed_float) = item.parse::<f64>() {
-            result.push(parsed_float.to_string());
-        } else if item.eq_ignore_ascii_case("true") {
-            result.push("true".to_string());
-        } else if item.eq_ignore_ascii_case("false") {
-            result.push("false".to_string());
-        } else {
-            // if it's not true or false, surround it with single quotes and push it.
-            result.push(format!("'{}'", item));
-        }
+/// An integer or real `Value` if the string is numeric, an integer `Value` for booleans,
+/// otherwise a text `Value`.
+pub fn convert_value(value: &str) -> Value {
+    if let Ok(parsed_int) = value.parse::<i64>() {
+        Value::Integer(parsed_int)
+    } else if let Some(parsed_float) = value.parse::<f64>().ok().filter(|f| f.is_finite()) {
+        Value::Real(parsed_float)
+    } else if value.eq_ignore_ascii_case("true") {
+        Value::Integer(1)
+    } else if value.eq_ignore_ascii_case("false") {
+        Value::Integer(0)
+    } else {
+        Value::Text(value.to_string())
     }
+}
 
-    result
+/// Converts values for SQL INSERT
+///
+/// # Arguments
+///
+/// * 'values' - A vector of strings to be parsed.
+///
+/// # Returns
+///
+/// A new vector of values to be bound to the statement parameters.
+pub fn convert_insert_values(values: Vec<String>) -> Vec<Value> {
+    values.iter().map(|item| convert_value(item)).collect()
 }
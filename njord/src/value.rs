@@ -0,0 +1,608 @@
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+/// The value of a column, or of a parameter bound to a query.
+///
+/// It is shared by every backend, which converts it from and into the value type of
+/// its driver.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// The NULL value.
+    Null,
+    /// A signed integer.
+    Integer(i64),
+    /// A floating point number.
+    Real(f64),
+    /// A UTF-8 string.
+    Text(String),
+    /// A byte array.
+    Blob(Vec<u8>),
+}
+
+impl Value {
+    /// Get the borrowed value, e.g. to parse a field from it with `FromColumn`.
+    pub fn as_value_ref(&self) -> ValueRef<'_> {
+        match self {
+            Value::Null => ValueRef::Null,
+            Value::Integer(value) => ValueRef::Integer(*value),
+            Value::Real(value) => ValueRef::Real(*value),
+            Value::Text(value) => ValueRef::Text(value.as_bytes()),
+            Value::Blob(value) => ValueRef::Blob(value),
+        }
+    }
+}
+
+/// The borrowed value a selected column is parsed from, used by `FromValue`, `FromColumn`
+/// and the conversions of `derive(Table)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueRef<'a> {
+    /// The NULL value.
+    Null,
+    /// A signed integer.
+    Integer(i64),
+    /// A floating point number.
+    Real(f64),
+    /// The UTF-8 bytes of a string.
+    Text(&'a [u8]),
+    /// A byte array.
+    Blob(&'a [u8]),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Get the integer, failing for any other value.
+    pub fn as_i64(&self) -> FromSqlResult<i64> {
+        match *self {
+            ValueRef::Integer(value) => Ok(value),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+
+    /// Get the floating point number, failing for any other value.
+    pub fn as_f64(&self) -> FromSqlResult<f64> {
+        match *self {
+            ValueRef::Real(value) => Ok(value),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+
+    /// Get the string, failing for any other value or for invalid UTF-8.
+    pub fn as_str(&self) -> FromSqlResult<&'a str> {
+        match *self {
+            ValueRef::Text(value) => {
+                std::str::from_utf8(value).map_err(|error| FromSqlError::Other(Box::new(error)))
+            }
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+
+    /// Get the byte array, failing for any other value.
+    pub fn as_blob(&self) -> FromSqlResult<&'a [u8]> {
+        match *self {
+            ValueRef::Blob(value) => Ok(value),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+impl From<ValueRef<'_>> for Value {
+    /// The bytes of a string that is not valid UTF-8 are replaced, as they cannot be
+    /// parsed into a field anyway.
+    fn from(value: ValueRef<'_>) -> Self {
+        match value {
+            ValueRef::Null => Value::Null,
+            ValueRef::Integer(value) => Value::Integer(value),
+            ValueRef::Real(value) => Value::Real(value),
+            ValueRef::Text(value) => Value::Text(String::from_utf8_lossy(value).into_owned()),
+            ValueRef::Blob(value) => Value::Blob(value.to_vec()),
+        }
+    }
+}
+
+/// The error of parsing a field from the value of its column.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FromSqlError {
+    /// The value has a type the field cannot be parsed from, e.g. TEXT for an integer.
+    InvalidType,
+    /// The integer does not fit into the field.
+    OutOfRange(i64),
+    /// The byte array does not have the size of the field.
+    InvalidBlobSize {
+        expected_size: usize,
+        blob_size: usize,
+    },
+    /// Any other error, e.g. of parsing a date.
+    Other(Box<dyn Error + Send + Sync + 'static>),
+}
+
+impl fmt::Display for FromSqlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromSqlError::InvalidType => write!(f, "Invalid type"),
+            FromSqlError::OutOfRange(value) => write!(f, "Value {} out of range", value),
+            FromSqlError::InvalidBlobSize {
+                expected_size,
+                blob_size,
+            } => write!(
+                f,
+                "Cannot read {} byte value out of {} byte blob",
+                expected_size, blob_size
+            ),
+            FromSqlError::Other(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for FromSqlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FromSqlError::Other(error) => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+/// The result of parsing a field from the value of its column.
+pub type FromSqlResult<T> = Result<T, FromSqlError>;
+
+/// The ToValue trait.
+///
+/// It converts the fields of a struct deriving `Table` without a `#[njord(...)]`
+/// conversion into the values of their columns.
+pub trait ToValue {
+    /// Get the value of the column, or `None` if the field does not fit into one, e.g.
+    /// a `u64` above `i64::MAX`.
+    fn to_value(&self) -> Option<Value>;
+}
+
+/// The FromValue trait.
+///
+/// It parses the fields of a struct deriving `Table` without a `#[njord(...)]`
+/// conversion from the values of their selected columns.
+pub trait FromValue: Sized {
+    /// Parse the value of the column.
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self>;
+}
+
+macro_rules! from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Value {
+                fn from(value: $t) -> Self {
+                    Value::Integer(i64::from(value))
+                }
+            }
+
+            impl ToValue for $t {
+                fn to_value(&self) -> Option<Value> {
+                    Some(Value::from(*self))
+                }
+            }
+
+            impl FromValue for $t {
+                fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                    let integer = value.as_i64()?;
+                    Self::try_from(integer).map_err(|_| FromSqlError::OutOfRange(integer))
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i8, i16, i32, i64, u8, u16, u32);
+
+/// Integers that may not fit into an INTEGER, which are converted fallibly.
+macro_rules! try_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl ToValue for $t {
+                fn to_value(&self) -> Option<Value> {
+                    i64::try_from(*self).ok().map(Value::Integer)
+                }
+            }
+
+            impl FromValue for $t {
+                fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                    let integer = value.as_i64()?;
+                    Self::try_from(integer).map_err(|_| FromSqlError::OutOfRange(integer))
+                }
+            }
+        )*
+    };
+}
+
+try_from_integer!(isize, u64, usize);
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Integer(i64::from(value))
+    }
+}
+
+/// Booleans are stored as 0 and 1, and any other integer is read as `true`.
+impl ToValue for bool {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_i64().map(|integer| integer != 0)
+    }
+}
+
+impl From<isize> for Value {
+    fn from(value: isize) -> Self {
+        Value::Integer(value as i64)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Real(f64::from(value))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Real(value)
+    }
+}
+
+impl ToValue for f32 {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
+}
+
+impl ToValue for f64 {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
+}
+
+/// Floating point numbers are also read from integers, e.g. of a column declared INTEGER.
+impl FromValue for f32 {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        f64::from_value(value).map(|real| real as f32)
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(integer) => Ok(integer as f64),
+            ValueRef::Real(real) => Ok(real),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Text(self.clone()))
+    }
+}
+
+impl ToValue for str {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(self))
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str().map(str::to_string)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Blob(value)
+    }
+}
+
+impl ToValue for Vec<u8> {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Blob(self.clone()))
+    }
+}
+
+impl ToValue for [u8] {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Blob(self.to_vec()))
+    }
+}
+
+impl<const N: usize> ToValue for [u8; N] {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Blob(self.to_vec()))
+    }
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_blob().map(<[u8]>::to_vec)
+    }
+}
+
+impl<const N: usize> FromValue for [u8; N] {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let blob = value.as_blob()?;
+
+        blob.try_into().map_err(|_| FromSqlError::InvalidBlobSize {
+            expected_size: N,
+            blob_size: blob.len(),
+        })
+    }
+}
+
+/// UUIDs are stored as a 16-byte BLOB, unless marked with `#[njord(uuid = "text")]`.
+#[cfg(feature = "uuid")]
+impl From<Uuid> for Value {
+    fn from(value: Uuid) -> Self {
+        Value::Blob(value.as_bytes().to_vec())
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl ToValue for Uuid {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl FromValue for Uuid {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        <[u8; 16]>::from_value(value).map(Uuid::from_bytes)
+    }
+}
+
+/// Dates and times are stored as ISO-8601 TEXT, unless marked with
+/// `#[njord(datetime = "unix")]`.
+#[cfg(feature = "chrono")]
+impl ToValue for NaiveDate {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Text(self.format("%F").to_string()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromValue for NaiveDate {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        NaiveDate::parse_from_str(value.as_str()?, "%F")
+            .map_err(|error| FromSqlError::Other(Box::new(error)))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToValue for NaiveTime {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Text(self.format("%T%.f").to_string()))
+    }
+}
+
+/// Times are read with or without their seconds, e.g. `12:30`.
+#[cfg(feature = "chrono")]
+impl FromValue for NaiveTime {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let text = value.as_str()?;
+        let format = match text.len() {
+            5 => "%H:%M",
+            8 => "%T",
+            _ => "%T%.f",
+        };
+
+        NaiveTime::parse_from_str(text, format)
+            .map_err(|error| FromSqlError::Other(Box::new(error)))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToValue for NaiveDateTime {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Text(self.format("%F %T%.f").to_string()))
+    }
+}
+
+/// Dates and times are read with a space or a `T` between the date and the time.
+#[cfg(feature = "chrono")]
+impl FromValue for NaiveDateTime {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let text = value.as_str()?;
+        let format = match text.as_bytes().get(10) {
+            Some(b'T') => "%FT%T%.f",
+            _ => "%F %T%.f",
+        };
+
+        NaiveDateTime::parse_from_str(text, format)
+            .map_err(|error| FromSqlError::Other(Box::new(error)))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToValue for DateTime<Utc> {
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::Text(self.format("%F %T%.f%:z").to_string()))
+    }
+}
+
+/// Dates and times with an offset are converted to UTC, and those without one are read
+/// as UTC, e.g. the `CURRENT_TIMESTAMP` of a `created_at` column.
+#[cfg(feature = "chrono")]
+impl FromValue for DateTime<Utc> {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let text = value.as_str()?;
+        let format = match text.as_bytes().get(10) {
+            Some(b'T') => "%FT%T%.f%#z",
+            _ => "%F %T%.f%#z",
+        };
+
+        match DateTime::parse_from_str(text, format) {
+            Ok(datetime) => Ok(datetime.with_timezone(&Utc)),
+            Err(_) => {
+                NaiveDateTime::from_value(value).map(|datetime| Utc.from_utc_datetime(&datetime))
+            }
+        }
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> Option<Value> {
+        match self {
+            Some(value) => value.to_value(),
+            None => Some(Value::Null),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(None),
+            _ => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl ToValue for Value {
+    fn to_value(&self) -> Option<Value> {
+        Some(self.clone())
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Ok(value.into())
+    }
+}
+
+/// Converts a field into the value of its column with its `ToValue` conversion.
+///
+/// It is used by `derive(Table)` for fields without a `#[njord(...)]` conversion.
+pub fn to_value<T: ToValue + ?Sized>(field: &T) -> Option<Value> {
+    field.to_value()
+}
+
+/// Parses a field from the value of its column with its `FromValue` conversion.
+///
+/// It is used by `derive(Table)` for fields without a `#[njord(...)]` conversion, and for enums.
+pub fn from_value<T: FromValue>(value: ValueRef<'_>) -> FromSqlResult<T> {
+    T::from_value(value)
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::{FromSqlError, Value, ValueRef};
+
+    use rusqlite::types::{FromSql, FromSqlResult, ToSql, ToSqlOutput};
+
+    impl From<rusqlite::types::Value> for Value {
+        fn from(value: rusqlite::types::Value) -> Self {
+            match value {
+                rusqlite::types::Value::Null => Value::Null,
+                rusqlite::types::Value::Integer(value) => Value::Integer(value),
+                rusqlite::types::Value::Real(value) => Value::Real(value),
+                rusqlite::types::Value::Text(value) => Value::Text(value),
+                rusqlite::types::Value::Blob(value) => Value::Blob(value),
+            }
+        }
+    }
+
+    impl From<Value> for rusqlite::types::Value {
+        fn from(value: Value) -> Self {
+            match value {
+                Value::Null => rusqlite::types::Value::Null,
+                Value::Integer(value) => rusqlite::types::Value::Integer(value),
+                Value::Real(value) => rusqlite::types::Value::Real(value),
+                Value::Text(value) => rusqlite::types::Value::Text(value),
+                Value::Blob(value) => rusqlite::types::Value::Blob(value),
+            }
+        }
+    }
+
+    impl<'a> From<rusqlite::types::ValueRef<'a>> for ValueRef<'a> {
+        fn from(value: rusqlite::types::ValueRef<'a>) -> Self {
+            match value {
+                rusqlite::types::ValueRef::Null => ValueRef::Null,
+                rusqlite::types::ValueRef::Integer(value) => ValueRef::Integer(value),
+                rusqlite::types::ValueRef::Real(value) => ValueRef::Real(value),
+                rusqlite::types::ValueRef::Text(value) => ValueRef::Text(value),
+                rusqlite::types::ValueRef::Blob(value) => ValueRef::Blob(value),
+            }
+        }
+    }
+
+    impl<'a> From<ValueRef<'a>> for rusqlite::types::ValueRef<'a> {
+        fn from(value: ValueRef<'a>) -> Self {
+            match value {
+                ValueRef::Null => rusqlite::types::ValueRef::Null,
+                ValueRef::Integer(value) => rusqlite::types::ValueRef::Integer(value),
+                ValueRef::Real(value) => rusqlite::types::ValueRef::Real(value),
+                ValueRef::Text(value) => rusqlite::types::ValueRef::Text(value),
+                ValueRef::Blob(value) => rusqlite::types::ValueRef::Blob(value),
+            }
+        }
+    }
+
+    impl From<rusqlite::types::ValueRef<'_>> for Value {
+        fn from(value: rusqlite::types::ValueRef<'_>) -> Self {
+            ValueRef::from(value).into()
+        }
+    }
+
+    impl From<FromSqlError> for rusqlite::types::FromSqlError {
+        fn from(error: FromSqlError) -> Self {
+            match error {
+                FromSqlError::InvalidType => rusqlite::types::FromSqlError::InvalidType,
+                FromSqlError::OutOfRange(value) => rusqlite::types::FromSqlError::OutOfRange(value),
+                FromSqlError::InvalidBlobSize {
+                    expected_size,
+                    blob_size,
+                } => rusqlite::types::FromSqlError::InvalidBlobSize {
+                    expected_size,
+                    blob_size,
+                },
+                FromSqlError::Other(error) => rusqlite::types::FromSqlError::Other(error),
+            }
+        }
+    }
+
+    impl ToSql for Value {
+        fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+            Ok(ToSqlOutput::Borrowed(self.as_value_ref().into()))
+        }
+    }
+
+    impl FromSql for Value {
+        fn column_result(value: rusqlite::types::ValueRef<'_>) -> FromSqlResult<Self> {
+            Ok(value.into())
+        }
+    }
+}
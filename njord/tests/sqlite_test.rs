@@ -316,3 +316,149 @@ mod common;
 //         Err(error) => panic!("Failed to select: {:?}", error),
 //     };
 // }
+
+#[cfg(feature = "chrono")]
+mod unix_timestamps {
+    use chrono::{DateTime, Utc};
+    use njord::condition::Condition;
+    use njord::sqlite;
+    #[allow(unused_imports)]
+    use njord::table::Table;
+    use njord_derive::Table;
+
+    #[derive(Table, Debug, Default)]
+    #[njord(table = "events")]
+    struct Event {
+        #[njord(primary_key, auto_increment)]
+        id: i64,
+        name: String,
+        #[njord(created_at, datetime = "unix")]
+        created_at: DateTime<Utc>,
+        #[njord(updated_at, datetime = "unix")]
+        updated_at: DateTime<Utc>,
+    }
+
+    #[test]
+    fn unix_timestamps_round_trip() {
+        let mut conn = sqlite::open_in_memory().unwrap();
+        sqlite::create_table::<Event>(&conn).unwrap();
+
+        let before = Utc::now().timestamp();
+        let mut event = Event {
+            name: "launch".to_string(),
+            ..Default::default()
+        };
+        sqlite::insert(&mut conn, &mut event).build().unwrap();
+        let after = Utc::now().timestamp();
+
+        // the timestamps are written back into the row
+        assert!((before..=after).contains(&event.created_at.timestamp()));
+        assert_eq!(event.updated_at, event.created_at);
+
+        sqlite::update(&mut conn)
+            .table(&Event::default())
+            .set("name".to_string(), "landing")
+            .where_clause(Condition::eq("id", event.id))
+            .build()
+            .unwrap();
+
+        // both columns are stored as seconds since the unix epoch
+        let stored: Vec<(String, i64)> = conn
+            .prepare("SELECT typeof(created_at), created_at FROM events UNION ALL SELECT typeof(updated_at), updated_at FROM events")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            stored[0],
+            ("integer".to_string(), event.created_at.timestamp())
+        );
+        assert_eq!(stored[1].0, "integer");
+        assert!((event.created_at.timestamp()..=Utc::now().timestamp()).contains(&stored[1].1));
+
+        let found: Event = sqlite::find(&conn, event.id).unwrap().unwrap();
+        assert_eq!(found.name, "landing");
+        assert_eq!(found.created_at, event.created_at);
+        assert_eq!(found.updated_at.timestamp(), stored[1].1);
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod rewritten_statements {
+    use njord::condition::Condition;
+    use njord::error::Result;
+    #[allow(unused_imports)]
+    use njord::table::Table;
+    use njord::value::Value;
+    use njord::{add_middleware, last_query_stats, sqlite, Middleware};
+    use njord_derive::Table;
+
+    #[derive(Table, Debug, Default)]
+    #[njord(table = "audits")]
+    struct Audit {
+        #[njord(primary_key, auto_increment)]
+        id: i64,
+        action: String,
+    }
+
+    /// Tags the statements on the audits table with a request id, and wraps their updates
+    /// in a `WITH`.
+    struct RequestId;
+
+    impl Middleware for RequestId {
+        fn before(&self, sql: &mut String, _params: &[Value]) -> Result<()> {
+            if !sql.contains("audits") {
+                return Ok(());
+            }
+
+            if sql.starts_with("UPDATE") {
+                sql.insert_str(0, "WITH request AS (SELECT 1) ");
+            }
+            sql.insert_str(0, "/* request-id: 42 */ ");
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn rewritten_statements_count_written_rows() {
+        add_middleware(RequestId);
+
+        let mut conn = sqlite::open_in_memory().unwrap();
+        sqlite::create_table::<Audit>(&conn).unwrap();
+
+        for action in ["login", "logout"] {
+            let mut audit = Audit {
+                action: action.to_string(),
+                ..Default::default()
+            };
+            sqlite::insert(&mut conn, &mut audit).build().unwrap();
+
+            let stats = last_query_stats().unwrap();
+            assert_eq!((stats.rows_read, stats.rows_written), (0, 1));
+        }
+
+        let changed = sqlite::update(&mut conn)
+            .table(&Audit::default())
+            .set("action".to_string(), "expired")
+            .where_clause(Condition::ne("action", "login"))
+            .build()
+            .unwrap();
+        assert_eq!(changed, 1);
+
+        let stats = last_query_stats().unwrap();
+        assert_eq!((stats.rows_read, stats.rows_written), (0, 1));
+
+        let audit = Audit::default();
+        let audits: Vec<Audit> =
+            sqlite::select(&conn, vec!["id".to_string(), "action".to_string()])
+                .from(&audit)
+                .build()
+                .unwrap();
+        assert_eq!(audits.len(), 2);
+
+        let stats = last_query_stats().unwrap();
+        assert_eq!((stats.rows_read, stats.rows_written), (2, 0));
+    }
+}
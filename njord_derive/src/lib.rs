@@ -1,11 +1,13 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use rusqlite::types::Value;
 
-use quote::quote;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
 use syn::{parse_macro_input, DeriveInput, FieldsNamed};
 
+mod migrations;
+
 /// Derives the `Table` trait for a struct.
 ///
 /// This procedural macro generates implementations of the `Table` trait for a struct.
@@ -26,32 +28,270 @@ use syn::{parse_macro_input, DeriveInput, FieldsNamed};
 ///
 /// This macro will generate implementations for `get_name`, `get_columns`, and `get_column_fields`
 /// based on the struct's field names and types.
-#[proc_macro_derive(Table)]
+///
+/// `Option<T>` fields map to nullable columns, with `None` stored as NULL, and other fields
+/// to NOT NULL columns.
+/// `Vec<u8>` and `[u8; N]` fields map to BLOB columns, bound as binary values.
+///
+/// The table is named after the struct, unless it is set with `#[njord(table = "app_users")]`
+/// on the struct. A table of an attached database is qualified with its alias with
+/// `#[njord(schema = "archive")]`, e.g. to join it with the tables of the main database.
+///
+/// A struct mapping a database view or a reporting projection is marked with `#[njord(readonly)]`.
+/// Only the mapping of selected rows is generated, so its fields do not need to convert into
+/// columns, and inserts, updates and deletes of it fail with an error.
+///
+/// Fields can be marked with `#[njord(...)]` attributes:
+///
+/// - `#[njord(primary_key)]` marks a primary key column. Marking several fields makes a
+///   composite primary key, in the order the fields are declared.
+/// - `#[njord(primary_key, auto_increment)]` marks an `INTEGER PRIMARY KEY` that is
+///   assigned by the database, so it is left out of inserts and written back afterwards.
+/// - `#[njord(default)]` marks a column filled by its database `DEFAULT`, so it is left out
+///   of inserts.
+/// - `#[njord(soft_delete)]` marks a nullable timestamp column set by deletes instead of
+///   removing the row. It is left out of inserts, and queries skip rows where it is set.
+/// - `#[njord(version)]` marks an integer column bumped by every update, used to detect
+///   that a saved row was updated by someone else since it was read.
+/// - `#[njord(sensitive)]` marks a column holding a sensitive value, e.g. a password or a
+///   token, whose values are masked in the parameters reported to the query logger.
+/// - `#[njord(created_at)]` and `#[njord(updated_at)]` mark timestamp columns set by the
+///   database when the row is inserted, and for `updated_at` also when it is updated.
+/// - `#[njord(unique)]` marks a column with a UNIQUE constraint.
+/// - `#[njord(index)]` and `#[njord(unique_index)]` declare an index and a UNIQUE index of the
+///   column, created by `sqlite::create_indexes`. Composite indexes are declared on the struct,
+///   e.g. `#[njord(index = "last_name, first_name")]` or `#[njord(unique_index = "...")]`.
+/// - `#[njord(check = "price > 0")]` adds a CHECK constraint to the column, and on the struct
+///   a CHECK constraint of the table, e.g. `#[njord(check = "starts_at < ends_at")]`.
+/// - `#[njord(references = "users(id)")]` marks a foreign key column referencing another table.
+/// - `#[njord(default_value = "0")]` sets the SQL `DEFAULT` of a column, and like `default`
+///   leaves it out of inserts.
+/// - `#[njord(column = "user_name")]` maps the field to a column with a different name.
+/// - `#[njord(enum = "text")]` and `#[njord(enum = "integer")]` store an enum deriving
+///   `ColumnEnum` as the name or the discriminant of its variant.
+/// - `#[njord(datetime = "unix")]` stores a chrono `NaiveDateTime`, `DateTime<Utc>` or `NaiveDate`
+///   as seconds since the unix epoch in an INTEGER column, instead of ISO-8601 TEXT.
+///   Date and time fields require the `chrono` feature of njord.
+/// - `#[njord(uuid = "text")]` stores a `uuid::Uuid` as hyphenated TEXT instead of a 16-byte BLOB.
+///   `#[njord(default = "uuid_v4")]` generates a random UUID on insert if the field is nil or `None`.
+///   UUID fields require the `uuid` feature of njord.
+/// - `#[njord(json)]` stores a `Serialize + DeserializeOwned` field as JSON in a TEXT column,
+///   with `None` stored as NULL. JSON fields require the `json` feature of njord.
+/// - `#[njord(decimal)]` stores a `rust_decimal::Decimal` losslessly as TEXT, and
+///   `#[njord(decimal = 2)]` as an INTEGER scaled by 10^2, e.g. cents. Decimal fields require
+///   the `decimal` feature of njord.
+/// - `#[njord(custom)]` stores a field of a custom type through its `ToColumn` and
+///   `FromColumn` implementations, e.g. a newtype like `EmailAddress`.
+/// - `#[njord(flatten)]` embeds a struct deriving `Table` and `Default`, e.g. an `Address`,
+///   whose columns are stored in the same table in place of the field.
+/// - `#[njord(foreign_key = "user_id")]` marks a `HasMany<Post>` field loading the posts whose
+///   `user_id` references the row on first access. It is not a column, and requires a single
+///   primary key. A `Lazy<User>` field stores the foreign key column itself, and loads the
+///   referenced user on first access.
+/// - `#[njord(skip)]` leaves a computed or transient field out of the table. It is neither
+///   inserted nor selected, and keeps its `Default` value in selected rows.
+///
+/// Relations to other tables are declared on the struct, and generate accessors querying them
+/// with the sqlite module of njord, unless its `sqlite` feature is disabled:
+///
+/// - `#[njord(belongs_to = "User", foreign_key = "user_id")]` on a `Post` generates
+///   `post.user(&conn)`, finding the `User` whose primary key is the `user_id` of the post.
+/// - `#[njord(has_many = "Post", foreign_key = "user_id")]` on a `User` generates
+///   `user.posts(&conn)`, finding the posts whose `user_id` is the primary key of the user.
+/// - `#[njord(many_to_many = "Tag", through = "post_tags")]` on a `Post` generates `post.tags(&conn)`,
+///   finding the tags related to the post through the `post_tags` join table, and
+///   `post.attach_tag(&mut conn, &tag)` and `post.detach_tag(&mut conn, &tag)` relating and unrelating them.
+///   The join table references the tables with `post_id` and `tag_id` columns, unless they are set
+///   with `foreign_key = "..."` and `related_key = "..."`.
+/// - `method = "..."` names the accessor, e.g. `method = "categories"` for `has_many = "Category"`.
+///
+/// It also generates a `MyTableColumn` enum with one variant per field (e.g. `MyTableColumn::InStock`),
+/// which converts into the column name so typos are caught at compile time.
+#[proc_macro_derive(Table, attributes(njord))]
 pub fn table_derive(input: TokenStream) -> TokenStream {
-    let DeriveInput { ident, data, .. } = parse_macro_input!(input);
+    let DeriveInput {
+        ident,
+        data,
+        vis,
+        attrs,
+        ..
+    } = parse_macro_input!(input);
+
+    let table_attributes = match parse_table_attributes(&ident, &attrs) {
+        Ok(table_attributes) => table_attributes,
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    // the name of the table, named after the struct unless renamed
+    let unqualified_table_name = table_attributes.table.unwrap_or_else(|| ident.to_string());
+    let table_name = match &table_attributes.schema {
+        Some(schema) => format!("{}.{}", schema, unqualified_table_name),
+        None => unqualified_table_name.clone(),
+    };
+    let readonly = table_attributes.readonly;
 
     let mut name_stream = TokenStream2::default();
     let mut columns_stream = TokenStream2::default();
     let mut column_fields_stream = TokenStream2::default();
     let mut column_values_stream = TokenStream2::default();
     let mut set_column_values_stream = TokenStream2::default();
+    let mut column_enum_stream = TokenStream2::default();
+    let mut primary_key_stream = TokenStream2::default();
+    let mut auto_increment_stream = TokenStream2::default();
+    let mut default_columns_stream = TokenStream2::default();
+    let mut soft_delete_stream = TokenStream2::default();
+    let mut version_stream = TokenStream2::default();
+    let mut sensitive_columns_stream = TokenStream2::default();
+    let mut timestamps_stream = TokenStream2::default();
+    let mut generate_values_stream = TokenStream2::default();
+    let mut schema_stream = TokenStream2::default();
+    let mut relations_stream = TokenStream2::default();
+    let mut indexes_stream = TokenStream2::default();
+    let mut accessors_stream = TokenStream2::default();
 
     if let syn::Data::Struct(s) = data {
         if let syn::Fields::Named(FieldsNamed { named, .. }) = s.fields {
+            let field_attributes = match named
+                .iter()
+                .map(parse_field_attributes)
+                .collect::<syn::Result<Vec<FieldAttributes>>>()
+            {
+                Ok(field_attributes) => field_attributes,
+                Err(error) => return error.to_compile_error().into(),
+            };
+
+            // `HasMany` fields are not columns, but are keyed by the primary key of the row
+            let has_many_fields: Vec<(&Option<syn::Ident>, String)> = named
+                .iter()
+                .zip(&field_attributes)
+                .filter_map(|(f, attributes)| {
+                    attributes
+                        .foreign_key
+                        .as_ref()
+                        .map(|foreign_key| (&f.ident, foreign_key.clone()))
+                })
+                .collect();
+
+            // skipped fields are not persisted and keep their default value in selected rows
+            let (named, field_attributes): (Vec<&syn::Field>, Vec<FieldAttributes>) = named
+                .iter()
+                .zip(field_attributes)
+                .filter(|(_, attributes)| !attributes.skip && attributes.foreign_key.is_none())
+                .unzip();
+
+            // flattened fields embed the columns of another table, which maps them itself
+            let fields_in_order: Vec<(&syn::Field, bool)> = named
+                .iter()
+                .zip(&field_attributes)
+                .map(|(f, attributes)| (*f, attributes.flatten))
+                .collect();
+            let flattened_fields: Vec<&Option<syn::Ident>> = fields_in_order
+                .iter()
+                .filter(|(_, flatten)| *flatten)
+                .map(|(f, _)| &f.ident)
+                .collect();
+            let (named, field_attributes): (Vec<&syn::Field>, Vec<FieldAttributes>) = named
+                .iter()
+                .zip(field_attributes)
+                .filter(|(_, attributes)| !attributes.flatten)
+                .unzip();
+
+            // the column of each field, named after the field without its `r#` prefix unless renamed
+            let column_names: Vec<String> = named
+                .iter()
+                .zip(&field_attributes)
+                .map(|(f, attributes)| match &attributes.column {
+                    Some(column) => column.clone(),
+                    None => f
+                        .ident
+                        .as_ref()
+                        .map(|i| i.unraw().to_string())
+                        .unwrap_or_default(),
+                })
+                .collect();
+
             let field_names = named.iter().map(|f| &f.ident);
-            let field_names_clone = field_names.clone();
-            let field_names_clone2 = field_names.clone();
-            let field_types = named.iter().map(|f| &f.ty);
-            let field_types_clone = named.iter().map(|f| &f.ty);
-            let field_values = named.iter().map(|f| {
-                let field_name = &f.ident;
-                quote! { self.#field_name.to_string() }
+            let field_readers = named.iter().zip(&field_attributes).map(|(f, attributes)| {
+                let field_type = &f.ty;
+                match &attributes.conversion {
+                    Some(conversion) => conversion.reader(),
+                    None => quote! { njord::value::from_value::<#field_type>(value_ref) },
+                }
             });
+            let column_types: Vec<TokenStream2> = named.iter().zip(&field_attributes).zip(&column_names).map(|((f, attributes), column_name)| {
+                let field_type = type_name(&f.ty);
+                match &attributes.conversion {
+                    Some(conversion) => conversion.column_type(&f.ty),
+                    // lazy relations store the foreign key, e.g. an integer `user_id`
+                    None if field_type.starts_with("Lazy<") => quote! { "INTEGER" },
+                    None => quote! {
+                        match #field_type {
+                            "i64" | "i32" | "i16" | "i8" | "u64" | "u32" | "u16" | "u8" | "usize" => "INTEGER",
+                            "String" => "TEXT",
+                            "f64" | "f32" => "REAL",
+                            "Vec<u8>" | "[u8; N]" => "BLOB",
+                            "Option<i64>" | "Option<i32>" | "Option<i16>" | "Option<i8>" | "Option<u64>" | "Option<u32>" | "Option<u16>" | "Option<u8>" | "Option<usize>" => "INTEGER",
+                            "Option<String>" => "TEXT",
+                            "Option<f64>" | "Option<f32>" => "REAL",
+                            "Option<Vec<u8>>" | "Option<[u8; N]>" => "BLOB",
+                            // booleans are bound as 0 and 1, which a TEXT column would store as text
+                            "bool" | "Option<bool>" => "INTEGER",
+                            "NaiveDateTime" | "DateTime<Utc>" | "NaiveDate" => "TEXT",
+                            "Option<NaiveDateTime>" | "Option<DateTime<Utc>>" | "Option<NaiveDate>" => "TEXT",
+                            "Uuid" | "Option<Uuid>" => "BLOB",
+                            _ => {
+                                eprintln!("Warning: Unknown data type for column '{}'", #column_name);
+                                "UNKNOWN_TYPE"
+                            }
+                        }
+                    },
+                }
+            }).collect();
+            let column_schemas: Vec<TokenStream2> = named
+                .iter()
+                .zip(&field_attributes)
+                .zip(column_names.iter().zip(&column_types))
+                .map(|((f, attributes), (column_name, column_type))| {
+                    let nullable = type_name(&f.ty).starts_with("Option<");
+                    let primary_key = attributes.primary_key;
+                    let auto_increment = attributes.auto_increment;
+                    let unique = attributes.unique;
+                    let default_value = optional_string(&attributes.default_value);
+                    let check = optional_string(&attributes.check);
+                    let references = optional_string(&attributes.references);
+
+                    quote! {
+                        njord::table::ColumnSchema {
+                            name: #column_name.to_string(),
+                            column_type: #column_type.to_string(),
+                            nullable: #nullable,
+                            primary_key: #primary_key,
+                            auto_increment: #auto_increment,
+                            unique: #unique,
+                            default_value: #default_value,
+                            check: #check,
+                            references: #references,
+                        }
+                    }
+                })
+                .collect();
+            let field_values: Vec<TokenStream2> = named.iter().zip(&field_attributes).zip(&column_names).map(|((f, attributes), column_name)| {
+                let field_name = &f.ident;
+                match &attributes.conversion {
+                    Some(conversion) => conversion.value(field_name, column_name),
+                    None => quote! {
+                        njord::value::to_value(&self.#field_name).ok_or_else(|| njord::Error::Mapping {
+                            column: #column_name.to_string(),
+                            source: njord::value::FromSqlError::Other("the value does not fit into its column".into()),
+                        })?
+                    },
+                }
+            }).collect();
 
             // implement the get_name() function
             name_stream.extend::<TokenStream2>(quote! {
                 fn get_name(&self) -> &str {
-                    stringify!(#ident)
+                    #table_name
                 }
             });
 
@@ -60,60 +300,519 @@ pub fn table_derive(input: TokenStream) -> TokenStream {
                 fn get_columns(&self) -> std::collections::HashMap<String, String> {
                     let mut columns = std::collections::HashMap::new();
                     #(
-                        let column_type = match stringify!(#field_types) {
-                            "i64" | "i32" | "i16" | "i8" | "u64" | "u32" | "u16" | "u8" | "usize" => "INTEGER",
-                            "String" => "TEXT",
-                            "f64" | "f32" => "REAL",
-                            "Vec<u8>" => "BLOB",
-                            "Option<i64>" | "Option<i32>" | "Option<i16>" | "Option<i8>" | "Option<u64>" | "Option<u32>" | "Option<u16>" | "Option<u8>" | "Option<usize>" => "INTEGER",
-                            "Option<String>" => "TEXT",
-                            "Option<f64>" | "Option<f32>" => "REAL",
-                            "Option<Vec<u8>>" => "BLOB",
-                            "bool" => "TEXT",
-                            _ => {
-                                eprintln!("Warning: Unknown data type for column '{}'", stringify!(#field_names));
-                                "UNKNOWN_TYPE"
-                            }
-                        };
+                        let column_type = #column_types;
                         columns.insert(
-                            stringify!(#field_names).to_string(),
+                            #column_names.to_string(),
                             column_type.to_string(),
                         );
                     )*
+                    #(columns.extend(self.#flattened_fields.get_columns());)*
                     columns
                 }
             });
 
+            // the columns of flattened fields are listed in place of the field
+            let mut own_columns = column_names.iter().zip(&field_values).zip(&column_schemas);
+            let column_parts: Vec<(TokenStream2, TokenStream2, TokenStream2)> = fields_in_order
+                .iter()
+                .filter_map(|(f, flatten)| {
+                    let field_name = &f.ident;
+                    if *flatten {
+                        return Some((
+                            quote! { fields.extend(self.#field_name.get_column_fields()); },
+                            quote! { values.extend(self.#field_name.get_column_values()?); },
+                            quote! { schema.extend(self.#field_name.get_schema()); },
+                        ));
+                    }
+
+                    own_columns
+                        .next()
+                        .map(|((column_name, value), column_schema)| {
+                            (
+                                quote! { fields.push(#column_name.to_string()); },
+                                quote! { values.push(#value); },
+                                quote! { schema.push(#column_schema); },
+                            )
+                        })
+                })
+                .collect();
+            let push_column_fields = column_parts.iter().map(|parts| &parts.0);
+            let push_column_values = column_parts.iter().map(|parts| &parts.1);
+            let push_column_schemas = column_parts.iter().map(|parts| &parts.2);
+
             // implement the get_column_fields() function
             column_fields_stream.extend(quote! {
                 fn get_column_fields(&self) -> Vec<String> {
-                    vec![#(stringify!(#field_names_clone).to_string()),*]
+                    let mut fields = Vec::new();
+                    #(#push_column_fields)*
+                    fields
+                }
+            });
+
+            // implement the get_schema() function
+            schema_stream.extend(quote! {
+                fn get_schema(&self) -> Vec<njord::table::ColumnSchema> {
+                    let mut schema = Vec::new();
+                    #(#push_column_schemas)*
+                    schema
                 }
             });
 
+            // setting the primary key also keys the `HasMany` fields of the row
+            let has_many_idents = has_many_fields.iter().map(|(field_name, _)| field_name);
+            let has_many_foreign_keys = has_many_fields.iter().map(|(_, foreign_key)| foreign_key);
+            let has_many_keys = quote! {
+                #(njord::sqlite::HasMany::set_key(&mut self.#has_many_idents, #has_many_foreign_keys, value.clone());)*
+            };
+            let primary_key_count = field_attributes
+                .iter()
+                .filter(|attributes| attributes.primary_key)
+                .count();
+            if !has_many_fields.is_empty() && primary_key_count != 1 {
+                return syn::Error::new_spanned(
+                    &ident,
+                    "HasMany fields require a single primary key column",
+                )
+                .to_compile_error()
+                .into();
+            }
+            let set_keys = field_attributes
+                .iter()
+                .map(|attributes| match attributes.primary_key {
+                    true => has_many_keys.clone(),
+                    false => TokenStream2::default(),
+                });
+
             set_column_values_stream.extend(quote! {
-                fn set_column_value(&mut self, column: &str, value: Value) {
+                fn set_column_value(&mut self, column: &str, value: njord::Value) -> njord::Result<()> {
                     match column {
                         #(
-                            stringify!(#field_names_clone2) => {
-                                if let Ok(val) = value.clone().into::<#field_types_clone>() {
-                                    self.#field_names_clone2 = val;
-                                } else {
-                                    eprintln!("Error: Failed to convert value for column '{}'", column);
-                                }
+                            #column_names => {
+                                let value_ref = value.as_value_ref();
+                                self.#field_names = #field_readers.map_err(|source| njord::Error::Mapping {
+                                    column: column.to_string(),
+                                    source,
+                                })?;
+                                #set_keys
                             }
                         )*
-                        _ => eprintln!("Warning: Unknown column '{}'", column),
+                        _ => {
+                            #(
+                                if self.#flattened_fields.get_column_fields().iter().any(|c| c == column) {
+                                    return self.#flattened_fields.set_column_value(column, value);
+                                }
+                            )*
+                            eprintln!("Warning: Unknown column '{}'", column)
+                        }
                     }
+
+                    Ok(())
                 }
             });
 
-            // implement the get_column_values() function
-            column_values_stream.extend(quote! {
-                fn get_column_values(&self) -> Vec<String> {
-                    vec![#(#field_values),*]
+            // generate the column enum, mapping each variant to its column name
+            let column_enum = format_ident!("{}Column", ident);
+            let column_variants: Vec<syn::Ident> = named
+                .iter()
+                .filter_map(|f| f.ident.as_ref())
+                .map(|f| format_ident!("{}", to_camel_case(&f.to_string())))
+                .collect();
+            column_enum_stream.extend(quote! {
+                #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+                #vis enum #column_enum {
+                    #(#column_variants),*
+                }
+
+                impl #column_enum {
+                    /// Get the name of the column.
+                    pub fn as_str(&self) -> &'static str {
+                        match self {
+                            #(#column_enum::#column_variants => #column_names),*
+                        }
+                    }
+
+                    /// Get the name of the column qualified with the table name, e.g. for joins.
+                    pub fn qualified(&self) -> String {
+                        format!("{}.{}", #table_name, self.as_str())
+                    }
+                }
+
+                impl std::fmt::Display for #column_enum {
+                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        f.write_str(self.as_str())
+                    }
+                }
+
+                impl From<#column_enum> for String {
+                    fn from(column: #column_enum) -> Self {
+                        column.as_str().to_string()
+                    }
                 }
             });
+
+            // implement the get_primary_key_columns() function
+            let primary_key_columns = column_names
+                .iter()
+                .zip(&field_attributes)
+                .filter(|(_, attributes)| attributes.primary_key)
+                .map(|(column, _)| column)
+                .collect::<Vec<_>>();
+            let primary_key_values = field_values
+                .iter()
+                .zip(&field_attributes)
+                .filter(|(_, attributes)| attributes.primary_key)
+                .map(|(value, _)| value);
+
+            if !primary_key_columns.is_empty() {
+                primary_key_stream.extend(quote! {
+                    fn get_primary_key_columns(&self) -> Vec<String> {
+                        vec![#(#primary_key_columns.to_string()),*]
+                    }
+                });
+            }
+
+            if !primary_key_columns.is_empty() && !readonly {
+                primary_key_stream.extend(quote! {
+                    fn get_primary_key(&self) -> njord::Result<Vec<(String, njord::Value)>> {
+                        Ok(vec![#((#primary_key_columns.to_string(), #primary_key_values)),*])
+                    }
+                });
+            }
+
+            // generate the accessors of the relations, which query the related table
+            let single_primary_key_value = match primary_key_columns.len() {
+                1 => field_values
+                    .iter()
+                    .zip(&field_attributes)
+                    .find(|(_, attributes)| attributes.primary_key)
+                    .map(|(value, _)| value),
+                _ => None,
+            };
+            let mut accessors = Vec::new();
+            let mut relations = Vec::new();
+            for relation in &table_attributes.relations {
+                let method = relation.method();
+                let target = &relation.target;
+                let foreign_key = &relation.foreign_key;
+
+                let accessor = match &relation.kind {
+                    RelationKind::BelongsTo => {
+                        let foreign_key_value =
+                            match column_names.iter().position(|column| column == foreign_key) {
+                                Some(index) => &field_values[index],
+                                None => {
+                                    let message = format!(
+                                        "foreign key `{}` is not a column of {}",
+                                        foreign_key, ident
+                                    );
+                                    return syn::Error::new_spanned(target, message)
+                                        .to_compile_error()
+                                        .into();
+                                }
+                            };
+                        let doc = format!(
+                            "Finds the row referenced by the `{}` column, or `None` if it is NULL.",
+                            foreign_key
+                        );
+
+                        quote! {
+                            #[doc = #doc]
+                            #vis fn #method(&self, conn: &njord::sqlite::Connection) -> njord::sqlite::Result<Option<#target>> {
+                                njord::sqlite::relation::belongs_to::<#target>(conn, #foreign_key_value)
+                            }
+                        }
+                    }
+                    RelationKind::HasMany => {
+                        let primary_key_value = match single_primary_key_value {
+                            Some(value) => value,
+                            None => {
+                                return syn::Error::new_spanned(
+                                    target,
+                                    "has_many requires a single primary key column",
+                                )
+                                .to_compile_error()
+                                .into();
+                            }
+                        };
+                        let doc = format!(
+                            "Finds the rows whose `{}` column references this row.",
+                            foreign_key
+                        );
+
+                        quote! {
+                            #[doc = #doc]
+                            #vis fn #method(&self, conn: &njord::sqlite::Connection) -> njord::sqlite::Result<Vec<#target>> {
+                                njord::sqlite::relation::has_many::<#target>(conn, #foreign_key, #primary_key_value)
+                            }
+                        }
+                    }
+                    RelationKind::ManyToMany {
+                        through,
+                        related_key,
+                    } => {
+                        let primary_key_value = match single_primary_key_value {
+                            Some(value) => value,
+                            None => {
+                                return syn::Error::new_spanned(
+                                    target,
+                                    "many_to_many requires a single primary key column",
+                                )
+                                .to_compile_error()
+                                .into();
+                            }
+                        };
+                        let attach = format_ident!("attach_{}", relation.target_name());
+                        let detach = format_ident!("detach_{}", relation.target_name());
+                        let doc =
+                            format!("Finds the rows related to this row through `{}`.", through);
+                        let attach_doc = format!(
+                            "Relates the row to this row by inserting it into `{}`, unless it is already related.",
+                            through
+                        );
+                        let detach_doc = format!(
+                            "Removes the relation of the row to this row from `{}`.",
+                            through
+                        );
+
+                        quote! {
+                            #[doc = #doc]
+                            #vis fn #method(&self, conn: &njord::sqlite::Connection) -> njord::sqlite::Result<Vec<#target>> {
+                                njord::sqlite::relation::many_to_many::<#target>(conn, #through, #foreign_key, #related_key, #primary_key_value)
+                            }
+
+                            #[doc = #attach_doc]
+                            #vis fn #attach(&self, conn: &mut njord::sqlite::Connection, related: &#target) -> njord::sqlite::Result<usize> {
+                                njord::sqlite::relation::attach(conn, #through, #foreign_key, #related_key, #primary_key_value, related)
+                            }
+
+                            #[doc = #detach_doc]
+                            #vis fn #detach(&self, conn: &mut njord::sqlite::Connection, related: &#target) -> njord::sqlite::Result<usize> {
+                                njord::sqlite::relation::detach(conn, #through, #foreign_key, #related_key, #primary_key_value, related)
+                            }
+                        }
+                    }
+                };
+                accessors.push(accessor);
+
+                // join tables have no single foreign key, so they are not eager loaded
+                let kind = match relation.kind {
+                    RelationKind::BelongsTo => quote! { njord::table::RelationKind::BelongsTo },
+                    RelationKind::HasMany => quote! { njord::table::RelationKind::HasMany },
+                    RelationKind::ManyToMany { .. } => continue,
+                };
+                relations.push(quote! {
+                    njord::table::Relation {
+                        kind: #kind,
+                        table: <#target as Default>::default().get_name().to_string(),
+                        foreign_key: #foreign_key.to_string(),
+                    }
+                });
+            }
+
+            if !relations.is_empty() {
+                relations_stream.extend(quote! {
+                    fn get_relations(&self) -> Vec<njord::table::Relation> {
+                        vec![#(#relations),*]
+                    }
+                });
+            }
+
+            // the accessors run on the sqlite module, which njord only has with its sqlite feature
+            if !accessors.is_empty() && cfg!(feature = "sqlite") {
+                accessors_stream.extend(quote! {
+                    impl #ident {
+                        #(#accessors)*
+                    }
+                });
+            }
+
+            // implement the get_indexes() function, with the indexes of single fields first
+            let field_indexes =
+                column_names
+                    .iter()
+                    .zip(&field_attributes)
+                    .flat_map(|(column, attributes)| {
+                        let index = attributes.index.then(|| (vec![column.clone()], false));
+                        let unique_index = attributes
+                            .unique_index
+                            .then(|| (vec![column.clone()], true));
+                        index.into_iter().chain(unique_index)
+                    });
+            let indexes: Vec<TokenStream2> = field_indexes
+                .chain(table_attributes.indexes.iter().cloned())
+                .map(|(columns, unique)| {
+                    let name = format!("{}_{}_index", unqualified_table_name, columns.join("_"));
+                    quote! {
+                        njord::table::IndexSchema {
+                            name: #name.to_string(),
+                            columns: vec![#(#columns.to_string()),*],
+                            unique: #unique,
+                        }
+                    }
+                })
+                .collect();
+
+            // implement the get_checks() function
+            let checks = &table_attributes.checks;
+            if !checks.is_empty() {
+                indexes_stream.extend(quote! {
+                    fn get_checks(&self) -> Vec<String> {
+                        vec![#(#checks.to_string()),*]
+                    }
+                });
+            }
+
+            if !indexes.is_empty() {
+                indexes_stream.extend(quote! {
+                    fn get_indexes(&self) -> Vec<njord::table::IndexSchema> {
+                        vec![#(#indexes),*]
+                    }
+                });
+            }
+
+            // implement the get_auto_increment_column() function
+            let auto_increment_column = column_names
+                .iter()
+                .zip(&field_attributes)
+                .find(|(_, attributes)| attributes.auto_increment)
+                .map(|(column, _)| column);
+
+            if let Some(column) = auto_increment_column {
+                auto_increment_stream.extend(quote! {
+                    fn get_auto_increment_column(&self) -> Option<String> {
+                        Some(#column.to_string())
+                    }
+                });
+            }
+
+            // implement the get_soft_delete_column() function
+            let soft_delete_column = column_names
+                .iter()
+                .zip(&field_attributes)
+                .find(|(_, attributes)| attributes.soft_delete)
+                .map(|(column, _)| column);
+
+            if let Some(column) = soft_delete_column {
+                soft_delete_stream.extend(quote! {
+                    fn get_soft_delete_column(&self) -> Option<String> {
+                        Some(#column.to_string())
+                    }
+                });
+            }
+
+            // implement the get_version_column() function
+            let version_column = column_names
+                .iter()
+                .zip(&field_attributes)
+                .find(|(_, attributes)| attributes.version)
+                .map(|(column, _)| column);
+
+            if let Some(column) = version_column {
+                version_stream.extend(quote! {
+                    fn get_version_column(&self) -> Option<String> {
+                        Some(#column.to_string())
+                    }
+                });
+            }
+
+            // implement the get_created_at_column() and get_updated_at_column() functions
+            let created_at_column = column_names
+                .iter()
+                .zip(&field_attributes)
+                .find(|(_, attributes)| attributes.created_at)
+                .map(|(column, _)| column);
+
+            if let Some(column) = created_at_column {
+                timestamps_stream.extend(quote! {
+                    fn get_created_at_column(&self) -> Option<String> {
+                        Some(#column.to_string())
+                    }
+                });
+            }
+
+            let updated_at_column = column_names
+                .iter()
+                .zip(&field_attributes)
+                .find(|(_, attributes)| attributes.updated_at)
+                .map(|(column, _)| column);
+
+            if let Some(column) = updated_at_column {
+                timestamps_stream.extend(quote! {
+                    fn get_updated_at_column(&self) -> Option<String> {
+                        Some(#column.to_string())
+                    }
+                });
+            }
+
+            // implement the get_default_columns() function
+            let default_columns = column_names
+                .iter()
+                .zip(&field_attributes)
+                .filter(|(_, attributes)| attributes.default || attributes.soft_delete)
+                .map(|(column, _)| column)
+                .collect::<Vec<_>>();
+
+            if !default_columns.is_empty() {
+                default_columns_stream.extend(quote! {
+                    fn get_default_columns(&self) -> Vec<String> {
+                        vec![#(#default_columns.to_string()),*]
+                    }
+                });
+            }
+
+            // implement the get_sensitive_columns() function
+            let sensitive_columns = column_names
+                .iter()
+                .zip(&field_attributes)
+                .filter(|(_, attributes)| attributes.sensitive)
+                .map(|(column, _)| column)
+                .collect::<Vec<_>>();
+
+            if !sensitive_columns.is_empty() {
+                sensitive_columns_stream.extend(quote! {
+                    fn get_sensitive_columns(&self) -> Vec<String> {
+                        vec![#(#sensitive_columns.to_string()),*]
+                    }
+                });
+            }
+
+            // implement the generate_values() function
+            let generated_uuid_fields = named
+                .iter()
+                .zip(&field_attributes)
+                .filter(|(_, attributes)| attributes.generate_uuid_v4)
+                .map(|(f, _)| &f.ident)
+                .collect::<Vec<_>>();
+
+            if !generated_uuid_fields.is_empty() {
+                generate_values_stream.extend(quote! {
+                    fn generate_values(&mut self) {
+                        #(njord::column::UuidColumn::generate_v4(&mut self.#generated_uuid_fields);)*
+                    }
+                });
+            }
+
+            // implement the get_column_values() function, which read-only tables leave empty
+            // so their fields only need to be converted from columns
+            if readonly {
+                column_values_stream.extend(quote! {
+                    fn get_column_values(&self) -> njord::Result<Vec<njord::Value>> {
+                        Ok(Vec::new())
+                    }
+
+                    fn is_readonly(&self) -> bool {
+                        true
+                    }
+                });
+            } else {
+                column_values_stream.extend(quote! {
+                    fn get_column_values(&self) -> njord::Result<Vec<njord::Value>> {
+                        let mut values = Vec::new();
+                        #(#push_column_values)*
+                        Ok(values)
+                    }
+                });
+            }
         }
     };
 
@@ -124,8 +823,773 @@ pub fn table_derive(input: TokenStream) -> TokenStream {
             #column_fields_stream
             #column_values_stream
             #set_column_values_stream
+            #primary_key_stream
+            #auto_increment_stream
+            #default_columns_stream
+            #soft_delete_stream
+            #version_stream
+            #sensitive_columns_stream
+            #timestamps_stream
+            #generate_values_stream
+            #schema_stream
+            #indexes_stream
+            #relations_stream
         }
+
+        #column_enum_stream
+        #accessors_stream
     };
 
     output.into()
 }
+
+/// Embeds the SQL files of a migrations directory into the binary, as a `njord::migrations::Migrator`.
+///
+/// The directory is relative to the manifest of the crate, `migrations` by default. Each migration
+/// is named after its version and name, e.g. `0001_create_users.up.sql` with an optional
+/// `0001_create_users.down.sql` reverting it. A migration without a down step can also be named
+/// `0001_create_users.sql`.
+///
+/// The files are included with `include_str!`, so editing them rebuilds the crate, but adding
+/// a new file requires touching the source using the macro.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use njord_derive::embed_migrations;
+///
+/// let migrator = embed_migrations!("migrations");
+/// migrator.migrate(&mut conn)?;
+/// ```
+#[proc_macro]
+pub fn embed_migrations(input: TokenStream) -> TokenStream {
+    let directory = if input.is_empty() {
+        syn::LitStr::new("migrations", proc_macro2::Span::call_site())
+    } else {
+        parse_macro_input!(input as syn::LitStr)
+    };
+
+    match migrations::embed_migrations(&directory.value(), directory.span()) {
+        Ok(output) => output.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Derives the `ColumnEnum` trait for a fieldless enum.
+///
+/// The enum can then be a field of a struct deriving `Table`, stored as the name of its variant
+/// in a TEXT column with `#[njord(enum = "text")]`, or as its discriminant in an INTEGER column
+/// with `#[njord(enum = "integer")]`. Selected rows accept either form.
+///
+/// # Example
+///
+/// ```rust
+/// use njord_derive::ColumnEnum;
+/// use njord::column::ColumnEnum;
+/// #[derive(ColumnEnum)]
+/// enum Status {
+///     Active,
+///     Archived = 10,
+/// }
+/// ```
+#[proc_macro_derive(ColumnEnum)]
+pub fn column_enum_derive(input: TokenStream) -> TokenStream {
+    let DeriveInput { ident, data, .. } = parse_macro_input!(input);
+
+    let variants = match data {
+        syn::Data::Enum(e) => e.variants,
+        _ => {
+            return syn::Error::new_spanned(ident, "ColumnEnum can only be derived for enums")
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    if let Some(variant) = variants
+        .iter()
+        .find(|v| !matches!(v.fields, syn::Fields::Unit))
+    {
+        return syn::Error::new_spanned(variant, "ColumnEnum requires a fieldless enum")
+            .to_compile_error()
+            .into();
+    }
+
+    let variant_idents: Vec<&syn::Ident> = variants.iter().map(|v| &v.ident).collect();
+    let variant_names: Vec<String> = variant_idents.iter().map(|v| v.to_string()).collect();
+
+    let output = quote! {
+        impl njord::column::ColumnEnum for #ident {
+            fn variant_name(&self) -> &'static str {
+                match self {
+                    #(#ident::#variant_idents => #variant_names),*
+                }
+            }
+
+            fn from_variant_name(name: &str) -> Option<Self> {
+                match name {
+                    #(#variant_names => Some(#ident::#variant_idents),)*
+                    _ => None,
+                }
+            }
+
+            fn discriminant(&self) -> i64 {
+                match self {
+                    #(#ident::#variant_idents => #ident::#variant_idents as i64),*
+                }
+            }
+
+            fn from_discriminant(discriminant: i64) -> Option<Self> {
+                #(
+                    if discriminant == #ident::#variant_idents as i64 {
+                        return Some(#ident::#variant_idents);
+                    }
+                )*
+                None
+            }
+        }
+
+        impl njord::value::ToValue for #ident {
+            fn to_value(&self) -> Option<njord::Value> {
+                Some(njord::column::ColumnEnum::variant_name(self).into())
+            }
+        }
+
+        impl njord::value::FromValue for #ident {
+            fn from_value(value: njord::value::ValueRef<'_>) -> njord::value::FromSqlResult<Self> {
+                let variant = match value {
+                    njord::value::ValueRef::Text(_) => {
+                        <#ident as njord::column::ColumnEnum>::from_variant_name(value.as_str()?)
+                    }
+                    njord::value::ValueRef::Integer(discriminant) => {
+                        <#ident as njord::column::ColumnEnum>::from_discriminant(discriminant)
+                    }
+                    _ => return Err(njord::value::FromSqlError::InvalidType),
+                };
+
+                variant.ok_or(njord::value::FromSqlError::InvalidType)
+            }
+        }
+    };
+
+    output.into()
+}
+
+/// The `#[njord(...)]` attributes of a struct.
+#[derive(Default)]
+struct TableAttributes {
+    table: Option<String>,
+    schema: Option<String>,
+    readonly: bool,
+    relations: Vec<Relation>,
+    /// The composite indexes, with their columns and whether they are unique.
+    indexes: Vec<(Vec<String>, bool)>,
+    checks: Vec<String>,
+}
+
+/// A relation to another table, set by `#[njord(belongs_to = "User", foreign_key = "user_id")]`,
+/// `#[njord(has_many = "Post", foreign_key = "user_id")]` or
+/// `#[njord(many_to_many = "Tag", through = "post_tags")]`.
+struct Relation {
+    kind: RelationKind,
+    /// The struct of the related table.
+    target: syn::Path,
+    /// The foreign key column, of this table for `belongs_to`, of the related table for `has_many`
+    /// and of the join table referencing this table for `many_to_many`.
+    foreign_key: String,
+    /// The name of the accessor, set with `method = "..."`.
+    method: Option<String>,
+}
+
+enum RelationKind {
+    BelongsTo,
+    HasMany,
+    /// Rows related through a join table, whose `related_key` column references the related table.
+    ManyToMany {
+        through: String,
+        related_key: String,
+    },
+}
+
+impl Relation {
+    /// Get the name of the accessor, e.g. `user` for `belongs_to = "User"` and `posts` for
+    /// `has_many = "Post"`, unless it is set with `method = "..."`.
+    fn method(&self) -> syn::Ident {
+        if let Some(method) = &self.method {
+            return format_ident!("{}", method);
+        }
+
+        match self.kind {
+            RelationKind::BelongsTo => format_ident!("{}", self.target_name()),
+            RelationKind::HasMany | RelationKind::ManyToMany { .. } => {
+                format_ident!("{}s", self.target_name())
+            }
+        }
+    }
+
+    /// Get the snake_case name of the related struct, e.g. `tag` for `Tag`.
+    fn target_name(&self) -> String {
+        target_name(&self.target)
+    }
+}
+
+fn target_name(target: &syn::Path) -> String {
+    target
+        .segments
+        .last()
+        .map(|segment| to_snake_case(&segment.ident.to_string()))
+        .unwrap_or_default()
+}
+
+/// Parses the `#[njord(...)]` attributes of a struct.
+fn parse_table_attributes(
+    ident: &syn::Ident,
+    attrs: &[syn::Attribute],
+) -> syn::Result<TableAttributes> {
+    let mut attributes = TableAttributes::default();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("njord")) {
+        // each attribute declares at most one relation, with its own foreign key
+        let mut relation: Option<(String, syn::Path)> = None;
+        let mut foreign_key: Option<String> = None;
+        let mut method: Option<String> = None;
+        let mut through: Option<String> = None;
+        let mut related_key: Option<String> = None;
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                let table: syn::LitStr = meta.value()?.parse()?;
+                attributes.table = Some(table.value());
+                Ok(())
+            } else if meta.path.is_ident("schema") {
+                let schema: syn::LitStr = meta.value()?.parse()?;
+                attributes.schema = Some(schema.value());
+                Ok(())
+            } else if meta.path.is_ident("readonly") {
+                attributes.readonly = true;
+                Ok(())
+            } else if meta.path.is_ident("check") {
+                let check: syn::LitStr = meta.value()?.parse()?;
+                attributes.checks.push(check.value());
+                Ok(())
+            } else if meta.path.is_ident("index") || meta.path.is_ident("unique_index") {
+                let columns: syn::LitStr = meta.value()?.parse()?;
+                let columns: Vec<String> = columns
+                    .value()
+                    .split(',')
+                    .map(|column| column.trim().to_string())
+                    .filter(|column| !column.is_empty())
+                    .collect();
+                if columns.is_empty() {
+                    return Err(meta.error("an index requires at least one column"));
+                }
+
+                attributes
+                    .indexes
+                    .push((columns, meta.path.is_ident("unique_index")));
+                Ok(())
+            } else if meta.path.is_ident("belongs_to")
+                || meta.path.is_ident("has_many")
+                || meta.path.is_ident("many_to_many")
+            {
+                if relation.is_some() {
+                    return Err(meta.error("an attribute can only declare one relation"));
+                }
+
+                let kind = meta
+                    .path
+                    .get_ident()
+                    .map(|kind| kind.to_string())
+                    .unwrap_or_default();
+                let target: syn::LitStr = meta.value()?.parse()?;
+                relation = Some((kind, target.parse()?));
+                Ok(())
+            } else if meta.path.is_ident("foreign_key") {
+                let column: syn::LitStr = meta.value()?.parse()?;
+                foreign_key = Some(column.value());
+                Ok(())
+            } else if meta.path.is_ident("method") {
+                let name: syn::LitStr = meta.value()?.parse()?;
+                method = Some(name.value());
+                Ok(())
+            } else if meta.path.is_ident("through") {
+                let table: syn::LitStr = meta.value()?.parse()?;
+                through = Some(table.value());
+                Ok(())
+            } else if meta.path.is_ident("related_key") {
+                let column: syn::LitStr = meta.value()?.parse()?;
+                related_key = Some(column.value());
+                Ok(())
+            } else {
+                Err(meta.error("unknown njord attribute"))
+            }
+        })?;
+
+        let (kind, target) = match relation {
+            Some(relation) => relation,
+            None if foreign_key.is_some()
+                || method.is_some()
+                || through.is_some()
+                || related_key.is_some() =>
+            {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "foreign_key, related_key, through and method require a relation",
+                ))
+            }
+            None => continue,
+        };
+
+        let (kind, foreign_key) = match (kind.as_str(), through, foreign_key) {
+            ("many_to_many", Some(through), foreign_key) => {
+                // the join table is keyed by both tables, e.g. `post_id` and `tag_id`
+                let foreign_key = foreign_key
+                    .unwrap_or_else(|| format!("{}_id", to_snake_case(&ident.to_string())));
+                let related_key =
+                    related_key.unwrap_or_else(|| format!("{}_id", target_name(&target)));
+
+                (
+                    RelationKind::ManyToMany {
+                        through,
+                        related_key,
+                    },
+                    foreign_key,
+                )
+            }
+            ("many_to_many", None, _) => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "many_to_many requires a through table",
+                ))
+            }
+            (_, Some(_), _) => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "through requires many_to_many",
+                ))
+            }
+            (_, None, None) => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "belongs_to and has_many require a foreign_key",
+                ))
+            }
+            (_, None, Some(_)) if related_key.is_some() => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "related_key requires many_to_many",
+                ))
+            }
+            ("belongs_to", None, Some(foreign_key)) => (RelationKind::BelongsTo, foreign_key),
+            (_, None, Some(foreign_key)) => (RelationKind::HasMany, foreign_key),
+        };
+
+        attributes.relations.push(Relation {
+            kind,
+            target,
+            foreign_key,
+            method,
+        });
+    }
+
+    Ok(attributes)
+}
+
+/// The `#[njord(...)]` attributes of a field.
+#[derive(Default)]
+struct FieldAttributes {
+    primary_key: bool,
+    auto_increment: bool,
+    default: bool,
+    soft_delete: bool,
+    version: bool,
+    sensitive: bool,
+    created_at: bool,
+    updated_at: bool,
+    column: Option<String>,
+    skip: bool,
+    conversion: Option<Conversion>,
+    generate_uuid_v4: bool,
+    flatten: bool,
+    unique: bool,
+    default_value: Option<String>,
+    foreign_key: Option<String>,
+    index: bool,
+    unique_index: bool,
+    check: Option<String>,
+    references: Option<String>,
+}
+
+/// How a field is converted from and into its column, instead of through its
+/// `ToValue` and `FromValue` implementations.
+enum Conversion {
+    /// An enum stored as the name of its variant, set by `#[njord(enum = "text")]`.
+    EnumText,
+    /// An enum stored as its discriminant, set by `#[njord(enum = "integer")]`.
+    EnumInteger,
+    /// A date and time stored as a unix timestamp, set by `#[njord(datetime = "unix")]`.
+    UnixTimestamp,
+    /// A UUID stored as hyphenated TEXT, set by `#[njord(uuid = "text")]`.
+    UuidText,
+    /// A serde value stored as JSON TEXT, set by `#[njord(json)]`.
+    Json,
+    /// A decimal stored as TEXT, set by `#[njord(decimal)]`.
+    Decimal,
+    /// A decimal stored as an INTEGER scaled by 10 to the given power, set by `#[njord(decimal = 2)]`.
+    ScaledDecimal(u32),
+    /// A custom type implementing `ToColumn` and `FromColumn`, set by `#[njord(custom)]`.
+    Custom,
+}
+
+impl Conversion {
+    /// Get the type of the column.
+    fn column_type(&self, field_type: &syn::Type) -> TokenStream2 {
+        match self {
+            Conversion::EnumText
+            | Conversion::UuidText
+            | Conversion::Json
+            | Conversion::Decimal => quote! { "TEXT" },
+            Conversion::EnumInteger | Conversion::UnixTimestamp | Conversion::ScaledDecimal(_) => {
+                quote! { "INTEGER" }
+            }
+            Conversion::Custom => quote! {
+                <#field_type as njord::column::ToColumn>::column_type()
+            },
+        }
+    }
+
+    /// Get the expression converting the field into the value of its column.
+    fn value(&self, field_name: &Option<syn::Ident>, column_name: &str) -> TokenStream2 {
+        match self {
+            Conversion::EnumText => quote! {
+                njord::column::EnumValue::to_text_value(&self.#field_name)
+            },
+            Conversion::EnumInteger => quote! {
+                njord::column::EnumValue::to_integer_value(&self.#field_name)
+            },
+            Conversion::UnixTimestamp => quote! {
+                njord::column::UnixTimestamp::to_unix_value(&self.#field_name)
+            },
+            Conversion::UuidText => quote! {
+                njord::column::UuidColumn::to_text_value(&self.#field_name)
+            },
+            Conversion::Json => quote! {
+                njord::column::to_json_value(&self.#field_name).map_err(|error| njord::Error::Mapping {
+                    column: #column_name.to_string(),
+                    source: njord::value::FromSqlError::Other(Box::new(error)),
+                })?
+            },
+            Conversion::Decimal => quote! {
+                njord::column::DecimalColumn::to_text_value(&self.#field_name)
+            },
+            Conversion::Custom => quote! {
+                njord::column::ToColumn::to_column(&self.#field_name)
+            },
+            Conversion::ScaledDecimal(scale) => {
+                let message = format!(
+                    "the decimal does not fit into an INTEGER scaled by 10^{}",
+                    scale
+                );
+
+                quote! {
+                    njord::column::DecimalColumn::to_scaled_value(&self.#field_name, #scale).ok_or_else(|| njord::Error::Mapping {
+                        column: #column_name.to_string(),
+                        source: njord::value::FromSqlError::Other(#message.into()),
+                    })?
+                }
+            }
+        }
+    }
+
+    /// Get the expression converting the selected `value_ref` into the field.
+    fn reader(&self) -> TokenStream2 {
+        match self {
+            // enums accept both their variant name and their discriminant through `FromValue`
+            Conversion::EnumText | Conversion::EnumInteger => quote! {
+                njord::value::from_value(value_ref)
+            },
+            Conversion::UnixTimestamp => quote! {
+                njord::column::UnixTimestamp::from_unix_value(value_ref)
+            },
+            Conversion::UuidText => quote! {
+                njord::column::UuidColumn::from_text_value(value_ref)
+            },
+            Conversion::Json => quote! {
+                njord::column::from_json_value(value_ref)
+            },
+            Conversion::Decimal => quote! {
+                njord::column::DecimalColumn::from_text_value(value_ref)
+            },
+            Conversion::ScaledDecimal(scale) => quote! {
+                njord::column::DecimalColumn::from_scaled_value(value_ref, #scale)
+            },
+            Conversion::Custom => quote! {
+                njord::column::FromColumn::from_column(value_ref)
+            },
+        }
+    }
+}
+
+/// Parses the `#[njord(...)]` attributes of a field.
+fn parse_field_attributes(field: &syn::Field) -> syn::Result<FieldAttributes> {
+    let mut attributes = FieldAttributes::default();
+
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("njord"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("primary_key") {
+                attributes.primary_key = true;
+                Ok(())
+            } else if meta.path.is_ident("auto_increment") {
+                attributes.auto_increment = true;
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                if meta.input.peek(syn::Token![=]) {
+                    let generator: syn::LitStr = meta.value()?.parse()?;
+                    match generator.value().as_str() {
+                        "uuid_v4" => attributes.generate_uuid_v4 = true,
+                        _ => {
+                            return Err(syn::Error::new_spanned(generator, "expected \"uuid_v4\""))
+                        }
+                    }
+                } else {
+                    attributes.default = true;
+                }
+                Ok(())
+            } else if meta.path.is_ident("soft_delete") {
+                attributes.soft_delete = true;
+                Ok(())
+            } else if meta.path.is_ident("version") {
+                attributes.version = true;
+                Ok(())
+            } else if meta.path.is_ident("sensitive") {
+                attributes.sensitive = true;
+                Ok(())
+            } else if meta.path.is_ident("created_at") {
+                attributes.created_at = true;
+                Ok(())
+            } else if meta.path.is_ident("updated_at") {
+                attributes.updated_at = true;
+                Ok(())
+            } else if meta.path.is_ident("column") {
+                let column: syn::LitStr = meta.value()?.parse()?;
+                attributes.column = Some(column.value());
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                attributes.skip = true;
+                Ok(())
+            } else if meta.path.is_ident("enum") {
+                let repr: syn::LitStr = meta.value()?.parse()?;
+                let conversion = match repr.value().as_str() {
+                    "text" => Conversion::EnumText,
+                    "integer" => Conversion::EnumInteger,
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            repr,
+                            "expected \"text\" or \"integer\"",
+                        ))
+                    }
+                };
+                set_conversion(&mut attributes, conversion, &meta)
+            } else if meta.path.is_ident("datetime") {
+                let repr: syn::LitStr = meta.value()?.parse()?;
+                match repr.value().as_str() {
+                    "text" => Ok(()),
+                    "unix" => set_conversion(&mut attributes, Conversion::UnixTimestamp, &meta),
+                    _ => Err(syn::Error::new_spanned(
+                        repr,
+                        "expected \"text\" or \"unix\"",
+                    )),
+                }
+            } else if meta.path.is_ident("uuid") {
+                let repr: syn::LitStr = meta.value()?.parse()?;
+                match repr.value().as_str() {
+                    "blob" => Ok(()),
+                    "text" => set_conversion(&mut attributes, Conversion::UuidText, &meta),
+                    _ => Err(syn::Error::new_spanned(
+                        repr,
+                        "expected \"blob\" or \"text\"",
+                    )),
+                }
+            } else if meta.path.is_ident("json") {
+                set_conversion(&mut attributes, Conversion::Json, &meta)
+            } else if meta.path.is_ident("unique") {
+                attributes.unique = true;
+                Ok(())
+            } else if meta.path.is_ident("index") {
+                attributes.index = true;
+                Ok(())
+            } else if meta.path.is_ident("check") {
+                let check: syn::LitStr = meta.value()?.parse()?;
+                attributes.check = Some(check.value());
+                Ok(())
+            } else if meta.path.is_ident("references") {
+                let references: syn::LitStr = meta.value()?.parse()?;
+                attributes.references = Some(references.value());
+                Ok(())
+            } else if meta.path.is_ident("unique_index") {
+                attributes.unique_index = true;
+                Ok(())
+            } else if meta.path.is_ident("default_value") {
+                let default_value: syn::LitStr = meta.value()?.parse()?;
+                attributes.default_value = Some(default_value.value());
+                attributes.default = true;
+                Ok(())
+            } else if meta.path.is_ident("flatten") {
+                attributes.flatten = true;
+                Ok(())
+            } else if meta.path.is_ident("foreign_key") {
+                let column: syn::LitStr = meta.value()?.parse()?;
+                attributes.foreign_key = Some(column.value());
+                Ok(())
+            } else if meta.path.is_ident("custom") {
+                set_conversion(&mut attributes, Conversion::Custom, &meta)
+            } else if meta.path.is_ident("decimal") {
+                let conversion = if meta.input.peek(syn::Token![=]) {
+                    let scale: syn::LitInt = meta.value()?.parse()?;
+                    Conversion::ScaledDecimal(scale.base10_parse()?)
+                } else {
+                    Conversion::Decimal
+                };
+                set_conversion(&mut attributes, conversion, &meta)
+            } else {
+                Err(meta.error("unknown njord attribute"))
+            }
+        })?;
+    }
+
+    if attributes.auto_increment && !attributes.primary_key {
+        return Err(syn::Error::new_spanned(
+            field,
+            "auto_increment requires primary_key",
+        ));
+    }
+
+    let is_column = attributes.primary_key
+        || attributes.default
+        || attributes.soft_delete
+        || attributes.version
+        || attributes.created_at
+        || attributes.updated_at
+        || attributes.column.is_some()
+        || attributes.conversion.is_some()
+        || attributes.generate_uuid_v4
+        || attributes.unique
+        || attributes.index
+        || attributes.unique_index
+        || attributes.check.is_some()
+        || attributes.references.is_some();
+
+    if attributes.skip && is_column {
+        return Err(syn::Error::new_spanned(
+            field,
+            "skip cannot be combined with column attributes",
+        ));
+    }
+
+    if attributes.flatten && (is_column || attributes.skip) {
+        return Err(syn::Error::new_spanned(
+            field,
+            "flatten cannot be combined with other njord attributes",
+        ));
+    }
+
+    if attributes.foreign_key.is_some() && (is_column || attributes.skip || attributes.flatten) {
+        return Err(syn::Error::new_spanned(
+            field,
+            "foreign_key cannot be combined with other njord attributes",
+        ));
+    }
+
+    Ok(attributes)
+}
+
+/// Sets the conversion of a field, which can only have one.
+fn set_conversion(
+    attributes: &mut FieldAttributes,
+    conversion: Conversion,
+    meta: &syn::meta::ParseNestedMeta,
+) -> syn::Result<()> {
+    if attributes.conversion.is_some() {
+        return Err(meta.error("a field can only have one conversion attribute"));
+    }
+
+    attributes.conversion = Some(conversion);
+    Ok(())
+}
+
+/// Get the expression of an optional string, e.g. `Some("0".to_string())`.
+fn optional_string(value: &Option<String>) -> TokenStream2 {
+    match value {
+        Some(value) => quote! { Some(#value.to_string()) },
+        None => quote! { None },
+    }
+}
+
+/// Renders a type without its module paths, e.g. `Option<chrono::NaiveDate>` as `Option<NaiveDate>`,
+/// so the column type does not depend on how the field type is imported.
+///
+/// Arrays are rendered without their length, e.g. `[u8; 16]` as `[u8; N]`.
+fn type_name(ty: &syn::Type) -> String {
+    let segment = match ty {
+        syn::Type::Path(path) => path.path.segments.last(),
+        syn::Type::Array(array) => return format!("[{}; N]", type_name(&array.elem)),
+        _ => None,
+    };
+
+    let segment = match segment {
+        Some(segment) => segment,
+        None => return quote!(#ty).to_string(),
+    };
+
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) => {
+            let arguments = arguments
+                .args
+                .iter()
+                .map(|argument| match argument {
+                    syn::GenericArgument::Type(ty) => type_name(ty),
+                    argument => quote!(#argument).to_string(),
+                })
+                .collect::<Vec<String>>();
+
+            format!("{}<{}>", segment.ident, arguments.join(", "))
+        }
+        _ => segment.ident.to_string(),
+    }
+}
+
+/// Converts a CamelCase struct name into a snake_case identifier.
+fn to_snake_case(name: &str) -> String {
+    let mut snake_case = String::new();
+
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if index > 0 {
+                snake_case.push('_');
+            }
+            snake_case.extend(c.to_lowercase());
+        } else {
+            snake_case.push(c);
+        }
+    }
+
+    snake_case
+}
+
+/// Converts a snake_case field name into a CamelCase identifier.
+fn to_camel_case(name: &str) -> String {
+    name.trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+
+/// A migration read from the migrations directory, with its SQL files.
+#[derive(Default)]
+struct MigrationFiles {
+    name: String,
+    up: Option<PathBuf>,
+    down: Option<PathBuf>,
+}
+
+/// Generates the `Migrator` holding the migrations of a directory, relative to the
+/// manifest of the crate using the macro.
+pub(crate) fn embed_migrations(directory: &str, span: Span) -> syn::Result<TokenStream2> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let directory = Path::new(&manifest_dir).join(directory);
+
+    let entries = std::fs::read_dir(&directory).map_err(|error| {
+        syn::Error::new(
+            span,
+            format!(
+                "cannot read migrations directory {}: {}",
+                directory.display(),
+                error
+            ),
+        )
+    })?;
+
+    let mut migrations: BTreeMap<i64, MigrationFiles> = BTreeMap::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|error| syn::Error::new(span, error.to_string()))?
+            .path();
+        let file_name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(file_name) if file_name.ends_with(".sql") => file_name.to_string(),
+            _ => continue,
+        };
+
+        // e.g. `0001_create_users.up.sql`, `0001_create_users.down.sql` or `0001_create_users.sql`
+        let stem = file_name.trim_end_matches(".sql");
+        let (stem, is_down) = match stem.strip_suffix(".down") {
+            Some(stem) => (stem, true),
+            None => (stem.strip_suffix(".up").unwrap_or(stem), false),
+        };
+        let (version, name) = stem.split_once('_').unwrap_or((stem, ""));
+        let version: i64 = version.parse().map_err(|_| {
+            syn::Error::new(
+                span,
+                format!(
+                    "migration file {} does not start with a version, e.g. 0001_create_users.up.sql",
+                    file_name
+                ),
+            )
+        })?;
+
+        let migration = migrations.entry(version).or_default();
+        if !migration.name.is_empty() && migration.name != name {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "migrations {} and {} have the same version {}",
+                    migration.name, name, version
+                ),
+            ));
+        }
+        migration.name = name.to_string();
+
+        let step = match is_down {
+            true => &mut migration.down,
+            false => &mut migration.up,
+        };
+        if step.is_some() {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "migration {} has several files for the same step",
+                    file_name
+                ),
+            ));
+        }
+        *step = Some(path);
+    }
+
+    let mut steps = Vec::new();
+    for (version, migration) in migrations {
+        let name = &migration.name;
+        let up = match &migration.up {
+            Some(up) => up.display().to_string(),
+            None => {
+                return Err(syn::Error::new(
+                    span,
+                    format!("migration {} {} has no up file", version, name),
+                ))
+            }
+        };
+        let down = migration.down.as_ref().map(|down| {
+            let down = down.display().to_string();
+            quote! { .down(include_str!(#down)) }
+        });
+
+        steps.push(quote! {
+            .migration(njord::migrations::Migration::new(#version, #name, include_str!(#up))#down)
+        });
+    }
+
+    Ok(quote! {
+        njord::migrations::Migrator::new()
+            #(#steps)*
+    })
+}